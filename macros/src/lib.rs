@@ -0,0 +1,131 @@
+//! Procedural macros for the `src-ctx` crate.
+//!
+//! These are re-exported by `src-ctx` when the `embed` feature is enabled and should
+//! be used from there.
+
+use std::path::{Path, PathBuf};
+
+use proc_macro::TokenStream;
+
+
+/// Embed all files matching a glob pattern relative to the crate root.
+///
+/// The macro expands to a `fn(&mut SourceMap) -> Vec<Insert>` that inserts the
+/// contents of all matching files into a `SourceMap`, using their paths relative to
+/// the crate root as file origins. The contents are embedded with `include_str!`, so
+/// no file system access is required at runtime.
+///
+/// The pattern supports `*` and `?` wildcards in path components, and `**` components
+/// matching any number of directories. Files are inserted in the order of their paths.
+///
+/// Newly added files matching the pattern are only picked up when the invoking crate
+/// is rebuilt for other reasons.
+///
+/// # Examples
+///
+/// ```rust,ignore
+/// let load_std = src_ctx::embed_sources!("std/**/*.ext");
+/// let mut map = src_ctx::SourceMap::new();
+/// load_std(&mut map);
+/// ```
+#[proc_macro]
+pub fn embed_sources(input: TokenStream) -> TokenStream {
+    match expand_embed_sources(input) {
+        Ok(output) => output,
+        Err(message) => format!("compile_error!({message:?})").parse().unwrap(),
+    }
+}
+
+fn expand_embed_sources(input: TokenStream) -> Result<TokenStream, String> {
+    let pattern = parse_string_literal(input)?;
+    let root = std::env::var("CARGO_MANIFEST_DIR")
+        .map_err(|_| String::from("`CARGO_MANIFEST_DIR` is not set"))?;
+    let root = Path::new(&root);
+
+    let pattern: Vec<&str> = pattern.split('/').filter(|part| !part.is_empty()).collect();
+    let base_len = pattern.iter().take_while(|part| !is_wildcard(part)).count();
+    if base_len == pattern.len() {
+        return Err(String::from("the pattern must contain at least one wildcard"));
+    }
+    let base: PathBuf = pattern[..base_len].iter().collect();
+
+    let mut files = Vec::new();
+    for entry in walkdir::WalkDir::new(root.join(&base)).follow_links(true) {
+        let entry = entry.map_err(|error| format!("failed to search for sources: {error}"))?;
+        if !entry.file_type().is_file() {
+            continue;
+        }
+        let relative = entry.path().strip_prefix(root).unwrap();
+        let mut parts = Vec::new();
+        for component in relative.components() {
+            let Some(part) = component.as_os_str().to_str() else {
+                return Err(format!("non UTF-8 path `{}`", relative.display()));
+            };
+            parts.push(part);
+        }
+        if match_path(&pattern, &parts) {
+            files.push(parts.join("/"));
+        }
+    }
+    files.sort();
+
+    let inserts: String = files.iter().map(|file| format!(
+        "map.insert(\
+            ::src_ctx::Origin::from_file({file:?}), \
+            ::std::include_str!(::std::concat!(::std::env!(\"CARGO_MANIFEST_DIR\"), \"/\", {file:?})).into()\
+        ),"
+    )).collect();
+    let output = format!(
+        "{{\
+            fn embed_sources(map: &mut ::src_ctx::SourceMap) -> ::std::vec::Vec<::src_ctx::Insert> {{\
+                ::std::vec![{inserts}]\
+            }}\
+            embed_sources as fn(&mut ::src_ctx::SourceMap) -> ::std::vec::Vec<::src_ctx::Insert>\
+        }}"
+    );
+    output.parse().map_err(|error| format!("failed to generate output: {error}"))
+}
+
+fn parse_string_literal(input: TokenStream) -> Result<String, String> {
+    const EXPECTED: &str = "expected a single string literal";
+
+    let mut tokens = input.into_iter();
+    let (Some(proc_macro::TokenTree::Literal(literal)), None) = (tokens.next(), tokens.next()) else {
+        return Err(String::from(EXPECTED));
+    };
+    let literal = literal.to_string();
+    let Some(content) = literal.strip_prefix('"').and_then(|rest| rest.strip_suffix('"')) else {
+        return Err(String::from(EXPECTED));
+    };
+    if content.contains('\\') {
+        return Err(String::from("escape sequences are not supported in patterns"));
+    }
+    Ok(content.into())
+}
+
+fn is_wildcard(part: &str) -> bool {
+    part.contains(['*', '?'])
+}
+
+fn match_path(pattern: &[&str], parts: &[&str]) -> bool {
+    match (pattern, parts) {
+        ([], []) => true,
+        (["**", rest @ ..], _) => {
+            (0..=parts.len()).any(|skip| match_path(rest, &parts[skip..]))
+        },
+        ([pattern, pattern_rest @ ..], [part, parts_rest @ ..]) => {
+            match_part(pattern.as_bytes(), part.as_bytes()) && match_path(pattern_rest, parts_rest)
+        },
+        _ => false,
+    }
+}
+
+fn match_part(pattern: &[u8], part: &[u8]) -> bool {
+    match (pattern, part) {
+        ([], []) => true,
+        ([b'*', rest @ ..], _) => (0..=part.len()).any(|skip| match_part(rest, &part[skip..])),
+        ([b'?', pattern_rest @ ..], [_, part_rest @ ..]) => match_part(pattern_rest, part_rest),
+        ([p, pattern_rest @ ..], [c, part_rest @ ..]) => p == c && match_part(pattern_rest, part_rest),
+        _ => false,
+    }
+}
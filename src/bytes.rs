@@ -0,0 +1,282 @@
+use std::collections::HashMap;
+use std::path::Path;
+
+use crate::{
+    ContextErrorLocation, ContextErrorOrigin, Insert, LoadError, Offset, Origin, SourceError,
+    SourceIndex, Span, BYTE_ROW_LEN,
+};
+use crate::map::fetch_next_source_map_id;
+
+
+/// A map storing binary source contents and their [`Origin`].
+///
+/// This is the binary counterpart to [`SourceMap`](crate::SourceMap) for contents that
+/// are not valid UTF-8. The produced [`SourceIndex`], [`Offset`], [`Span`], and
+/// [`SourceError`] values are shared with textual sources.
+///
+/// Every map has its own internal ID to prevent use of a [`SourceIndex`]
+/// with a map it didn't originate from. This includes indices from a
+/// [`SourceMap`](crate::SourceMap).
+///
+/// # Panics
+///
+/// A panic will occur if the internal ID or the number of entries exceeds
+/// [`u32::MAX`].
+pub struct ByteSourceMap {
+    id: u32,
+    origin_indices: HashMap<Origin, u32>,
+    data: Vec<ByteSourceData>,
+}
+
+impl Default for ByteSourceMap {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ByteSourceMap {
+    /// Construct an empty [`ByteSourceMap`].
+    pub fn new() -> Self {
+        Self {
+            id: fetch_next_source_map_id(),
+            origin_indices: HashMap::new(),
+            data: Vec::new(),
+        }
+    }
+
+    /// Verify that an [`SourceIndex`] belongs to this map.
+    pub fn contains(&self, idx: SourceIndex) -> bool {
+        self.id == idx.map_id
+    }
+
+    /// Retrieve the [`Origin`] associated with a [`SourceIndex`].
+    ///
+    /// # Panics
+    ///
+    /// This function will panic if the index does not belong to this map.
+    #[track_caller]
+    pub fn origin(&self, idx: SourceIndex) -> &Origin {
+        assert_eq!(self.id, idx.map_id, "origin index must belong to source map");
+        &self.data[idx.data_index as usize].origin
+    }
+
+    /// Retrieve the content associated with a [`SourceIndex`].
+    ///
+    /// # Panics
+    ///
+    /// This function will panic if the index does not belong to this map.
+    #[track_caller]
+    pub fn content(&self, idx: SourceIndex) -> &[u8] {
+        assert_eq!(self.id, idx.map_id, "content index must belong to source map");
+        &self.data[idx.data_index as usize].content
+    }
+
+    /// Construct a [`ByteInput`] for the content associated with a [`SourceIndex`].
+    ///
+    /// # Panics
+    ///
+    /// This function will panic if the index does not belong to this map.
+    #[track_caller]
+    pub fn input(&self, idx: SourceIndex) -> ByteInput<'_> {
+        assert_eq!(self.id, idx.map_id, "input index must belong to source map");
+        ByteInput::new(idx, &self.data[idx.data_index as usize].content)
+    }
+
+    /// An iterator over all [`Origin`]s in this map.
+    pub fn origins(&self) -> impl Iterator<Item = &Origin> + '_ {
+        self.data.iter().map(|data| &data.origin)
+    }
+
+    /// Find the [`SourceIndex`] for a given [`Origin`] if there is one.
+    pub fn origin_index(&self, origin: &Origin) -> Option<SourceIndex> {
+        self.origin_indices.get(origin).map(|index| {
+            SourceIndex::new(self.id, *index)
+        })
+    }
+
+    /// Try to insert a new source entry into the map.
+    ///
+    /// Returns a [`Insert::Previous`] if an entry with the same origin already exists
+    /// in the map.
+    pub fn insert(&mut self, origin: Origin, content: Box<[u8]>) -> Insert {
+        if let Some(prev_index) = self.origin_indices.get(&origin).copied() {
+            return Insert::Previous(SourceIndex::new(self.id, prev_index));
+        }
+        let index: u32 = self.data.len().try_into().expect("maximum map size exceeded");
+        self.origin_indices.insert(origin.clone(), index);
+        self.data.push(ByteSourceData { origin, content });
+        Insert::Inserted(SourceIndex::new(self.id, index))
+    }
+
+    /// Try to load a file into the source map.
+    ///
+    /// Returns a [`Insert::Previous`] if a file with the same path already exists
+    /// in the map before attempting to load the path.
+    ///
+    /// # Errors
+    ///
+    /// An error will be returned if the file could not be read.
+    pub fn load_file<P>(&mut self, path: P) -> Result<Insert, LoadError>
+    where
+        P: AsRef<Path>,
+    {
+        let origin = Origin::from_file(path.as_ref());
+        if let Some(prev_index) = self.origin_index(&origin) {
+            return Ok(Insert::Previous(prev_index));
+        }
+        let content = std::fs::read(path.as_ref()).map_err(|error| LoadError::Read {
+            file: path.as_ref().into(),
+            error: error.into(),
+        })?;
+        Ok(self.insert(origin, content.into()))
+    }
+
+    /// Retrieve the bytes corresponding to a [`Span`] in the map.
+    ///
+    /// # Panics
+    ///
+    /// This function will panic if the given span does not belong to this map.
+    pub fn span_bytes(&self, span: Span) -> &[u8] {
+        let content = self.content(span.source_index());
+        &content[span.byte_range()]
+    }
+
+    fn context_error_location(&self, offset: Offset) -> ContextErrorLocation {
+        let content = self.content(offset.source_index());
+        let row_index = offset.byte() / BYTE_ROW_LEN;
+        let row_start = row_index * BYTE_ROW_LEN;
+        let row_end = content.len().min(row_start + BYTE_ROW_LEN);
+        ContextErrorLocation::new_bytes(
+            content[row_start.min(row_end)..row_end].into(),
+            row_index + 1,
+            1 + (offset.byte() - row_start),
+        )
+    }
+
+    /// Capture a [`ContextErrorOrigin`] for a given [`Offset`].
+    ///
+    /// The context will be displayed as a hex dump of the rows containing the offsets.
+    pub fn context_error_origin(
+        &self,
+        offset: Offset,
+        note: &'static str,
+        context: Option<Offset>,
+    ) -> ContextErrorOrigin {
+        let location = self.context_error_location(offset);
+        let context = context.map(|offset| self.context_error_location(offset));
+        ContextErrorOrigin::new(
+            self.origin(offset.source_index()).clone(),
+            note,
+            location,
+            context,
+        )
+    }
+}
+
+struct ByteSourceData {
+    origin: Origin,
+    content: Box<[u8]>,
+}
+
+/// An input traversal wrapper for contents in a [`ByteSourceMap`].
+///
+/// Inputs are constructed with [`ByteSourceMap::input`].
+#[derive(Debug, Clone)]
+pub struct ByteInput<'src> {
+    source_index: SourceIndex,
+    content: &'src [u8],
+    byte: usize,
+}
+
+impl<'src> ByteInput<'src> {
+    pub(crate) fn new(source_index: SourceIndex, content: &'src [u8]) -> Self {
+        Self { source_index, content, byte: 0 }
+    }
+
+    /// The byte-length of the remaining input content.
+    pub fn len(&self) -> usize {
+        self.content.len()
+    }
+
+    /// Determine if the input content has been fully consumed.
+    pub fn is_empty(&self) -> bool {
+        self.content.is_empty()
+    }
+
+    /// The content left for the input.
+    pub fn content(&self) -> &'src [u8] {
+        self.content
+    }
+
+    /// The [`Offset`] corresponding to the current input position.
+    pub fn offset(&self) -> Offset {
+        Offset::new(self.source_index, self.byte)
+    }
+
+    /// Skip to the end of the input.
+    #[must_use]
+    pub fn end(&self) -> Self {
+        self.skip(self.len())
+    }
+
+    /// Skip a number of bytes.
+    #[must_use]
+    pub fn skip(&self, byte_len: usize) -> Self {
+        Self {
+            source_index: self.source_index,
+            content: &self.content[byte_len..],
+            byte: self.byte + byte_len,
+        }
+    }
+
+    /// Truncate the input content to a specific byte length.
+    #[must_use]
+    pub fn truncate(&self, byte_len: usize) -> Self {
+        Self {
+            source_index: self.source_index,
+            content: &self.content[..byte_len],
+            byte: self.byte,
+        }
+    }
+
+    /// Split the input into two parts at a given byte position.
+    #[must_use]
+    pub fn split(&self, byte_len: usize) -> (Self, Self) {
+        (self.truncate(byte_len), self.skip(byte_len))
+    }
+
+    /// The next byte in the input.
+    #[must_use]
+    pub fn byte(&self) -> Option<u8> {
+        self.content.first().copied()
+    }
+
+    /// Try to skip a specific byte in the input.
+    #[must_use]
+    pub fn skip_byte(&self, byte: u8) -> Option<Self> {
+        (self.byte() == Some(byte)).then(|| self.skip(1))
+    }
+
+    /// Try to skip a specific sequence of bytes in the input.
+    #[must_use]
+    pub fn skip_bytes(&self, bytes: &[u8]) -> Option<Self> {
+        self.content.starts_with(bytes).then(|| self.skip(bytes.len()))
+    }
+
+    /// Try to consume any byte in the input.
+    #[must_use]
+    pub fn take_byte(&self) -> Option<(u8, Self)> {
+        self.byte().map(|byte| (byte, self.skip(1)))
+    }
+
+    /// Try to consume a fixed number of bytes in the input.
+    #[must_use]
+    pub fn take_bytes(&self, byte_len: usize) -> Option<(&'src [u8], Self)> {
+        (byte_len <= self.len()).then(|| (&self.content[..byte_len], self.skip(byte_len)))
+    }
+
+    /// Construct a [`SourceError`] for the current input position.
+    pub fn error<E>(&self, error: E, offset_note: &'static str) -> SourceError<E> {
+        SourceError::new(error, self.offset(), offset_note)
+    }
+}
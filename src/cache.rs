@@ -0,0 +1,268 @@
+use std::cmp::Reverse;
+use std::collections::BinaryHeap;
+use std::path::Path;
+use std::sync::{Arc, Mutex, MutexGuard, OnceLock, PoisonError};
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+
+use crate::Fingerprint;
+
+
+/// The bookkeeping of a [`SourceMap`](crate::SourceMap) for evictable contents.
+#[derive(Debug, Default)]
+pub(crate) struct ContentCache {
+    limit: Option<usize>,
+    #[cfg(feature = "lz4")]
+    compress: bool,
+    clock: AtomicU64,
+    bytes: AtomicUsize,
+    /// Entry positions of evictable contents by their last access, least recent first.
+    ///
+    /// Accesses since a position was queued are only checked when it is taken from the
+    /// queue, so a position can be queued multiple times.
+    queue: BinaryHeap<Reverse<(u64, usize)>>,
+    /// Positions reloaded through shared references, which are queued on the next trim.
+    reloaded: Mutex<Vec<(u64, usize)>>,
+}
+
+impl Clone for ContentCache {
+    fn clone(&self) -> Self {
+        Self {
+            limit: self.limit,
+            #[cfg(feature = "lz4")]
+            compress: self.compress,
+            clock: AtomicU64::new(self.clock.load(Ordering::Relaxed)),
+            bytes: AtomicUsize::new(self.bytes.load(Ordering::Relaxed)),
+            queue: self.queue.clone(),
+            reloaded: Mutex::new(self.lock_reloaded().clone()),
+        }
+    }
+}
+
+impl ContentCache {
+    pub(crate) fn limit(&self) -> Option<usize> {
+        self.limit
+    }
+
+    pub(crate) fn set_limit(&mut self, limit: Option<usize>) {
+        self.limit = limit;
+    }
+
+    #[cfg(feature = "lz4")]
+    pub(crate) fn set_compress(&mut self, enabled: bool) {
+        self.compress = enabled;
+    }
+
+    pub(crate) fn bytes(&self) -> usize {
+        self.bytes.load(Ordering::Relaxed)
+    }
+
+    /// Create the content of a new entry, which can be evicted if it is stored compressed,
+    /// or if it was loaded from a file while a limit is configured.
+    ///
+    /// The file path is made absolute, so reloads don't depend on the working directory.
+    pub(crate) fn content(&self, content: Arc<str>, file: Option<&Path>) -> SourceContent {
+        let reload = self.compressed_reload(&content).or_else(|| {
+            let path = std::path::absolute(file.filter(|_| self.limit.is_some())?).ok()?;
+            Some(Reload::File {
+                path: path.into(),
+                hash: Fingerprint::hash_content(content.as_bytes()),
+                line_breaks: None,
+            })
+        });
+        let mut last_access = 0;
+        if reload.is_some() {
+            self.bytes.fetch_add(content.len(), Ordering::Relaxed);
+            last_access = self.clock.fetch_add(1, Ordering::Relaxed);
+        }
+        SourceContent {
+            len: content.len(),
+            loaded: OnceLock::from(content),
+            reload,
+            last_access: AtomicU64::new(last_access),
+        }
+    }
+
+    #[cfg(feature = "lz4")]
+    fn compressed_reload(&self, content: &str) -> Option<Reload> {
+        self.compress.then(|| Reload::Compressed(
+            lz4_flex::compress_prepend_size(content.as_bytes()).into(),
+        ))
+    }
+
+    #[cfg(not(feature = "lz4"))]
+    fn compressed_reload(&self, _content: &str) -> Option<Reload> {
+        None
+    }
+
+    /// Whether a limit is configured and exceeded.
+    pub(crate) fn is_exceeded(&mut self) -> bool {
+        self.limit.is_some_and(|limit| *self.bytes.get_mut() > limit)
+    }
+
+    /// Whether an access is the most recent one of any evictable content.
+    pub(crate) fn is_most_recent(&mut self, access: u64) -> bool {
+        access + 1 == *self.clock.get_mut()
+    }
+
+    /// Queue the position of a content for eviction, if it is evictable.
+    pub(crate) fn queue(&mut self, position: usize, content: &SourceContent) {
+        if content.is_evictable() {
+            self.queue.push(Reverse((content.last_access(), position)));
+        }
+    }
+
+    /// Take the least recently used position from the queue, together with the access
+    /// it was queued with.
+    ///
+    /// Positions reloaded since the last call are queued first.
+    pub(crate) fn pop_least_recent(&mut self) -> Option<(u64, usize)> {
+        let reloaded = self.reloaded.get_mut().unwrap_or_else(PoisonError::into_inner);
+        self.queue.extend(reloaded.drain(..).map(Reverse));
+        self.queue.pop().map(|Reverse(entry)| entry)
+    }
+
+    /// Evict a content, accounting for the freed bytes.
+    pub(crate) fn evict(&mut self, content: &mut SourceContent) {
+        *self.bytes.get_mut() -= content.evict();
+    }
+
+    fn lock_reloaded(&self) -> MutexGuard<'_, Vec<(u64, usize)>> {
+        self.reloaded.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// A cache with the same configuration, but without accounted contents.
+    pub(crate) fn without_contents(&self) -> Self {
+        Self {
+            limit: self.limit,
+            #[cfg(feature = "lz4")]
+            compress: self.compress,
+            clock: AtomicU64::new(self.clock.load(Ordering::Relaxed)),
+            bytes: AtomicUsize::new(0),
+            queue: BinaryHeap::new(),
+            reloaded: Mutex::default(),
+        }
+    }
+
+    /// Account for the addition of an existing entry at a position.
+    pub(crate) fn adopt(&mut self, position: usize, content: &SourceContent) {
+        if content.is_evictable() {
+            *self.bytes.get_mut() += content.len;
+        }
+        self.queue(position, content);
+    }
+
+    /// Account for the removal of an entry.
+    pub(crate) fn remove(&mut self, content: &SourceContent) {
+        if content.is_evictable() {
+            *self.bytes.get_mut() -= content.len;
+        }
+    }
+}
+
+/// How an evicted content can be restored.
+///
+/// The line breaks of file contents are recorded on eviction, so a
+/// [placeholder](SourceContent::get) with the same layout can be provided if the file
+/// changed in the meantime.
+#[derive(Debug, Clone)]
+enum Reload {
+    File { path: Arc<Path>, hash: u64, line_breaks: Option<Arc<[u32]>> },
+    #[cfg(feature = "lz4")]
+    Compressed(Arc<[u8]>),
+}
+
+/// The content of a [`SourceMap`](crate::SourceMap) entry, which might be evicted and
+/// transparently reloaded from its file or compressed form on access.
+#[derive(Debug)]
+pub(crate) struct SourceContent {
+    loaded: OnceLock<Arc<str>>,
+    len: usize,
+    reload: Option<Reload>,
+    last_access: AtomicU64,
+}
+
+impl Clone for SourceContent {
+    fn clone(&self) -> Self {
+        Self {
+            loaded: self.loaded.clone(),
+            len: self.len,
+            reload: self.reload.clone(),
+            last_access: AtomicU64::new(self.last_access.load(Ordering::Relaxed)),
+        }
+    }
+}
+
+impl SourceContent {
+    pub(crate) fn len(&self) -> usize {
+        self.len
+    }
+
+    pub(crate) fn last_access(&self) -> u64 {
+        self.last_access.load(Ordering::Relaxed)
+    }
+
+    pub(crate) fn is_evictable(&self) -> bool {
+        self.reload.is_some() && self.loaded.get().is_some()
+    }
+
+    /// Drop the content, returning the number of freed bytes.
+    fn evict(&mut self) -> usize {
+        let Some(content) = self.loaded.take() else {
+            return 0;
+        };
+        if let Some(Reload::File { line_breaks: line_breaks @ None, .. }) = &mut self.reload {
+            *line_breaks = Some(content.match_indices('\n').map(|(i, _)| i as u32).collect());
+        }
+        content.len()
+    }
+
+    /// Retrieve the content, reloading it from its file or compressed form if it was
+    /// evicted.
+    ///
+    /// If the file no longer holds the same content, a placeholder of spaces with the
+    /// same length and line breaks is used instead, so all positions stay valid. The
+    /// file is tried again after the placeholder was evicted.
+    ///
+    /// Reloaded contents are queued for eviction under the given entry position.
+    pub(crate) fn get(&self, cache: &ContentCache, position: usize) -> &str {
+        if let Some(content) = self.loaded.get() {
+            if self.reload.is_some() {
+                let access = cache.clock.fetch_add(1, Ordering::Relaxed);
+                self.last_access.store(access, Ordering::Relaxed);
+            }
+            return content;
+        }
+        let reload = self.reload.as_ref().expect("only reloadable contents are evicted");
+        let access = cache.clock.fetch_add(1, Ordering::Relaxed);
+        self.last_access.store(access, Ordering::Relaxed);
+        let content: Arc<str> = match reload {
+            Reload::File { path, hash, line_breaks } => std::fs::read(path).ok()
+                .filter(|content| {
+                    content.len() == self.len && Fingerprint::hash_content(content) == *hash
+                })
+                .and_then(|content| String::from_utf8(content).ok())
+                .unwrap_or_else(|| placeholder(self.len, line_breaks.as_deref().unwrap_or(&[])))
+                .into(),
+            #[cfg(feature = "lz4")]
+            Reload::Compressed(compressed) => {
+                let content = lz4_flex::decompress_size_prepended(compressed)
+                    .expect("compressed content must be valid");
+                String::from_utf8(content).expect("compressed content must be UTF-8").into()
+            },
+        };
+        if self.loaded.set(content).is_ok() {
+            cache.bytes.fetch_add(self.len, Ordering::Relaxed);
+            cache.lock_reloaded().push((access, position));
+        }
+        self.loaded.get().expect("content was just loaded")
+    }
+}
+
+/// Construct a content of spaces with line breaks at the given positions.
+fn placeholder(len: usize, line_breaks: &[u32]) -> String {
+    let mut content = vec![b' '; len];
+    for &position in line_breaks {
+        content[position as usize] = b'\n';
+    }
+    String::from_utf8(content).expect("placeholder is ASCII")
+}
@@ -0,0 +1,255 @@
+use crate::{Offset, SourceIndex, SourceMap, Span};
+
+
+/// The units in which the characters of a [`LinePosition`] are counted.
+///
+/// These correspond to the position encodings negotiated by the Language Server
+/// Protocol.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
+pub enum PositionEncoding {
+    /// Characters are counted in UTF-8 code units, or bytes.
+    Utf8,
+    /// Characters are counted in UTF-16 code units. This is the default encoding
+    /// of the Language Server Protocol.
+    #[default]
+    Utf16,
+    /// Characters are counted in Unicode scalar values.
+    Utf32,
+}
+
+impl PositionEncoding {
+    fn char_len(self, c: char) -> usize {
+        match self {
+            Self::Utf8 => c.len_utf8(),
+            Self::Utf16 => c.len_utf16(),
+            Self::Utf32 => 1,
+        }
+    }
+}
+
+/// A zero-based line and character position, as used by the Language Server Protocol.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
+pub struct LinePosition {
+    /// The zero-based line number.
+    pub line: usize,
+    /// The zero-based character on the line, counted in the units of a
+    /// [`PositionEncoding`].
+    pub character: usize,
+}
+
+/// A range between two [`LinePosition`] values.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
+pub struct LineRange {
+    /// The position at the start of the range.
+    pub start: LinePosition,
+    /// The position at the end of the range.
+    pub end: LinePosition,
+}
+
+impl SourceMap {
+    /// Convert an [`Offset`] into a [`LinePosition`] with the given [`PositionEncoding`].
+    ///
+    /// For [preprocessed](Self::with_preprocessor) entries, the position is in the
+    /// [original content](Self::original_content), like the positions in diagnostics.
+    ///
+    /// # Panics
+    ///
+    /// This function will panic if the offset does not belong to this map.
+    #[track_caller]
+    pub fn line_position(&self, offset: Offset, encoding: PositionEncoding) -> LinePosition {
+        let idx = offset.source_index();
+        let (content, line_starts) = (self.original_content(idx), self.original_line_starts(idx));
+        byte_line_position(content, line_starts, self.original_byte(offset), encoding)
+    }
+
+    /// Convert a [`Span`] into a [`LineRange`] with the given [`PositionEncoding`].
+    ///
+    /// # Panics
+    ///
+    /// This function will panic if the span does not belong to this map.
+    #[track_caller]
+    pub fn line_range(&self, span: Span, encoding: PositionEncoding) -> LineRange {
+        LineRange {
+            start: self.line_position(span.start(), encoding),
+            end: self.line_position(span.end(), encoding),
+        }
+    }
+
+    /// Convert a [`LinePosition`] with the given [`PositionEncoding`] into an [`Offset`]
+    /// in a map entry.
+    ///
+    /// Characters beyond the end of the line resolve to the end of the line, excluding
+    /// the line terminator. Characters inside of a multi-unit character resolve to the
+    /// start of that character. Returns `None` if the line does not exist.
+    ///
+    /// For [preprocessed](Self::with_preprocessor) entries, the position is in the
+    /// [original content](Self::original_content). Positions in removed parts of the
+    /// original content resolve to the start of the next kept part.
+    ///
+    /// # Panics
+    ///
+    /// This function will panic if the index does not belong to this map.
+    #[track_caller]
+    pub fn line_position_offset(
+        &self,
+        index: SourceIndex,
+        position: LinePosition,
+        encoding: PositionEncoding,
+    ) -> Option<Offset> {
+        let content = self.original_content(index);
+        let line_starts = self.original_line_starts(index);
+        let byte = line_position_byte(content, line_starts, position, encoding)?;
+        Some(self.preprocessed_offset(index, byte))
+    }
+
+    /// Convert a [`LinePosition`] in the content of a map entry into an [`Offset`], even
+    /// for preprocessed entries.
+    #[cfg(any(feature = "serde_json", feature = "tree-sitter"))]
+    #[track_caller]
+    pub(crate) fn content_line_position_offset(
+        &self,
+        index: SourceIndex,
+        position: LinePosition,
+        encoding: PositionEncoding,
+    ) -> Option<Offset> {
+        let content = self.content(index);
+        let byte = line_position_byte(content, self.line_starts(index), position, encoding)?;
+        Some(Offset::new(index, byte))
+    }
+
+    /// Convert a [`LineRange`] with the given [`PositionEncoding`] into a [`Span`] in a
+    /// map entry.
+    ///
+    /// The positions are resolved as with
+    /// [`line_position_offset`](Self::line_position_offset). Returns `None` if either
+    /// line does not exist.
+    ///
+    /// # Panics
+    ///
+    /// This function will panic if the index does not belong to this map.
+    #[track_caller]
+    pub fn line_range_span(
+        &self,
+        index: SourceIndex,
+        range: LineRange,
+        encoding: PositionEncoding,
+    ) -> Option<Span> {
+        let start = self.line_position_offset(index, range.start, encoding)?;
+        let end = self.line_position_offset(index, range.end, encoding)?;
+        Some(start.span(end))
+    }
+}
+
+/// The [`LinePosition`] of a byte-position in a content.
+fn byte_line_position(
+    content: &str,
+    line_starts: &[usize],
+    byte: usize,
+    encoding: PositionEncoding,
+) -> LinePosition {
+    let line = line_starts.partition_point(|&start| start <= byte) - 1;
+    let character = content[line_starts[line]..byte]
+        .chars()
+        .map(|c| encoding.char_len(c))
+        .sum();
+    LinePosition { line, character }
+}
+
+/// The byte-position of a [`LinePosition`] in a content, if the line exists.
+fn line_position_byte(
+    content: &str,
+    line_starts: &[usize],
+    position: LinePosition,
+    encoding: PositionEncoding,
+) -> Option<usize> {
+    let start = *line_starts.get(position.line)?;
+    let line = content[start..].split('\n').next().unwrap_or_default();
+    let line = line.strip_suffix('\r').unwrap_or(line);
+    let mut units = 0;
+    let mut byte = line.len();
+    for (char_byte, c) in line.char_indices() {
+        units += encoding.char_len(c);
+        if units > position.character {
+            byte = char_byte;
+            break;
+        }
+    }
+    Some(start + byte)
+}
+
+#[cfg(feature = "tree-sitter")]
+impl SourceMap {
+    /// Convert an [`Offset`] into a [`tree_sitter::Point`].
+    ///
+    /// Unlike [`line_position`](Self::line_position), points are in the content the
+    /// offset refers to, which is what gets parsed for
+    /// [preprocessed](Self::with_preprocessor) entries.
+    ///
+    /// # Panics
+    ///
+    /// This function will panic if the offset does not belong to this map.
+    #[track_caller]
+    pub fn tree_sitter_point(&self, offset: Offset) -> tree_sitter::Point {
+        let idx = offset.source_index();
+        let (content, line_starts) = (self.content(idx), self.line_starts(idx));
+        let encoding = PositionEncoding::Utf8;
+        let position = byte_line_position(content, line_starts, offset.byte(), encoding);
+        tree_sitter::Point { row: position.line, column: position.character }
+    }
+
+    /// Convert a [`Span`] into a [`tree_sitter::Range`].
+    ///
+    /// # Panics
+    ///
+    /// This function will panic if the span does not belong to this map.
+    #[track_caller]
+    pub fn tree_sitter_range(&self, span: Span) -> tree_sitter::Range {
+        tree_sitter::Range {
+            start_byte: span.start().byte(),
+            end_byte: span.end().byte(),
+            start_point: self.tree_sitter_point(span.start()),
+            end_point: self.tree_sitter_point(span.end()),
+        }
+    }
+
+    /// Convert a [`tree_sitter::Point`] into an [`Offset`] in a map entry.
+    ///
+    /// Points are resolved as with [`line_position_offset`](Self::line_position_offset)
+    /// using byte columns, but in the content of the entry. Returns `None` if the row
+    /// does not exist.
+    ///
+    /// # Panics
+    ///
+    /// This function will panic if the index does not belong to this map.
+    #[track_caller]
+    pub fn tree_sitter_point_offset(
+        &self,
+        index: SourceIndex,
+        point: tree_sitter::Point,
+    ) -> Option<Offset> {
+        let position = LinePosition { line: point.row, character: point.column };
+        self.content_line_position_offset(index, position, PositionEncoding::Utf8)
+    }
+
+    /// Convert a [`tree_sitter::Range`] into a [`Span`] in a map entry.
+    ///
+    /// The byte positions of the range are used. Returns `None` if they exceed the
+    /// content or don't lie on character boundaries.
+    ///
+    /// # Panics
+    ///
+    /// This function will panic if the index does not belong to this map.
+    #[track_caller]
+    pub fn tree_sitter_range_span(
+        &self,
+        index: SourceIndex,
+        range: tree_sitter::Range,
+    ) -> Option<Span> {
+        let content = self.content(index);
+        if range.start_byte > range.end_byte || content.get(range.start_byte..range.end_byte).is_none() {
+            return None;
+        }
+        let input = self.input(index);
+        Some(input.skip(range.start_byte).offset().span(input.skip(range.end_byte).offset()))
+    }
+}
@@ -30,4 +30,70 @@ pub fn count_digits(mut n: usize) -> usize {
         }
         digits
     }
-}
\ No newline at end of file
+}
+
+/// Split text into lines of at most `width` characters at whitespace.
+///
+/// Existing line breaks are kept. Words longer than the width are not split.
+pub(crate) fn wrap_text(text: &str, width: usize) -> Vec<String> {
+    let mut lines = Vec::new();
+    for text_line in text.split('\n') {
+        let mut line = String::new();
+        let mut line_width = 0;
+        for word in text_line.split_whitespace() {
+            let word_width = word.chars().count();
+            if line_width > 0 && line_width + 1 + word_width > width {
+                lines.push(std::mem::take(&mut line));
+                line_width = 0;
+            }
+            if line_width > 0 {
+                line.push(' ');
+                line_width += 1;
+            }
+            line.push_str(word);
+            line_width += word_width;
+        }
+        lines.push(line);
+    }
+    lines
+}
+
+/// The styles used for colored output.
+#[derive(Debug, Clone, Copy)]
+pub(crate) enum Style {
+    Error,
+    Cause,
+    Gutter,
+    Primary,
+    Secondary,
+    Added,
+    Unnecessary,
+    Deprecated,
+}
+
+impl Style {
+    fn ansi_code(self) -> &'static str {
+        match self {
+            Self::Error | Self::Primary => "1;31",
+            Self::Cause => "1",
+            Self::Gutter | Self::Secondary => "1;34",
+            Self::Added => "32",
+            Self::Unnecessary => "2",
+            Self::Deprecated => "9",
+        }
+    }
+}
+
+/// Display a value wrapped in ANSI escape codes for the style if enabled.
+pub(crate) fn paint<'a, T>(enabled: bool, style: Style, value: T) -> impl fmt::Display + 'a
+where
+    T: fmt::Display + 'a,
+{
+    display_fn(move |f| {
+        if enabled {
+            write!(f, "\x1b[{}m{}\x1b[0m", style.ansi_code(), value)
+        } else {
+            write!(f, "{}", value)
+        }
+    })
+}
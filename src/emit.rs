@@ -0,0 +1,220 @@
+use std::fmt::{self, Write};
+use std::path::Path;
+
+use crate::{ContextError, DisplayOptions, SourceIndex, SourceProvider};
+use crate::display::{display_fn, Style};
+use crate::error::display_origin_name;
+
+
+/// Construct a [`Display`](std::fmt::Display) proxy producing checkstyle XML for a batch
+/// of [`ContextError`] values.
+///
+/// Each error is reported as an `<error>` element with `error` severity in the `<file>`
+/// element of its first visible origin. Files are listed in the order they are first
+/// encountered. Errors without a visible origin are listed under a file with an empty
+/// name. Positions in binary content are reported without line or column.
+pub fn display_checkstyle<'a, E>(
+    errors: &'a [ContextError<E>],
+    options: &'a DisplayOptions,
+) -> impl fmt::Display + 'a
+where
+    E: fmt::Display,
+{
+    display_fn(move |f| {
+        let mut files: Vec<(String, Vec<&ContextError<E>>)> = Vec::new();
+        for error in errors {
+            let name = match error.visible_origins(options).next() {
+                Some(origin) => origin.display_name(options).to_string(),
+                None => String::new(),
+            };
+            match files.iter_mut().find(|(file_name, _)| *file_name == name) {
+                Some((_, file_errors)) => file_errors.push(error),
+                None => files.push((name, vec![error])),
+            }
+        }
+        writeln!(f, r#"<?xml version="1.0" encoding="UTF-8"?>"#)?;
+        writeln!(f, r#"<checkstyle version="4.3">"#)?;
+        for (name, file_errors) in &files {
+            writeln!(f, r#"  <file name="{}">"#, xml_escaped(name))?;
+            for error in file_errors {
+                f.write_str("    <error")?;
+                let position = error.visible_origins(options).next()
+                    .and_then(|origin| origin.line_and_column());
+                if let Some((line, column)) = position {
+                    let column = options.reported_column(column);
+                    write!(f, r#" line="{line}" column="{column}""#)?;
+                }
+                let message = error.error().to_string();
+                writeln!(f, r#" severity="error" message="{}"/>"#, xml_escaped(&message))?;
+            }
+            writeln!(f, "  </file>")?;
+        }
+        writeln!(f, "</checkstyle>")
+    })
+}
+
+/// Construct a [`Display`](std::fmt::Display) proxy producing one line per
+/// [`ContextError`] in a batch, without any source context.
+///
+/// Each line has the form `path:2:3: error: ...`, the same as with
+/// [`Verbosity::Short`](crate::Verbosity::Short), which is understood by most editor
+/// and compiler output parsers.
+pub fn display_short<'a, E>(
+    errors: &'a [ContextError<E>],
+    options: &'a DisplayOptions,
+) -> impl fmt::Display + 'a
+where
+    E: fmt::Display,
+{
+    display_fn(move |f| {
+        for error in errors {
+            error.fmt_short(f, options)?;
+        }
+        Ok(())
+    })
+}
+
+/// Construct a [`Display`](std::fmt::Display) proxy producing Vim quickfix lines for a
+/// batch of [`ContextError`] values.
+///
+/// Each line has the form `file:2:3: error: ...` for the first visible origin of an
+/// error, which is matched by the default `errorformat` of Vim. Unlike with
+/// [`display_short`], named and generated origins are listed with their plain name
+/// in the same form. Positions in binary content are listed as `file: error: ...`,
+/// and errors without a visible origin as `error: ...`. Line breaks in messages are
+/// replaced by spaces to keep one line per error.
+pub fn display_quickfix<'a, E>(
+    errors: &'a [ContextError<E>],
+    options: &'a DisplayOptions,
+) -> impl fmt::Display + 'a
+where
+    E: fmt::Display,
+{
+    display_fn(move |f| {
+        for error in errors {
+            if let Some(origin) = error.visible_origins(options).next() {
+                write!(f, "{}", origin.display_name(options))?;
+                if let Some((line, column)) = origin.line_and_column() {
+                    write!(f, ":{line}:{}", options.reported_column(column))?;
+                }
+                f.write_str(": ")?;
+            }
+            let message = error.error().to_string();
+            writeln!(f, "error: {}", message.replace(['\r', '\n'], " "))?;
+        }
+        Ok(())
+    })
+}
+
+/// Write a quickfix file for a batch of [`ContextError`] values, as produced by
+/// [`display_quickfix`].
+///
+/// The file can be loaded in Vim with `:cfile`.
+///
+/// # Errors
+///
+/// An error will be returned if the file could not be written.
+pub fn write_quickfix_file<P, E>(
+    path: P,
+    errors: &[ContextError<E>],
+    options: &DisplayOptions,
+) -> std::io::Result<()>
+where
+    P: AsRef<Path>,
+    E: fmt::Display,
+{
+    std::fs::write(path, display_quickfix(errors, options).to_string())
+}
+
+/// Construct a [`Display`](std::fmt::Display) proxy showing the entire content of a
+/// [`SourceMap`](crate::SourceMap) entry, or a source of another [`SourceProvider`], with the given
+/// [`ContextError`] values interleaved at their lines.
+///
+/// The [original content](SourceProvider::original_content) of the source is shown, so
+/// the line numbers match those of diagnostics. All visible origins of the errors that
+/// belong to the entry are shown below their line with their note, followed by the error
+/// message. Origins in other sources and in binary content are ignored. Errors on the
+/// same line are shown in column order.
+///
+/// # Panics
+///
+/// The proxy will panic on display if the index does not belong to the map.
+pub fn display_annotated_source<'a, P, E>(
+    map: &'a P,
+    index: SourceIndex,
+    errors: &'a [ContextError<E>],
+    options: &'a DisplayOptions,
+) -> impl fmt::Display + 'a
+where
+    P: SourceProvider + ?Sized,
+    E: fmt::Display,
+{
+    display_fn(move |f| {
+        let origin = map.origin(index);
+        let mut labels = Vec::new();
+        for error in errors {
+            for error_origin in error.visible_origins(options) {
+                if error_origin.origin() != origin {
+                    continue;
+                }
+                if let Some((line, column)) = error_origin.line_and_column() {
+                    labels.push((line, column, error_origin, error));
+                }
+            }
+        }
+        labels.sort_by_key(|&(line, column, ..)| (line, column));
+
+        let content = map.original_content(index);
+        let mut lines = content.split('\n').collect::<Vec<_>>();
+        let last_label_line = labels.last().map_or(1, |&(line, ..)| line);
+        if content.ends_with('\n') && lines.len() > last_label_line {
+            lines.pop();
+        }
+        let lnum_width = options.line_number_width(lines.len());
+        writeln!(f, "{} {}", options.paint(Style::Gutter, "-->"), display_origin_name(origin, options))?;
+        let mut labels = labels.into_iter().peekable();
+        for (line_index, line) in lines.into_iter().enumerate() {
+            let line_number = line_index + 1;
+            let line = line.strip_suffix('\r').unwrap_or(line);
+            let gutter = format_args!(" {line_number:lnum_width$} |");
+            writeln!(f, "{} {line}", options.paint(Style::Gutter, gutter))?;
+            while let Some((_, column, error_origin, error)) = labels.next_if(|l| l.0 == line_number) {
+                write!(f, "{} ", options.paint(Style::Gutter, format_args!(" {:lnum_width$} |", "")))?;
+                let (skipped, rest) = line.split_at((column - 1).min(line.len()));
+                for c in skipped.chars() {
+                    f.write_char(match c { '\t' => '\t', _ => ' '})?;
+                }
+                let byte_len = error_origin.highlight_len();
+                let highlighted = rest.char_indices().take_while(|(index, _)| *index < byte_len).count();
+                let markers: String = std::iter::repeat_n('^', highlighted.max(1)).collect();
+                let note = error_origin.note();
+                writeln!(f, "{}", options.paint(Style::Primary, format_args!("{markers} {note}")))?;
+                writeln!(
+                    f,
+                    "{} {}: {}",
+                    options.paint(Style::Gutter, format_args!(" {:lnum_width$} =", "")),
+                    options.paint(Style::Error, options.strings().error),
+                    error.error(),
+                )?;
+            }
+        }
+        Ok(())
+    })
+}
+
+fn xml_escaped(value: &str) -> impl fmt::Display + '_ {
+    display_fn(move |f| {
+        for c in value.chars() {
+            match c {
+                '&' => f.write_str("&amp;")?,
+                '<' => f.write_str("&lt;")?,
+                '>' => f.write_str("&gt;")?,
+                '"' => f.write_str("&quot;")?,
+                '\'' => f.write_str("&apos;")?,
+                '\n' => f.write_str("&#10;")?,
+                _ => f.write_char(c)?,
+            }
+        }
+        Ok(())
+    })
+}
@@ -1,8 +1,12 @@
+use std::borrow::Cow;
 use std::fmt::{self, Write};
 use std::sync::Arc;
 
-use crate::{Origin, Offset, SourceMap};
-use crate::display::{display_fn, count_digits};
+use crate::{
+    Origin, Offset, Span, SourceCategory, ByteSourceMap, DisplayOptions, CategoryDisplay,
+    Verbosity, SourceProvider,
+};
+use crate::display::{display_fn, paint, wrap_text, Style};
 
 
 /// A generic error with associated context information.
@@ -16,6 +20,8 @@ use crate::display::{display_fn, count_digits};
 /// print the inner error display followed by in-line source location information.
 ///
 /// Use [`display_with_context`](Self::display_with_context) for the full output.
+///
+/// [`SourceMap`]: crate::SourceMap
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct ContextError<E> {
     error: E,
@@ -36,7 +42,8 @@ where
     E: fmt::Display,
 {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(f, "{}{}", self.error, self.display_origins_as_suffix())
+        write!(f, "{}", self.error)?;
+        self.fmt_origins_as_suffix(f, &DisplayOptions::default())
     }
 }
 
@@ -49,6 +56,14 @@ impl<E> ContextError<E> {
         Self { error, origins: origins.into_iter().collect() }
     }
 
+    /// Construct a context error concerning an [`Origin`] as a whole, without a position.
+    ///
+    /// This is a shorthand for [`ContextError::with_origins`] with an origin created by
+    /// [`ContextErrorOrigin::without_position`].
+    pub fn without_position(error: E, origin: Origin, note: &'static str) -> Self {
+        Self::with_origins(error, [ContextErrorOrigin::without_position(origin, note)])
+    }
+
     /// The encapsulated error value.
     pub fn error(&self) -> &E {
         &self.error
@@ -59,6 +74,40 @@ impl<E> ContextError<E> {
         &self.origins
     }
 
+    /// Add a [`ContextErrorOrigin`] after all existing origins.
+    ///
+    /// This allows attaching related locations that only become known after the error
+    /// was constructed.
+    pub fn push_origin(&mut self, origin: ContextErrorOrigin) {
+        self.extend_origins([origin]);
+    }
+
+    /// Add multiple [`ContextErrorOrigin`] values after all existing origins.
+    pub fn extend_origins<I>(&mut self, origins: I)
+    where
+        I: IntoIterator<Item = ContextErrorOrigin>,
+    {
+        let mut extended = self.origins.to_vec();
+        extended.extend(origins);
+        self.origins = extended.into();
+    }
+
+    /// Sort the [`ContextErrorOrigin`] values by their [`Origin`], then by their line and
+    /// column.
+    ///
+    /// The sort is stable. Note that the first origin is the one used for
+    /// single-line output, as with [`Verbosity::Short`].
+    pub fn sort_origins(&mut self) {
+        let mut sorted = self.origins.to_vec();
+        sorted.sort_by(|a, b| {
+            let key = |origin: &ContextErrorOrigin| {
+                (origin.location.line_number, origin.location.column_number)
+            };
+            a.origin.cmp(&b.origin).then_with(|| key(a).cmp(&key(b)))
+        });
+        self.origins = sorted.into();
+    }
+
     /// Map the encapsulated error value to a new value and/or type.
     pub fn map<M, F>(self, map_error: F) -> ContextError<M>
     where
@@ -89,18 +138,90 @@ impl<E> ContextError<E> {
     where
         E: fmt::Display + std::error::Error,
     {
-        display_fn(move |f| {
-            writeln!(f, "error: {}", self.error)?;
-            let mut curr: &dyn std::error::Error = &self.error;
-            while let Some(source) = curr.source() {
-                curr = source;
-                writeln!(f, "cause: {}", curr)?;
-            }
-            for origin in self.origins.iter() {
-                write!(f, "{origin}")?;
+        display_fn(move |f| self.fmt_with_context(f, &DisplayOptions::default(), self.error.source()))
+    }
+
+    /// Construct a [`Display`](std::fmt::Display) proxy showing a full context using
+    /// the given [`DisplayOptions`].
+    ///
+    /// This is the same as [`display_with_context`](Self::display_with_context) with
+    /// customizable output. The amount of detail is controlled by the
+    /// [`Verbosity`] of the options.
+    pub fn display_with_options<'a>(&'a self, options: &'a DisplayOptions) -> impl fmt::Display + 'a
+    where
+        E: fmt::Display + std::error::Error,
+    {
+        display_fn(move |f| self.fmt_with_context(f, options, self.error.source()))
+    }
+
+    /// Render the full context using the given [`DisplayOptions`] into a [`String`].
+    ///
+    /// This is the same as formatting the output of
+    /// [`display_with_options`](Self::display_with_options).
+    pub fn render_string(&self, options: &DisplayOptions) -> String
+    where
+        E: fmt::Display + std::error::Error,
+    {
+        self.display_with_options(options).to_string()
+    }
+
+    /// Render the full contexts of all given errors using the given [`DisplayOptions`]
+    /// into a single [`String`].
+    ///
+    /// The outputs of the errors are separated by empty lines.
+    pub fn render_batch_string(errors: &[Self], options: &DisplayOptions) -> String
+    where
+        E: fmt::Display + std::error::Error,
+    {
+        let mut rendered = String::new();
+        for (index, error) in errors.iter().enumerate() {
+            if index > 0 {
+                rendered.push('\n');
             }
-            Ok(())
-        })
+            write!(rendered, "{}", error.display_with_options(options)).unwrap();
+        }
+        rendered
+    }
+
+    /// Render the full contexts of all given errors in parallel using the given
+    /// [`DisplayOptions`], producing one [`String`] per error in the original order.
+    ///
+    /// The rendering is distributed across the global [`rayon`] thread pool.
+    #[cfg(feature = "rayon")]
+    pub fn render_each_parallel(errors: &[Self], options: &DisplayOptions) -> Vec<String>
+    where
+        E: fmt::Display + std::error::Error + Sync,
+    {
+        use rayon::prelude::*;
+
+        errors.par_iter().map(|error| error.render_string(options)).collect()
+    }
+
+    /// Render the full contexts of all given errors in parallel into a single [`String`].
+    ///
+    /// The output is the same as with [`render_batch_string`](Self::render_batch_string).
+    #[cfg(feature = "rayon")]
+    pub fn render_batch_string_parallel(errors: &[Self], options: &DisplayOptions) -> String
+    where
+        E: fmt::Display + std::error::Error + Sync,
+    {
+        Self::render_each_parallel(errors, options).join("\n")
+    }
+
+    /// Emit the error to the [`log`] crate.
+    ///
+    /// The in-line form of the error is logged at the given level. For levels more
+    /// severe than [`Debug`](log::Level::Debug), the full context is additionally logged
+    /// at the `Debug` level.
+    #[cfg(feature = "log")]
+    pub fn log(&self, level: log::Level)
+    where
+        E: fmt::Display + std::error::Error,
+    {
+        log::log!(level, "{}", self);
+        if level < log::Level::Debug {
+            log::debug!("{}", self.display_with_context());
+        }
     }
 
     /// Construct a [`Display`](std::fmt::Display) proxy showing context without
@@ -109,124 +230,823 @@ impl<E> ContextError<E> {
     /// This is functionally the same as [`display_with_context`] just without the
     /// [`std::error::Error`] requirement.
     pub fn display_with_outer_context(&self) -> impl fmt::Display + '_
+    where
+        E: fmt::Display,
+    {
+        display_fn(move |f| self.fmt_with_context(f, &DisplayOptions::default(), None))
+    }
+
+    /// Construct a [`Display`](std::fmt::Display) proxy showing the in-line output
+    /// using the given [`DisplayOptions`].
+    ///
+    /// This is the same as the [`Display`](std::fmt::Display) implementation with
+    /// customizable output.
+    pub fn display_inline_with_options<'a>(
+        &'a self,
+        options: &'a DisplayOptions,
+    ) -> impl fmt::Display + 'a
     where
         E: fmt::Display,
     {
         display_fn(move |f| {
-            writeln!(f, "error: {}", self.error)?;
-            for origin in self.origins.iter() {
-                write!(f, "{origin}")?;
-            }
-            Ok(())
+            write!(f, "{}", self.error)?;
+            self.fmt_origins_as_suffix(f, options)
         })
     }
 
-    fn display_origins_as_suffix(&self) -> impl fmt::Display + '_ {
-        display_fn(move |f| {
-            let mut origins = self.origins.as_ref();
-            loop {
-                break match origins {
-                    [] => Ok(()),
-                    [o] => {
-                        write!(f, " {}", o.display_as_suffix())
-                    },
-                    [a, b] => {
-                        write!(f, " {} and {}", a.display_as_suffix(), b.display_as_suffix())
-                    },
-                    [o, rest @ ..] => {
-                        write!(f, " {},", o.display_as_suffix())?;
-                        origins = rest;
-                        continue
-                    },
+    fn fmt_with_context(
+        &self,
+        f: &mut fmt::Formatter<'_>,
+        options: &DisplayOptions,
+        source: Option<&dyn std::error::Error>,
+    ) -> fmt::Result
+    where
+        E: fmt::Display,
+    {
+        let strings = options.strings();
+        if options.verbosity() == Verbosity::Short {
+            return self.fmt_short(f, options);
+        }
+        fmt_labeled(f, options, Style::Error, strings.error, &self.error)?;
+        let mut curr = source.filter(|_| options.verbosity() == Verbosity::Full);
+        let mut prev_message = options.cause_deduplication().then(|| self.error.to_string());
+        let mut depth = 0;
+        while let Some(source) = curr {
+            curr = source.source();
+            if let Some(prev_message) = &mut prev_message {
+                let message = source.to_string();
+                if message == *prev_message {
+                    continue;
                 }
+                *prev_message = message;
+            }
+            if options.max_cause_depth().is_some_and(|limit| depth >= limit) {
+                break;
             }
+            depth += 1;
+            fmt_labeled(f, options, Style::Cause, strings.cause, source)?;
+        }
+        for origin in self.origins.iter() {
+            origin.fmt_with_options(f, options)?;
+        }
+        Ok(())
+    }
+
+    /// Write a single `path:2:3: error: ...` line.
+    pub(crate) fn fmt_short(&self, f: &mut fmt::Formatter<'_>, options: &DisplayOptions) -> fmt::Result
+    where
+        E: fmt::Display,
+    {
+        if let Some(origin) = self.visible_origins(options).next() {
+            write!(f, "{}: ", origin.display_as_location(options))?;
+        }
+        writeln!(f, "{}: {}", options.paint(Style::Error, options.strings().error), self.error)
+    }
+
+    pub(crate) fn visible_origins<'a>(
+        &'a self,
+        options: &'a DisplayOptions,
+    ) -> impl Iterator<Item = &'a ContextErrorOrigin> + 'a {
+        self.origins.iter().filter(|origin| {
+            options.category_display(origin.category()) != CategoryDisplay::Hidden
         })
     }
+
+    fn fmt_origins_as_suffix(&self, f: &mut fmt::Formatter<'_>, options: &DisplayOptions) -> fmt::Result {
+        let and = options.strings().and;
+        let origins: Vec<_> = self.visible_origins(options).collect();
+        let mut origins = origins.as_slice();
+        loop {
+            break match origins {
+                [] => Ok(()),
+                [o] => {
+                    write!(f, " {}", o.display_as_suffix(options))
+                },
+                [a, b] => {
+                    write!(f, " {} {and} {}", a.display_as_suffix(options), b.display_as_suffix(options))
+                },
+                [o, rest @ ..] => {
+                    write!(f, " {},", o.display_as_suffix(options))?;
+                    origins = rest;
+                    continue
+                },
+            }
+        }
+    }
+}
+
+impl ContextError<std::io::Error> {
+    /// Construct a context error for an I/O failure concerning an [`Origin`] as a whole.
+    ///
+    /// The error is rendered without a snippet, so failures like not being able to write
+    /// an output file can be reported alongside source diagnostics. Paths can be turned
+    /// into an origin with [`Origin::from_file`].
+    pub fn from_io_error(error: std::io::Error, origin: Origin, note: &'static str) -> Self {
+        Self::without_position(error, origin, note)
+    }
 }
 
 /// The contextual origin of a position in a [`SourceMap`] context.
 ///
 /// Can be displayed directly, or passed to [`ContextError::with_origins`] to associate
 /// context objects with an error.
+///
+/// [`SourceMap`]: crate::SourceMap
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct ContextErrorOrigin {
     origin: Origin,
     note: &'static str,
     location: ContextErrorLocation,
     context: Option<ContextErrorLocation>,
+    annotations: Vec<ContextErrorAnnotation>,
+    expected: Option<Arc<str>>,
+    category: SourceCategory,
+    highlight_len: usize,
+    whole_line: bool,
+    tags: Vec<DiagnosticTag>,
+    trace: Vec<ContextErrorOrigin>,
 }
 
 impl fmt::Display for ContextErrorOrigin {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        let lnum_width = count_digits(self.location.line_number);
+        self.fmt_with_options(f, &DisplayOptions::default())
+    }
+}
+
+impl ContextErrorOrigin {
+    pub(crate) fn fmt_with_options(
+        &self,
+        f: &mut fmt::Formatter<'_>,
+        options: &DisplayOptions,
+    ) -> fmt::Result {
+        match options.category_display(self.category) {
+            CategoryDisplay::Full => {
+                if self.location.is_unpositioned() {
+                    writeln!(f, "{} {}", options.paint(Style::Gutter, "-->"), self.display_as_location(options))?;
+                    if !self.note.is_empty() {
+                        writeln!(f, " {} {}", options.paint(Style::Gutter, "="), self.note)?;
+                    }
+                } else if self.location.is_bytes() {
+                    self.fmt_hex_dump(f, options)?;
+                } else {
+                    self.fmt_snippet(f, options)?;
+                }
+            },
+            CategoryDisplay::Location => {
+                writeln!(f, "{} {}", options.paint(Style::Gutter, "-->"), self.display_as_location(options))?;
+            },
+            CategoryDisplay::Hidden => {
+                return Ok(());
+            },
+        }
+        for frame in &self.trace {
+            writeln!(
+                f,
+                " {} {} {}",
+                options.paint(Style::Gutter, "="),
+                options.strings().included_from,
+                frame.display_as_location(options),
+            )?;
+        }
+        Ok(())
+    }
+
+    fn fmt_snippet(&self, f: &mut fmt::Formatter<'_>, options: &DisplayOptions) -> fmt::Result {
         let self_lnum = self.location.line_number;
-        let self_line = &self.location.line;
-        writeln!(f, "--> {}", self.display_as_location())?;
+        let mut rows = Vec::new();
+        if let Some(ctx_location) = &self.context {
+            if ctx_location.line_number < self_lnum {
+                rows.push(SnippetRow::new(ctx_location));
+            }
+        }
+        let mut self_row = SnippetRow::new(&self.location)
+            .with_label('^', &self.location, self.highlight_len, self.note);
+        self_row.expected = self.expected.as_deref();
+        self_row.tags = &self.tags;
+        rows.push(self_row);
+        for annotation in &self.annotations {
+            let location = &annotation.location;
+            let row = match rows.iter_mut().find(|row| row.line_number == location.line_number) {
+                Some(row) => row,
+                None => {
+                    rows.push(SnippetRow::new(location));
+                    rows.last_mut().unwrap()
+                },
+            };
+            row.labels.push(('-', location.column_number, 1, annotation.note));
+        }
+        rows.sort_by_key(|row| row.line_number);
+
+        let max_line_number = rows.iter().map(|row| row.line_number).max().unwrap_or(1);
+        let lnum_width = options.line_number_width(max_line_number);
+        writeln!(f, "{} {}", options.paint(Style::Gutter, "-->"), self.display_as_location(options))?;
+        if options.column_ruler() {
+            let width = rows.iter().map(|row| row.line.chars().count()).max().unwrap_or(0);
+            write!(f, "{} ", options.paint(Style::Gutter, format_args!(" {:lnum_width$} |", "")))?;
+            writeln!(f, "{}", options.paint(Style::Gutter, display_ruler(width, options)))?;
+        }
+        let mut prev_lnum = None;
+        for row in &rows {
+            if let Some(prev_lnum) = prev_lnum {
+                if row.line_number - prev_lnum > 1 {
+                    writeln!(f, "{} ...", options.paint(Style::Gutter, format_args!(" {:lnum_width$} |", "")))?;
+                }
+            }
+            prev_lnum = Some(row.line_number);
+            let gutter = if row.expected.is_some() { '-' } else { '|' };
+            let gutter = format_args!(" {:lnum_width$} {gutter}", row.line_number);
+            write!(f, "{} ", options.paint(Style::Gutter, gutter))?;
+            row.fmt_line(f, options)?;
+            for &(marker, column_number, byte_len, note) in &row.labels {
+                write!(f, "{} ", options.paint(Style::Gutter, format_args!(" {:lnum_width$} |", "")))?;
+                let (skipped, rest) = row.line.split_at(column_number - 1);
+                for c in skipped.chars() {
+                    f.write_char(match c { '\t' => '\t', _ => ' '})?;
+                }
+                let highlighted = rest.char_indices().take_while(|(index, _)| *index < byte_len).count();
+                let rest_marker = match marker == '^' && options.tilde_underlines() {
+                    true => '~',
+                    false => marker,
+                };
+                let markers: String = std::iter::once(marker)
+                    .chain(std::iter::repeat_n(rest_marker, highlighted.saturating_sub(1)))
+                    .collect();
+                let style = if marker == '^' { Style::Primary } else { Style::Secondary };
+                let note = match marker == '^' && options.highlight_widths() {
+                    true => {
+                        let end = rest.char_indices().nth(highlighted).map(|(end, _)| end);
+                        let highlighted = &rest[..end.unwrap_or(rest.len())];
+                        Cow::Owned(with_highlight_width(note, highlighted, options))
+                    },
+                    false => Cow::Borrowed(note),
+                };
+                let Some(width) = options.wrap_width() else {
+                    writeln!(f, "{}", options.paint(style, format_args!("{markers} {note}")))?;
+                    continue;
+                };
+                let markers_width = highlighted.max(1) + 1;
+                let indent = lnum_width + 4 + skipped.chars().count() + markers_width;
+                let lines = wrap_text(&note, width.saturating_sub(indent));
+                for (index, line) in lines.iter().enumerate() {
+                    if index == 0 {
+                        writeln!(f, "{}", options.paint(style, format_args!("{markers} {line}")))?;
+                        continue;
+                    }
+                    write!(f, "{} ", options.paint(Style::Gutter, format_args!(" {:lnum_width$} |", "")))?;
+                    for c in skipped.chars() {
+                        f.write_char(match c { '\t' => '\t', _ => ' '})?;
+                    }
+                    writeln!(f, "{:markers_width$}{}", "", options.paint(style, line))?;
+                }
+            }
+            for expected_line in row.expected.iter().flat_map(|expected| expected.lines()) {
+                writeln!(
+                    f,
+                    "{} {}",
+                    options.paint(Style::Gutter, format_args!(" {:lnum_width$}", "")),
+                    options.paint(Style::Added, format_args!("+ {expected_line}")),
+                )?;
+            }
+        }
+        Ok(())
+    }
+
+    fn fmt_hex_dump(&self, f: &mut fmt::Formatter<'_>, options: &DisplayOptions) -> fmt::Result {
+        let mut rows = Vec::new();
         if let Some(ctx_location) = &self.context {
-            let ctx_lnum = ctx_location.line_number;
-            let ctx_line = &ctx_location.line;
-            if ctx_lnum < self_lnum {
-                writeln!(f, " {ctx_lnum:lnum_width$} | {ctx_line}")?;
-                if ctx_lnum.checked_add(1) != Some(self_lnum) {
-                    writeln!(f, " {:lnum_width$} | ...", "")?;
+            if ctx_location.line_number < self.location.line_number {
+                rows.push(ctx_location);
+            }
+        }
+        rows.push(&self.location);
+
+        writeln!(f, "{} {}", options.paint(Style::Gutter, "-->"), self.display_as_location(options))?;
+        let mut prev_lnum = None;
+        for row in rows {
+            if let Some(prev_lnum) = prev_lnum {
+                if row.line_number - prev_lnum > 1 {
+                    writeln!(f, "{} ...", options.paint(Style::Gutter, format_args!(" {:8} |", "")))?;
                 }
             }
+            prev_lnum = Some(row.line_number);
+            let bytes = row.bytes();
+            write!(f, "{}", options.paint(Style::Gutter, format_args!(" {:08x} |", row.row_start())))?;
+            for index in 0..BYTE_ROW_LEN {
+                match bytes.get(index) {
+                    Some(byte) => write!(f, " {byte:02x}")?,
+                    None => f.write_str("   ")?,
+                }
+            }
+            f.write_str(" | ")?;
+            for &byte in bytes {
+                f.write_char(if byte.is_ascii_graphic() || byte == b' ' { byte as char } else { '.' })?;
+            }
+            writeln!(f)?;
         }
-        writeln!(f, " {self_lnum:lnum_width$} | {self_line}")?;
-        let skipped = &self_line[..self.location.column_number];
-        write!(f, " {:lnum_width$} |", "")?;
-        for c in skipped.chars() {
-            f.write_char(match c { '\t' => '\t', _ => ' '})?;
+        let skipped = 3 * (self.location.column_number - 1);
+        let lines = match options.wrap_width() {
+            Some(width) => wrap_text(self.note, width.saturating_sub(15 + skipped)),
+            None => vec![self.note.into()],
+        };
+        for (index, line) in lines.iter().enumerate() {
+            write!(f, "{} {:skipped$}", options.paint(Style::Gutter, format_args!(" {:8} |", "")), "")?;
+            if index == 0 {
+                writeln!(f, "{}", options.paint(Style::Primary, format_args!("^^ {line}")))?;
+            } else {
+                writeln!(f, "   {}", options.paint(Style::Primary, line))?;
+            }
         }
-        writeln!(f, "^ {}", self.note)?;
         Ok(())
     }
-}
 
-impl ContextErrorOrigin {
     pub(crate) fn new(
         origin: Origin,
         note: &'static str,
         location: ContextErrorLocation,
         context: Option<ContextErrorLocation>,
     ) -> Self {
-        Self { origin, note, location, context }
+        Self {
+            origin,
+            note,
+            location,
+            context,
+            annotations: Vec::new(),
+            expected: None,
+            category: SourceCategory::default(),
+            highlight_len: 1,
+            whole_line: false,
+            tags: Vec::new(),
+            trace: Vec::new(),
+        }
+    }
+
+    /// Construct an origin for an [`Origin`] as a whole, without a position.
+    ///
+    /// This is intended for errors like empty files or failed reads, which don't
+    /// concern a specific position in the content. The origin is shown without a source
+    /// snippet or line and column numbers, with the note listed below its location.
+    /// Annotations and context positions are not shown for such origins.
+    ///
+    /// Use [`SourceMap::context_error_source_origin`] to also apply the
+    /// [`SourceCategory`] of a map entry.
+    ///
+    /// [`SourceMap::context_error_source_origin`]: crate::SourceMap::context_error_source_origin
+    pub fn without_position(origin: Origin, note: &'static str) -> Self {
+        Self::new(origin, note, ContextErrorLocation::unpositioned(), None)
+    }
+
+    /// Append a frame to the trace of positions the error position was reached from.
+    ///
+    /// This is intended for include or expansion stacks, with the innermost frame
+    /// being attached first. Each frame is displayed as a trailing line after the
+    /// context, as in `= included from a.ext:10:1`.
+    pub fn with_trace_frame(mut self, frame: ContextErrorOrigin) -> Self {
+        self.trace.push(frame);
+        self
+    }
+
+    /// All attached trace frames, starting with the innermost.
+    pub fn trace_frames(&self) -> &[ContextErrorOrigin] {
+        &self.trace
+    }
+
+    /// Highlight the given number of bytes starting at the error position.
+    ///
+    /// The highlight will be displayed as a sequence of `^` markers under the
+    /// highlighted characters, and is limited to the line of the error position.
+    /// At least one marker will always be displayed. With
+    /// [`DisplayOptions::with_tilde_underlines`], all markers after the first are `~`.
+    pub fn with_highlight_len(mut self, byte_len: usize) -> Self {
+        self.highlight_len = byte_len;
+        self
+    }
+
+    /// The number of highlighted bytes starting at the error position.
+    pub fn highlight_len(&self) -> usize {
+        self.highlight_len
+    }
+
+    /// Mark the origin as concerning its whole line instead of a specific column.
+    ///
+    /// Whole-line origins are located by their line number only, as in `a.ext:10`.
+    /// Use [`SourceMap::context_error_line_origin`] to capture such an origin with the
+    /// full line highlighted.
+    ///
+    /// [`SourceMap::context_error_line_origin`]: crate::SourceMap::context_error_line_origin
+    pub fn with_whole_line(mut self, enabled: bool) -> Self {
+        self.whole_line = enabled;
+        self
+    }
+
+    /// Determine if the origin concerns its whole line instead of a specific column.
+    pub fn is_whole_line(&self) -> bool {
+        self.whole_line
+    }
+
+    /// Attach a [`DiagnosticTag`] to the highlighted content.
+    ///
+    /// With [colors](DisplayOptions::with_colors) enabled, content tagged as
+    /// [unnecessary](DiagnosticTag::Unnecessary) is shown dimmed in the source snippet,
+    /// and content tagged as [deprecated](DiagnosticTag::Deprecated) is shown struck
+    /// through. Attaching a tag multiple times has no additional effect.
+    pub fn with_tag(mut self, tag: DiagnosticTag) -> Self {
+        if !self.tags.contains(&tag) {
+            self.tags.push(tag);
+        }
+        self
+    }
+
+    /// All attached [`DiagnosticTag`] values.
+    pub fn tags(&self) -> &[DiagnosticTag] {
+        &self.tags
+    }
+
+    /// Set the [`SourceCategory`] of the source this origin belongs to.
+    ///
+    /// Origins captured with [`SourceMap::context_error_origin`] receive the category
+    /// of their source entry.
+    ///
+    /// [`SourceMap::context_error_origin`]: crate::SourceMap::context_error_origin
+    pub fn with_category(mut self, category: SourceCategory) -> Self {
+        self.category = category;
+        self
+    }
+
+    /// The [`SourceCategory`] of the source this origin belongs to.
+    pub fn category(&self) -> SourceCategory {
+        self.category
+    }
+
+    /// Attach the expected content for the line of the error position.
+    ///
+    /// The line will be displayed with a `-` marker, followed by the expected content
+    /// with `+` markers, similar to a diff.
+    pub fn with_expected<T>(mut self, expected: T) -> Self
+    where
+        T: AsRef<str>,
+    {
+        self.expected = Some(expected.as_ref().into());
+        self
+    }
+
+    /// The expected content for the line of the error position, if any was given.
+    pub fn expected(&self) -> Option<&str> {
+        self.expected.as_deref()
+    }
+
+    /// Attach a [`ContextErrorAnnotation`] to be shown on its own line in the context.
+    ///
+    /// Annotations are displayed with a `-` marker, distinguishing them from the error
+    /// position itself.
+    ///
+    /// # Panics
+    ///
+    /// This function will panic if the annotation belongs to a different [`Origin`].
+    #[track_caller]
+    pub fn with_annotation(mut self, annotation: ContextErrorAnnotation) -> Self {
+        assert_eq!(self.origin, annotation.origin, "annotation must belong to same origin");
+        self.annotations.push(annotation);
+        self
+    }
+
+    /// All attached [`ContextErrorAnnotation`] values.
+    pub fn annotations(&self) -> &[ContextErrorAnnotation] {
+        &self.annotations
+    }
+
+    /// The [`Origin`] of the error position.
+    pub fn origin(&self) -> &Origin {
+        &self.origin
     }
 
-    fn display(&self, include_prefix: bool) -> impl fmt::Display + '_ {
-        let ContextErrorLocation { line_number, column_number, .. } = &self.location;
+    /// The note for the error position.
+    pub fn note(&self) -> &'static str {
+        self.note
+    }
+
+    /// Construct a [`Display`](std::fmt::Display) proxy using the given
+    /// [`DisplayOptions`].
+    pub fn display_with_options<'a>(&'a self, options: &'a DisplayOptions) -> impl fmt::Display + 'a {
+        display_fn(move |f| self.fmt_with_options(f, options))
+    }
+
+    fn display<'a>(&'a self, options: &'a DisplayOptions, include_prefix: bool) -> impl fmt::Display + 'a {
+        let line_number = self.location.line_number;
+        let column_number = options.reported_column(self.location.column_number);
+        let end_column_number = self.end_column_number()
+            .filter(|_| options.location_ranges())
+            .map(|end| options.reported_column(end));
+        let strings = options.strings();
         display_fn(move |f| match &self.origin {
-            Origin::File(path) => {
-                let prefix = if include_prefix { "at " } else { "" };
-                write!(f, "{}{}:{}:{}", prefix, path.display(), line_number, column_number)
+            Origin::File(_) | Origin::Url(_) | Origin::Stdin => {
+                if include_prefix {
+                    write!(f, "{} ", strings.at)?;
+                }
+                match &self.origin {
+                    Origin::File(path) => write!(f, "{}", options.display_path(path))?,
+                    Origin::Url(url) => f.write_str(url)?,
+                    _ => f.write_str("<stdin>")?,
+                }
+                if self.location.is_unpositioned() {
+                    Ok(())
+                } else if self.location.is_bytes() {
+                    write!(f, "+0x{:x}", self.location.byte())
+                } else if self.whole_line {
+                    write!(f, ":{}", line_number)
+                } else {
+                    write!(f, ":{}:{}", line_number, column_number)?;
+                    match end_column_number {
+                        Some(end) => write!(f, "-{}:{}", line_number, end),
+                        None => Ok(()),
+                    }
+                }
             },
-            Origin::Named(name) => {
-                let prefix = if include_prefix { "in " } else { "" };
-                write!(f, "{}`{}`, line {}, column {}", prefix, name, line_number, column_number)
+            Origin::Named(_) | Origin::Generated { .. } => {
+                if include_prefix {
+                    write!(f, "{} ", strings.in_named)?;
+                }
+                match &self.origin {
+                    Origin::Generated { generator } => {
+                        write!(f, "{} `{}`", strings.generated, generator)?;
+                    },
+                    Origin::Named(name) => write!(f, "`{}`", name)?,
+                    _ => unreachable!(),
+                }
+                if self.location.is_unpositioned() {
+                    Ok(())
+                } else if self.location.is_bytes() {
+                    write!(f, ", {} 0x{:x}", strings.offset, self.location.byte())
+                } else if self.whole_line {
+                    write!(f, ", {} {}", strings.line, line_number)
+                } else {
+                    write!(
+                        f,
+                        ", {} {}, {} {}",
+                        strings.line,
+                        line_number,
+                        strings.column,
+                        column_number,
+                    )?;
+                    match end_column_number {
+                        Some(end) => write!(
+                            f,
+                            " {} {} {}, {} {}",
+                            strings.to,
+                            strings.line,
+                            line_number,
+                            strings.column,
+                            end,
+                        ),
+                        None => Ok(()),
+                    }
+                }
             },
         })
     }
 
-    fn display_as_suffix(&self) -> impl fmt::Display + '_ {
-        self.display(true)
+    /// The plain name of the origin, without any position information.
+    pub(crate) fn display_name<'a>(&'a self, options: &'a DisplayOptions) -> impl fmt::Display + 'a {
+        display_origin_name(&self.origin, options)
+    }
+
+    /// The column number after the highlighted part of the line, if the highlight covers
+    /// more than one character.
+    fn end_column_number(&self) -> Option<usize> {
+        if self.whole_line || self.location.is_bytes() || self.location.is_unpositioned() {
+            return None;
+        }
+        let line = self.location.text();
+        let line = line.strip_suffix('\r').unwrap_or(line);
+        let rest = line.get((self.location.column_number - 1)..)?;
+        let first_len = rest.chars().next()?.len_utf8();
+        let end = rest.char_indices().map(|(index, _)| index)
+            .find(|index| *index >= self.highlight_len)
+            .unwrap_or(rest.len());
+        (end > first_len).then_some(self.location.column_number + end)
+    }
+
+    /// The line and column numbers of the error position, unless it is in binary content
+    /// or the origin has no position.
+    pub(crate) fn line_and_column(&self) -> Option<(usize, usize)> {
+        if self.location.is_bytes() || self.location.is_unpositioned() {
+            None
+        } else {
+            Some((self.location.line_number, self.location.column_number))
+        }
     }
 
-    fn display_as_location(&self) -> impl fmt::Display + '_ {
-        self.display(false)
+    fn display_as_suffix<'a>(&'a self, options: &'a DisplayOptions) -> impl fmt::Display + 'a {
+        self.display(options, true)
+    }
+
+    fn display_as_location<'a>(&'a self, options: &'a DisplayOptions) -> impl fmt::Display + 'a {
+        self.display(options, false)
     }
 }
 
+/// The number of bytes per row in a hex dump.
+pub(crate) const BYTE_ROW_LEN: usize = 16;
+
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub(crate) struct ContextErrorLocation {
     line_number: usize,
     column_number: usize,
-    line: Arc<str>,
+    line: ContextErrorLine,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum ContextErrorLine {
+    Text(Arc<str>),
+    Bytes(Arc<[u8]>),
+    Unpositioned,
 }
 
 impl ContextErrorLocation {
     pub(crate) fn new(line: Arc<str>, line_number: usize, column_number: usize) -> Self {
-        Self { line, line_number, column_number }
+        Self { line: ContextErrorLine::Text(line), line_number, column_number }
+    }
+
+    /// A location in a hex dump row of up to [`BYTE_ROW_LEN`] bytes.
+    pub(crate) fn new_bytes(row: Arc<[u8]>, row_number: usize, column_number: usize) -> Self {
+        Self { line: ContextErrorLine::Bytes(row), line_number: row_number, column_number }
+    }
+
+    /// A location for an origin as a whole, without a position.
+    pub(crate) fn unpositioned() -> Self {
+        Self { line: ContextErrorLine::Unpositioned, line_number: 1, column_number: 1 }
+    }
+
+    fn is_bytes(&self) -> bool {
+        matches!(self.line, ContextErrorLine::Bytes(_))
+    }
+
+    fn is_unpositioned(&self) -> bool {
+        matches!(self.line, ContextErrorLine::Unpositioned)
     }
+
+    fn text(&self) -> &str {
+        match &self.line {
+            ContextErrorLine::Text(line) => line,
+            ContextErrorLine::Bytes(_) | ContextErrorLine::Unpositioned => "",
+        }
+    }
+
+    fn bytes(&self) -> &[u8] {
+        match &self.line {
+            ContextErrorLine::Text(_) | ContextErrorLine::Unpositioned => &[],
+            ContextErrorLine::Bytes(row) => row,
+        }
+    }
+
+    fn row_start(&self) -> usize {
+        (self.line_number - 1) * BYTE_ROW_LEN
+    }
+
+    fn byte(&self) -> usize {
+        self.row_start() + (self.column_number - 1)
+    }
+}
+
+/// A note attached to an additional line in the context of a [`ContextErrorOrigin`].
+///
+/// Annotations are captured with [`SourceMap::context_error_annotation`] and attached
+/// with [`ContextErrorOrigin::with_annotation`].
+///
+/// [`SourceMap::context_error_annotation`]: crate::SourceMap::context_error_annotation
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ContextErrorAnnotation {
+    origin: Origin,
+    note: &'static str,
+    location: ContextErrorLocation,
+}
+
+impl ContextErrorAnnotation {
+    pub(crate) fn new(origin: Origin, note: &'static str, location: ContextErrorLocation) -> Self {
+        Self { origin, note, location }
+    }
+
+    /// The note for the annotated position.
+    pub fn note(&self) -> &'static str {
+        self.note
+    }
+}
+
+struct SnippetRow<'a> {
+    line_number: usize,
+    line: &'a str,
+    labels: Vec<(char, usize, usize, &'static str)>,
+    expected: Option<&'a str>,
+    tags: &'a [DiagnosticTag],
+}
+
+impl<'a> SnippetRow<'a> {
+    fn new(location: &'a ContextErrorLocation) -> Self {
+        Self {
+            line_number: location.line_number,
+            line: location.text(),
+            labels: Vec::new(),
+            expected: None,
+            tags: &[],
+        }
+    }
+
+    fn with_label(
+        mut self,
+        marker: char,
+        location: &ContextErrorLocation,
+        byte_len: usize,
+        note: &'static str,
+    ) -> Self {
+        self.labels.push((marker, location.column_number, byte_len, note));
+        self
+    }
+
+    /// Write the line, styling the primary highlight according to the tags.
+    fn fmt_line(&self, f: &mut fmt::Formatter<'_>, options: &DisplayOptions) -> fmt::Result {
+        let label = self.labels.first().filter(|_| !self.tags.is_empty());
+        let Some(&(_, column_number, byte_len, _)) = label else {
+            return writeln!(f, "{}", self.line);
+        };
+        let (before, rest) = self.line.split_at(column_number - 1);
+        let end = rest.char_indices().map(|(index, _)| index).find(|index| *index >= byte_len);
+        let (highlighted, after) = rest.split_at(end.unwrap_or(rest.len()));
+        let tagged = |tag| options.colors() && self.tags.contains(&tag);
+        let highlighted = paint(tagged(DiagnosticTag::Unnecessary), Style::Unnecessary, highlighted);
+        let highlighted = paint(tagged(DiagnosticTag::Deprecated), Style::Deprecated, highlighted);
+        writeln!(f, "{before}{highlighted}{after}")
+    }
+}
+
+/// A tag classifying the highlighted content of a [`ContextErrorOrigin`], like the
+/// diagnostic tags of the Language Server Protocol.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum DiagnosticTag {
+    /// The content is unused or unnecessary, like an unused variable.
+    Unnecessary,
+    /// The content is deprecated.
+    Deprecated,
+}
+
+/// The plain name of an [`Origin`], without any position information.
+pub(crate) fn display_origin_name<'a>(
+    origin: &'a Origin,
+    options: &'a DisplayOptions,
+) -> impl fmt::Display + 'a {
+    display_fn(move |f| match origin {
+        Origin::File(path) => write!(f, "{}", options.display_path(path)),
+        Origin::Url(url) => f.write_str(url),
+        Origin::Stdin => f.write_str("<stdin>"),
+        Origin::Named(name) => f.write_str(name),
+        Origin::Generated { generator } => f.write_str(generator),
+    })
+}
+
+/// Append the width of a highlighted text to a note, as in `note (3 bytes, 3 chars)`.
+fn with_highlight_width(note: &str, highlighted: &str, options: &DisplayOptions) -> String {
+    let strings = options.strings();
+    let bytes = highlighted.len();
+    let chars = highlighted.chars().count();
+    let width = format!("({bytes} {}, {chars} {})", strings.bytes, strings.chars);
+    if note.is_empty() { width } else { format!("{note} {width}") }
+}
+
+/// A column ruler like `....+....1....+....2` covering a number of characters.
+fn display_ruler(width: usize, options: &DisplayOptions) -> impl fmt::Display + '_ {
+    display_fn(move |f| {
+        for column_number in 1..=width {
+            let column = options.reported_column(column_number);
+            f.write_char(match column {
+                0 => '.',
+                _ if column.is_multiple_of(10) => char::from_digit((column / 10 % 10) as u32, 10).unwrap(),
+                _ if column.is_multiple_of(5) => '+',
+                _ => '.',
+            })?;
+        }
+        Ok(())
+    })
+}
+
+/// Write a labeled message line like `error: ...`, wrapped to the configured width.
+pub(crate) fn fmt_labeled(
+    f: &mut fmt::Formatter<'_>,
+    options: &DisplayOptions,
+    style: Style,
+    label: &str,
+    message: &dyn fmt::Display,
+) -> fmt::Result {
+    let Some(width) = options.wrap_width() else {
+        return writeln!(f, "{}: {}", options.paint(style, label), message);
+    };
+    let indent = label.chars().count() + 2;
+    write!(f, "{}: ", options.paint(style, label))?;
+    let lines = wrap_text(&message.to_string(), width.saturating_sub(indent));
+    for (index, line) in lines.iter().enumerate() {
+        if index > 0 {
+            write!(f, "{:indent$}", "")?;
+        }
+        writeln!(f, "{line}")?;
+    }
+    Ok(())
 }
 
 /// A generic error carrying contextual [`Offset`] data.
@@ -250,6 +1070,8 @@ pub struct SourceError<E> {
     offset: Offset,
     offset_note: &'static str,
     context_offset: Option<Offset>,
+    opening: Option<(Offset, &'static str)>,
+    byte_len: usize,
 }
 
 impl<E> std::error::Error for SourceError<E>
@@ -275,7 +1097,29 @@ impl<E> SourceError<E> {
     ///
     /// The given note will be used to highlight the error position.
     pub fn new(error: E, offset: Offset, offset_note: &'static str) -> Self {
-        Self { error, offset, offset_note, context_offset: None }
+        Self { error, offset, offset_note, context_offset: None, opening: None, byte_len: 1 }
+    }
+
+    /// Construct an error covering a [`Span`].
+    ///
+    /// The error position is the start of the span, and the whole span is highlighted
+    /// when the error is turned into a [`ContextError`].
+    pub fn from_span(error: E, span: Span, offset_note: &'static str) -> Self {
+        Self::new(error, span.start(), offset_note).with_byte_len(span.byte_len())
+    }
+
+    /// Set the number of bytes covered by the error, starting at the error position.
+    ///
+    /// This is passed on to [`ContextErrorOrigin::with_highlight_len`] when the error
+    /// is turned into a [`ContextError`]. The default is a single byte.
+    pub fn with_byte_len(mut self, byte_len: usize) -> Self {
+        self.byte_len = byte_len;
+        self
+    }
+
+    /// The number of bytes covered by the error, starting at the error position.
+    pub fn byte_len(&self) -> usize {
+        self.byte_len
     }
 
     /// Associate some additional context [`Offset`] with the error.
@@ -287,16 +1131,37 @@ impl<E> SourceError<E> {
         self
     }
 
+    /// Associate the [`Offset`] of an opening delimiter with the error.
+    ///
+    /// This is intended for errors about unclosed or mismatched delimiters, with the
+    /// error position being where the closing delimiter was expected. The opening
+    /// position will be shown with the given note in the same context if it belongs
+    /// to the same source, or as a separate origin otherwise.
+    pub fn with_opening(mut self, offset: Offset, note: &'static str) -> Self {
+        self.opening = Some((offset, note));
+        self
+    }
+
     /// The encapsulated error value.
     pub fn error(&self) -> &E {
         &self.error
     }
 
+    /// The [`Offset`] of the opening delimiter and its note, if any was given.
+    pub fn opening(&self) -> Option<(Offset, &'static str)> {
+        self.opening
+    }
+
     /// The [`Offset`] this error is associated with.
     pub fn offset(&self) -> Offset {
         self.offset
     }
 
+    /// The [`Span`] covered by the error, starting at the error position.
+    pub fn span(&self) -> Span {
+        Span::new(self.offset, self.byte_len)
+    }
+
     /// The additional context [`Offset`] to be included in the output, if any was given.
     pub fn context_offset(&self) -> Option<Offset> {
         self.context_offset
@@ -317,15 +1182,43 @@ impl<E> SourceError<E> {
             offset: self.offset,
             offset_note: self.offset_note,
             context_offset: self.context_offset,
+            opening: self.opening,
+            byte_len: self.byte_len,
         }
     }
 
     /// Turn the error into a full [`ContextError`] by resolving it through a
-    /// [`SourceMap`].
-    pub fn into_context_error(self, map: &SourceMap) -> ContextError<E> {
+    /// [`SourceMap`] or another [`SourceProvider`].
+    ///
+    /// [`SourceMap`]: crate::SourceMap
+    pub fn into_context_error<P>(self, map: &P) -> ContextError<E>
+    where
+        P: SourceProvider + ?Sized,
+    {
+        let mut origin = map.context_error_origin(self.offset, self.offset_note, self.context_offset)
+            .with_highlight_len(self.byte_len);
+        let mut opening_origin = None;
+        if let Some((offset, note)) = self.opening {
+            if offset.source_index() == self.offset.source_index() {
+                origin = origin.with_annotation(map.context_error_annotation(offset, note));
+            } else {
+                opening_origin = Some(map.context_error_origin(offset, note, None));
+            }
+        }
+        ContextError::with_origins(self.error, [origin].into_iter().chain(opening_origin))
+    }
+
+    /// Turn the error into a full [`ContextError`] by resolving it through a
+    /// [`ByteSourceMap`].
+    ///
+    /// An [opening delimiter](Self::with_opening) is always shown as a separate origin.
+    pub fn into_byte_context_error(self, map: &ByteSourceMap) -> ContextError<E> {
+        let opening_origin = self.opening.map(|(offset, note)| {
+            map.context_error_origin(offset, note, None)
+        });
         ContextError::with_origins(self.error, [
             map.context_error_origin(self.offset, self.offset_note, self.context_offset),
-        ])
+        ].into_iter().chain(opening_origin))
     }
 
     /// Discard the context and unwrap the encapsulated error value.
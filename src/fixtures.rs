@@ -0,0 +1,150 @@
+use std::fmt::{self, Write};
+use std::path::{Path, PathBuf};
+
+use crate::{ContextError, DisplayOptions, Origin, SourceIndex, SourceMap};
+
+
+/// The environment variable enabling the update mode of [`Fixtures::run`].
+pub const UPDATE_EXPECT_VAR: &str = "UPDATE_EXPECT";
+
+/// A harness for tests comparing rendered diagnostics for a directory of fixture
+/// sources against expected outputs.
+///
+/// The expected output for each fixture is stored in a sibling file with the extension
+/// replaced by `expected`. When the [`UPDATE_EXPECT_VAR`] environment variable is set
+/// to `1`, the expected outputs are written instead of being compared.
+///
+/// ```no_run
+/// # use src_ctx::{Fixtures, ContextError};
+/// # fn check(map: &src_ctx::SourceMap, index: src_ctx::SourceIndex)
+/// #     -> Vec<ContextError<std::fmt::Error>> { Vec::new() }
+/// Fixtures::new("tests/ui", "lang").run(|map, index| check(map, index));
+/// ```
+#[derive(Debug, Clone)]
+pub struct Fixtures {
+    root: PathBuf,
+    extension: String,
+    options: DisplayOptions,
+}
+
+impl Fixtures {
+    /// Create a harness for all files with a specific extension below a root path.
+    ///
+    /// Diagnostics are rendered with [`DisplayOptions::stable`] by default.
+    pub fn new<P>(root: P, extension: &str) -> Self
+    where
+        P: Into<PathBuf>,
+    {
+        Self {
+            root: root.into(),
+            extension: extension.into(),
+            options: DisplayOptions::stable(),
+        }
+    }
+
+    /// Replace the [`DisplayOptions`] used to render diagnostics.
+    pub fn with_options(mut self, options: DisplayOptions) -> Self {
+        self.options = options;
+        self
+    }
+
+    /// The [`DisplayOptions`] used to render diagnostics.
+    pub fn options(&self) -> &DisplayOptions {
+        &self.options
+    }
+
+    /// The root path of the fixtures.
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    /// The extension of fixture files.
+    pub fn extension(&self) -> &str {
+        &self.extension
+    }
+
+    /// Load all fixtures and compare the diagnostics produced by `check` for each of
+    /// them against the expected outputs.
+    ///
+    /// Fixtures are processed in path order. The errors returned for a fixture are
+    /// rendered with [`ContextError::render_batch_string`].
+    ///
+    /// # Panics
+    ///
+    /// This function will panic if the fixtures could not be loaded, if an expected
+    /// output is missing or different, or if an expected output could not be written
+    /// in update mode.
+    #[track_caller]
+    pub fn run<F, E>(&self, check: F)
+    where
+        F: FnMut(&SourceMap, SourceIndex) -> Vec<ContextError<E>>,
+        E: fmt::Display + std::error::Error,
+    {
+        let update = std::env::var_os(UPDATE_EXPECT_VAR).is_some_and(|value| value == "1");
+        let mismatches = self.run_with_update(update, check);
+        if !mismatches.is_empty() {
+            let mut message = String::new();
+            for mismatch in &mismatches {
+                writeln!(message, "{mismatch}").unwrap();
+            }
+            writeln!(message, "Set {UPDATE_EXPECT_VAR}=1 to update the expected outputs").unwrap();
+            panic!("{} of the fixtures did not match:\n\n{message}", mismatches.len());
+        }
+    }
+
+    #[track_caller]
+    fn run_with_update<F, E>(&self, update: bool, mut check: F) -> Vec<FixtureMismatch>
+    where
+        F: FnMut(&SourceMap, SourceIndex) -> Vec<ContextError<E>>,
+        E: fmt::Display + std::error::Error,
+    {
+        let mut map = SourceMap::new();
+        let mut fixtures = match map.load_directory_report(&self.root, &self.extension) {
+            Ok(report) => report.inserted().iter()
+                .copied()
+                .filter_map(|index| match map.origin(index) {
+                    Origin::File(path) => Some((path.to_path_buf(), index)),
+                    _ => None,
+                })
+                .collect::<Vec<_>>(),
+            Err(error) => panic!("Failed to load fixtures: {error}"),
+        };
+        fixtures.sort();
+        let mut mismatches = Vec::new();
+        for (path, index) in fixtures {
+            let actual = ContextError::render_batch_string(&check(&map, index), &self.options);
+            let expected_path = path.with_extension("expected");
+            if update {
+                if let Err(error) = std::fs::write(&expected_path, &actual) {
+                    panic!("Failed to write `{}`: {error}", expected_path.display());
+                }
+                continue;
+            }
+            let expected = std::fs::read_to_string(&expected_path).ok();
+            if expected.as_deref() != Some(actual.as_str()) {
+                mismatches.push(FixtureMismatch { path: expected_path, expected, actual });
+            }
+        }
+        mismatches
+    }
+}
+
+struct FixtureMismatch {
+    path: PathBuf,
+    expected: Option<String>,
+    actual: String,
+}
+
+impl fmt::Display for FixtureMismatch {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.expected {
+            Some(expected) => {
+                writeln!(f, "--- expected `{}`", self.path.display())?;
+                writeln!(f, "{expected}")?;
+            },
+            None => writeln!(f, "--- missing `{}`", self.path.display())?,
+        }
+        writeln!(f, "+++ actual")?;
+        writeln!(f, "{}", self.actual)
+    }
+}
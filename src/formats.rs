@@ -0,0 +1,105 @@
+use crate::{Offset, SourceError, SourceIndex, SourceMap};
+
+
+impl SourceMap {
+    /// Turn a [`serde_json::Error`] for the content of an entry into a [`SourceError`]
+    /// at the reported line and column.
+    ///
+    /// Errors without a position, like I/O errors, point at the start of the content.
+    ///
+    /// # Panics
+    ///
+    /// This function will panic if the index does not belong to this map.
+    #[cfg(feature = "serde_json")]
+    #[track_caller]
+    pub fn json_source_error(
+        &self,
+        index: SourceIndex,
+        error: serde_json::Error,
+    ) -> SourceError<serde_json::Error> {
+        if error.line() == 0 {
+            return self.byte_offset_clamped(index, 0).error(error, "invalid JSON");
+        }
+        let position = crate::LinePosition {
+            line: error.line() - 1,
+            character: error.column().saturating_sub(1),
+        };
+        let encoding = crate::PositionEncoding::Utf8;
+        let offset = self.content_line_position_offset(index, position, encoding)
+            .unwrap_or_else(|| self.input(index).end().offset());
+        offset.error(error, "invalid JSON")
+    }
+
+    /// Turn a [`toml::de::Error`] for the content of an entry into a [`SourceError`]
+    /// at the start of the reported span.
+    ///
+    /// Errors without a span point at the start of the content.
+    ///
+    /// # Panics
+    ///
+    /// This function will panic if the index does not belong to this map.
+    #[cfg(feature = "toml")]
+    #[track_caller]
+    pub fn toml_source_error(
+        &self,
+        index: SourceIndex,
+        error: toml::de::Error,
+    ) -> SourceError<toml::de::Error> {
+        let byte = error.span().map_or(0, |span| span.start);
+        self.byte_offset_clamped(index, byte).error(error, "invalid TOML")
+    }
+
+    /// Turn a [`serde_yaml::Error`] for the content of an entry into a [`SourceError`]
+    /// at the reported location.
+    ///
+    /// Errors without a location point at the start of the content.
+    ///
+    /// # Panics
+    ///
+    /// This function will panic if the index does not belong to this map.
+    #[cfg(feature = "serde_yaml")]
+    #[track_caller]
+    pub fn yaml_source_error(
+        &self,
+        index: SourceIndex,
+        error: serde_yaml::Error,
+    ) -> SourceError<serde_yaml::Error> {
+        let byte = error.location().map_or(0, |location| location.index());
+        self.byte_offset_clamped(index, byte).error(error, "invalid YAML")
+    }
+
+    /// Turn a [`pest::error::Error`] for the content of an entry into a [`SourceError`]
+    /// at the start of the reported location.
+    ///
+    /// # Panics
+    ///
+    /// This function will panic if the index does not belong to this map.
+    #[cfg(feature = "pest")]
+    #[track_caller]
+    pub fn pest_source_error<R>(
+        &self,
+        index: SourceIndex,
+        error: pest::error::Error<R>,
+    ) -> SourceError<pest::error::Error<R>>
+    where
+        R: pest::RuleType,
+    {
+        let byte = match error.location {
+            pest::error::InputLocation::Pos(byte) => byte,
+            pest::error::InputLocation::Span((start, _)) => start,
+        };
+        self.byte_offset_clamped(index, byte).error(error, "invalid syntax")
+    }
+
+    /// An offset at the given byte, moved back to the content end or the previous
+    /// character boundary if necessary.
+    #[track_caller]
+    fn byte_offset_clamped(&self, index: SourceIndex, byte: usize) -> Offset {
+        let content = self.content(index);
+        let mut byte = byte.min(content.len());
+        while !content.is_char_boundary(byte) {
+            byte -= 1;
+        }
+        self.input(index).skip(byte).offset()
+    }
+}
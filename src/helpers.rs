@@ -1,3 +1,5 @@
+use crate::Spanned;
+
 
 /// Normalilze content for tests and whitespace-sensitive inputs.
 ///
@@ -39,4 +41,168 @@ pub fn normalize(content: &str) -> String {
         normalized.push('\n');
     }
     normalized
-}
\ No newline at end of file
+}
+
+/// The Levenshtein distance between two strings, counted in [`char`]s.
+pub fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut row: Vec<usize> = (0..=b.len()).collect();
+    for (i, a_char) in a.chars().enumerate() {
+        let mut diagonal = row[0];
+        row[0] = i + 1;
+        for (j, &b_char) in b.iter().enumerate() {
+            let substituted = diagonal + usize::from(a_char != b_char);
+            diagonal = row[j + 1];
+            row[j + 1] = substituted.min(diagonal + 1).min(row[j] + 1);
+        }
+    }
+    row[b.len()]
+}
+
+/// Find the candidates most similar to a misspelled name, for "did you mean" hints.
+///
+/// Candidates are accepted if their [`edit_distance`] to the name is at most a third of
+/// the name's length, but at least one. The result is ordered by distance, keeping the
+/// order of the candidates for equal distances. Only the first occurrence of each
+/// candidate value is kept, and exact matches are skipped.
+///
+/// # Examples
+///
+/// ```rust
+/// # use src_ctx::{SourceMap, Spanned, suggest_similar};
+/// # let mut map = SourceMap::new();
+/// # let index = map.insert_anonymous("count counter total".into());
+/// let candidates = [(0, 5), (6, 13), (14, 19)].map(|(start, end)| {
+///     let span = map.span_from_range(index, start..end).unwrap();
+///     Spanned::new(span, map.span_str(span))
+/// });
+/// let suggestions = suggest_similar("cont", candidates);
+/// assert_eq!(suggestions.iter().map(|s| *s.value()).collect::<Vec<_>>(), ["count"]);
+/// ```
+pub fn suggest_similar<'a, I>(name: &str, candidates: I) -> Vec<Spanned<&'a str>>
+where
+    I: IntoIterator<Item = Spanned<&'a str>>,
+{
+    let max_distance = (name.chars().count() / 3).max(1);
+    let mut suggestions: Vec<(usize, Spanned<&'a str>)> = Vec::new();
+    for candidate in candidates {
+        let value = *candidate.value();
+        if value == name || suggestions.iter().any(|(_, prev)| *prev.value() == value) {
+            continue;
+        }
+        if value.chars().count().abs_diff(name.chars().count()) > max_distance {
+            continue;
+        }
+        let distance = edit_distance(name, value);
+        if distance <= max_distance {
+            suggestions.push((distance, candidate));
+        }
+    }
+    suggestions.sort_by_key(|(distance, _)| *distance);
+    suggestions.into_iter().map(|(_, candidate)| candidate).collect()
+}
+
+/// A builder for "expected one of ..., found ..." messages in parsers.
+///
+/// Expected alternatives are listed in insertion order, with duplicates ignored. Past
+/// the [maximum](Self::with_max_listed), remaining alternatives are elided.
+///
+/// # Examples
+///
+/// ```rust
+/// # use src_ctx::ExpectedTokens;
+/// let mut expected = ExpectedTokens::new();
+/// expected.insert_literal(",");
+/// expected.insert_literal(";");
+/// expected.insert("an identifier");
+/// assert_eq!(
+///     expected.message(Some("}")),
+///     "expected one of `,`, `;`, or an identifier, found `}`",
+/// );
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct ExpectedTokens {
+    alternatives: Vec<Box<str>>,
+    max_listed: usize,
+}
+
+impl Default for ExpectedTokens {
+    fn default() -> Self {
+        Self { alternatives: Vec::new(), max_listed: 8 }
+    }
+}
+
+impl ExpectedTokens {
+    /// Construct an empty set listing at most 8 alternatives.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set the maximum number of listed alternatives.
+    ///
+    /// If more alternatives are expected, the listed ones are followed by `...`. A
+    /// maximum of zero only reports the number of alternatives.
+    pub fn with_max_listed(mut self, max_listed: usize) -> Self {
+        self.max_listed = max_listed;
+        self
+    }
+
+    /// The maximum number of listed alternatives.
+    pub fn max_listed(&self) -> usize {
+        self.max_listed
+    }
+
+    /// Add a description of an expected alternative, like `an identifier`.
+    pub fn insert(&mut self, description: &str) {
+        if !self.alternatives.iter().any(|prev| **prev == *description) {
+            self.alternatives.push(description.into());
+        }
+    }
+
+    /// Add an expected literal token, which is shown in backticks.
+    pub fn insert_literal(&mut self, token: &str) {
+        self.insert(&format!("`{token}`"));
+    }
+
+    /// An iterator over the descriptions of all expected alternatives, in insertion
+    /// order.
+    pub fn iter(&self) -> impl Iterator<Item = &str> + '_ {
+        self.alternatives.iter().map(|alternative| &**alternative)
+    }
+
+    /// The number of expected alternatives.
+    pub fn len(&self) -> usize {
+        self.alternatives.len()
+    }
+
+    /// Whether no alternatives are expected.
+    pub fn is_empty(&self) -> bool {
+        self.alternatives.is_empty()
+    }
+
+    /// Build the message for the text that was found instead, or `None` at the end of
+    /// the input.
+    ///
+    /// The found text is shown in backticks. Without any expected alternatives, the
+    /// message only reports the found text as unexpected.
+    pub fn message(&self, found: Option<&str>) -> String {
+        let found = match found {
+            Some(found) => format!("`{found}`"),
+            None => "end of input".into(),
+        };
+        let expected = match self.alternatives.as_slice() {
+            [] => return format!("unexpected {found}"),
+            [single] => single.to_string(),
+            _ if self.max_listed == 0 => {
+                format!("one of {} alternatives", self.alternatives.len())
+            },
+            alternatives if alternatives.len() > self.max_listed => {
+                let listed = alternatives[..self.max_listed].join(", ");
+                format!("one of {listed}, ...")
+            },
+            [first, second] => format!("one of {first} or {second}"),
+            [init @ .., last] => format!("one of {}, or {last}", init.join(", ")),
+        };
+        format!("expected {expected}, found {found}")
+    }
+}
@@ -0,0 +1,112 @@
+use crate::{Input, Offset, SourceMap, Span};
+
+
+impl<'src> Input<'src> {
+    /// The leading spaces and tabs of the input.
+    #[must_use]
+    pub fn indentation(&self) -> Self {
+        let content = self.content();
+        self.truncate(content.len() - content.trim_start_matches([' ', '\t']).len())
+    }
+
+    /// The display width of the leading spaces and tabs of the input.
+    ///
+    /// Spaces advance by one column, tabs advance to the next multiple of `tab_width`.
+    pub fn indentation_width(&self, tab_width: usize) -> usize {
+        self.indentation().content().chars().fold(0, |width, c| match c {
+            '\t' if tab_width > 0 => (width / tab_width + 1) * tab_width,
+            '\t' => width,
+            _ => width + 1,
+        })
+    }
+}
+
+impl SourceMap {
+    /// The longest run of leading spaces and tabs shared by all lines in a [`Span`].
+    ///
+    /// Lines only consisting of whitespace are not considered. Tabs and spaces are
+    /// compared literally, so lines indented with different characters share no
+    /// indentation.
+    ///
+    /// # Panics
+    ///
+    /// This function will panic if the span does not belong to this map.
+    #[track_caller]
+    pub fn common_indentation(&self, span: Span) -> &str {
+        let content = self.span_str(span);
+        let mut common: Option<&str> = None;
+        for line in content.split('\n') {
+            if line.trim().is_empty() {
+                continue;
+            }
+            let indent = &line[..(line.len() - line.trim_start_matches([' ', '\t']).len())];
+            common = Some(match common {
+                None => indent,
+                Some(common) => {
+                    let len = common.bytes().zip(indent.bytes()).take_while(|(a, b)| a == b).count();
+                    &common[..len]
+                },
+            });
+        }
+        common.unwrap_or("")
+    }
+
+    /// Produce a [`Dedented`] view of a [`Span`] with the
+    /// [common indentation](Self::common_indentation) removed from every line.
+    ///
+    /// # Panics
+    ///
+    /// This function will panic if the span does not belong to this map.
+    #[track_caller]
+    pub fn dedent(&self, span: Span) -> Dedented {
+        let indent = self.common_indentation(span);
+        let content = self.span_str(span);
+        let mut dedented = Dedented { content: String::new(), segments: Vec::new() };
+        let mut line_start = span.start().byte();
+        for line in content.split_inclusive('\n') {
+            let removed = line.bytes().zip(indent.bytes()).take_while(|(a, b)| a == b).count();
+            let removed = removed.min(line.trim_end_matches(['\r', '\n']).len());
+            dedented.segments.push((
+                dedented.content.len(),
+                Offset::new(span.source_index(), line_start + removed),
+            ));
+            dedented.content.push_str(&line[removed..]);
+            line_start += line.len();
+        }
+        if dedented.segments.is_empty() {
+            dedented.segments.push((0, span.start()));
+        }
+        dedented
+    }
+}
+
+/// A view of source content with indentation removed, produced by
+/// [`SourceMap::dedent`].
+///
+/// Positions in the dedented content can be mapped back to their [`Offset`] in the
+/// original content.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Dedented {
+    content: String,
+    segments: Vec<(usize, Offset)>,
+}
+
+impl Dedented {
+    /// The dedented content.
+    pub fn content(&self) -> &str {
+        &self.content
+    }
+
+    /// Map a byte-position in the dedented content back to the original [`Offset`].
+    ///
+    /// # Panics
+    ///
+    /// This function will panic if the position exceeds the dedented content.
+    #[track_caller]
+    pub fn offset(&self, byte: usize) -> Offset {
+        assert!(byte <= self.content.len(), "position must not exceed dedented content");
+        let index = self.segments.partition_point(|(start, _)| *start <= byte) - 1;
+        let (start, offset) = self.segments[index];
+        Offset::new(offset.source_index(), offset.byte() + (byte - start))
+    }
+}
@@ -1,6 +1,6 @@
 use std::ops::Range;
 
-use crate::{SourceIndex, SourceError};
+use crate::{SourceIndex, SourceError, SourceMap};
 
 
 /// A position in a [`SourceMap`](crate::SourceMap) entry.
@@ -15,6 +15,19 @@ impl Offset {
         Self { source_index, byte }
     }
 
+    /// The offset at the end of the content associated with a [`SourceIndex`].
+    ///
+    /// Errors at this offset, like an unexpected end of input, are shown with the caret
+    /// one column past the last character of the content, including for empty contents.
+    ///
+    /// # Panics
+    ///
+    /// This function will panic if the index does not belong to the map.
+    #[track_caller]
+    pub fn end_of(map: &SourceMap, idx: SourceIndex) -> Self {
+        Self::new(idx, map.content(idx).len())
+    }
+
     /// The [`SourceIndex`] of the map entry this offset is associated with.
     pub fn source_index(&self) -> SourceIndex {
         self.source_index
@@ -57,6 +70,31 @@ impl Offset {
     pub fn error<E>(&self, error: E, offset_note: &'static str) -> SourceError<E> {
         SourceError::new(error, *self, offset_note)
     }
+
+    /// Construct an offset from a [`pest::Position`] produced by parsing the content of
+    /// the given source.
+    ///
+    /// The offset is [not validated](Span#unvalidated-positions).
+    #[cfg(feature = "pest")]
+    pub fn from_pest(source_index: SourceIndex, position: pest::Position<'_>) -> Self {
+        Self::new(source_index, position.pos())
+    }
+
+    /// Convert the offset into a [`text_size::TextSize`].
+    ///
+    /// Returns `None` if the position doesn't fit into a `u32`.
+    #[cfg(feature = "text-size")]
+    pub fn to_text_size(&self) -> Option<text_size::TextSize> {
+        text_size::TextSize::try_from(self.byte).ok()
+    }
+
+    /// Construct an offset from a [`text_size::TextSize`] in the given source.
+    ///
+    /// The offset is [not validated](Span#unvalidated-positions).
+    #[cfg(feature = "text-size")]
+    pub fn from_text_size(source_index: SourceIndex, size: text_size::TextSize) -> Self {
+        Self::new(source_index, size.into())
+    }
 }
 
 impl From<Span> for Offset {
@@ -68,6 +106,13 @@ impl From<Span> for Offset {
 /// A span of content in a [`SourceMap`](crate::SourceMap) entry.
 ///
 /// Spans are constructed from two [`Offset`]s with [`Offset::span`].
+///
+/// # Unvalidated positions
+///
+/// Offsets and spans constructed from raw byte-positions, like those of a [`CompactSpan`]
+/// or of other crates' parsers and lexers, are not validated against the content of their
+/// source. Using them with a [`SourceMap`](crate::SourceMap) will panic if they exceed
+/// the content.
 #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
 pub struct Span {
     offset: Offset,
@@ -112,6 +157,342 @@ impl Span {
     pub fn is_at_start(&self) -> bool {
         self.offset.is_at_start()
     }
+
+    /// Convert the span into a [`text_size::TextRange`].
+    ///
+    /// Returns `None` if the start or end of the span don't fit into a `u32`.
+    #[cfg(feature = "text-size")]
+    pub fn to_text_range(&self) -> Option<text_size::TextRange> {
+        self.to_compact().map(Into::into)
+    }
+
+    /// Construct a span from a [`text_size::TextRange`] in the given source.
+    ///
+    /// The span is [not validated](Span#unvalidated-positions).
+    #[cfg(feature = "text-size")]
+    pub fn from_text_range(source_index: SourceIndex, range: text_size::TextRange) -> Self {
+        CompactSpan::from(range).to_span(source_index)
+    }
+
+    /// Construct a span from a [`logos::Span`] produced by lexing the content of the
+    /// given source.
+    ///
+    /// The span is [not validated](Span#unvalidated-positions).
+    #[cfg(feature = "logos")]
+    pub fn from_logos(source_index: SourceIndex, span: logos::Span) -> Self {
+        Self::new(Offset::new(source_index, span.start), span.end - span.start)
+    }
+
+    /// Construct a span from a [`pest::Span`] produced by parsing the content of the
+    /// given source.
+    ///
+    /// The span is [not validated](Span#unvalidated-positions).
+    #[cfg(feature = "pest")]
+    pub fn from_pest(source_index: SourceIndex, span: pest::Span<'_>) -> Self {
+        Self::new(Offset::new(source_index, span.start()), span.end() - span.start())
+    }
+
+    /// Convert the span into a [`CompactSpan`] without its [`SourceIndex`].
+    ///
+    /// Returns `None` if the start or end of the span don't fit into a `u32`.
+    pub fn to_compact(&self) -> Option<CompactSpan> {
+        let start = u32::try_from(self.offset.byte).ok()?;
+        let byte_len = u32::try_from(self.byte_len).ok()?;
+        start.checked_add(byte_len)?;
+        Some(CompactSpan { start, byte_len })
+    }
+}
+
+impl From<Span> for Range<Offset> {
+    fn from(span: Span) -> Self {
+        span.start()..span.end()
+    }
+}
+
+impl TryFrom<(Offset, Offset)> for Span {
+    type Error = SpanOffsetsError;
+
+    /// Construct a [`Span`] from a start and end [`Offset`].
+    ///
+    /// Unlike [`Offset::span`], this does not accept an end before the start.
+    fn try_from((start, end): (Offset, Offset)) -> Result<Self, Self::Error> {
+        if start.source_index != end.source_index {
+            Err(SpanOffsetsError::DifferentSources)
+        } else if end.byte < start.byte {
+            Err(SpanOffsetsError::Reversed)
+        } else {
+            Ok(Span::new(start, end.byte - start.byte))
+        }
+    }
+}
+
+impl TryFrom<Range<Offset>> for Span {
+    type Error = SpanOffsetsError;
+
+    fn try_from(range: Range<Offset>) -> Result<Self, Self::Error> {
+        Self::try_from((range.start, range.end))
+    }
+}
+
+/// Errors that can occur while constructing a [`Span`] from a pair of [`Offset`]s.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SpanOffsetsError {
+    /// The offsets belong to different sources.
+    DifferentSources,
+    /// The end offset lies before the start offset.
+    Reversed,
+}
+
+impl std::error::Error for SpanOffsetsError {}
+
+impl std::fmt::Display for SpanOffsetsError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SpanOffsetsError::DifferentSources => write!(f, "Span offsets belong to different sources"),
+            SpanOffsetsError::Reversed => write!(f, "Span end offset lies before the start offset"),
+        }
+    }
+}
+
+/// A [`Span`] without its [`SourceIndex`], for when the source is implied by context.
+///
+/// This is intended for syntax trees of a single source, where storing the
+/// [`SourceIndex`] in every span would be redundant. Compact spans are produced with
+/// [`Span::to_compact`] and turned back into full spans with
+/// [`CompactSpan::to_span`].
+///
+/// A compact span is guaranteed to be 8 bytes in size, consisting of a `u32` start
+/// position followed by a `u32` length, with an alignment of 4 bytes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
+#[repr(C)]
+pub struct CompactSpan {
+    start: u32,
+    byte_len: u32,
+}
+
+impl CompactSpan {
+    /// Construct a compact span from a start position and a length in bytes.
+    ///
+    /// # Panics
+    ///
+    /// This function will panic if the end of the span doesn't fit into a `u32`.
+    #[track_caller]
+    pub fn new(start: u32, byte_len: u32) -> Self {
+        assert!(start.checked_add(byte_len).is_some(), "compact span end must fit into u32");
+        Self { start, byte_len }
+    }
+
+    /// The byte-position of the start of the span.
+    pub fn start(&self) -> u32 {
+        self.start
+    }
+
+    /// The byte-position of the end of the span.
+    pub fn end(&self) -> u32 {
+        self.start + self.byte_len
+    }
+
+    /// The length of the span in bytes.
+    pub fn byte_len(&self) -> u32 {
+        self.byte_len
+    }
+
+    /// The range of the span in bytes.
+    pub fn byte_range(&self) -> Range<usize> {
+        (self.start as usize)..(self.end() as usize)
+    }
+
+    /// Turn the compact span into a full [`Span`] in the given source.
+    ///
+    /// The span is [not validated](Span#unvalidated-positions).
+    pub fn to_span(&self, source_index: SourceIndex) -> Span {
+        Span::new(Offset::new(source_index, self.start as usize), self.byte_len as usize)
+    }
+}
+
+#[cfg(feature = "text-size")]
+impl From<CompactSpan> for text_size::TextRange {
+    fn from(span: CompactSpan) -> Self {
+        text_size::TextRange::at(span.start.into(), span.byte_len.into())
+    }
+}
+
+#[cfg(feature = "text-size")]
+impl From<text_size::TextRange> for CompactSpan {
+    fn from(range: text_size::TextRange) -> Self {
+        Self { start: range.start().into(), byte_len: range.len().into() }
+    }
+}
+
+#[cfg(feature = "codespan")]
+impl From<CompactSpan> for codespan::Span {
+    fn from(span: CompactSpan) -> Self {
+        codespan::Span::new(span.start(), span.end())
+    }
+}
+
+#[cfg(feature = "codespan")]
+impl From<codespan::Span> for CompactSpan {
+    fn from(span: codespan::Span) -> Self {
+        Self::new(span.start().0, span.end().0 - span.start().0)
+    }
+}
+
+#[cfg(feature = "codespan")]
+impl TryFrom<Span> for codespan::Span {
+    type Error = std::num::TryFromIntError;
+
+    fn try_from(span: Span) -> Result<Self, Self::Error> {
+        let start = u32::try_from(span.start().byte())?;
+        let end = u32::try_from(span.end().byte())?;
+        Ok(codespan::Span::new(start, end))
+    }
+}
+
+#[cfg(feature = "codespan")]
+impl Span {
+    /// Construct a span from a [`codespan::Span`] in the given source.
+    ///
+    /// The span is [not validated](Span#unvalidated-positions).
+    pub fn from_codespan(source_index: SourceIndex, span: codespan::Span) -> Self {
+        CompactSpan::from(span).to_span(source_index)
+    }
+}
+
+#[cfg(feature = "codemap")]
+impl Span {
+    /// Convert the span into a [`codemap::Span`] in the given [`codemap::File`].
+    ///
+    /// # Panics
+    ///
+    /// This function will panic if the span exceeds the file.
+    #[track_caller]
+    pub fn to_codemap(&self, file: &codemap::File) -> codemap::Span {
+        file.span.subspan(self.start().byte() as u64, self.end().byte() as u64)
+    }
+
+    /// Construct a span from a [`codemap::Span`] in the given [`codemap::File`] and
+    /// source.
+    ///
+    /// The span is [not validated](Span#unvalidated-positions).
+    ///
+    /// # Panics
+    ///
+    /// This function will panic if the span does not lie in the file.
+    #[track_caller]
+    pub fn from_codemap(source_index: SourceIndex, file: &codemap::File, span: codemap::Span) -> Self {
+        assert!(file.span.contains(span), "codemap span must lie in file");
+        let start = (span.low() - file.span.low()) as usize;
+        Span::new(Offset::new(source_index, start), span.len() as usize)
+    }
+}
+
+/// A value associated with the [`Span`] it was parsed from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Spanned<T> {
+    span: Span,
+    value: T,
+}
+
+impl<T> Spanned<T> {
+    /// Associate a value with a [`Span`].
+    pub fn new(span: Span, value: T) -> Self {
+        Self { span, value }
+    }
+
+    /// The [`Span`] the value is associated with.
+    pub fn span(&self) -> Span {
+        self.span
+    }
+
+    /// The encapsulated value.
+    pub fn value(&self) -> &T {
+        &self.value
+    }
+
+    /// Mutable access to the encapsulated value.
+    pub fn value_mut(&mut self) -> &mut T {
+        &mut self.value
+    }
+
+    /// Discard the span and unwrap the encapsulated value.
+    pub fn into_value(self) -> T {
+        self.value
+    }
+
+    /// Map the encapsulated value to a new value and/or type, keeping the span.
+    pub fn map<U, F>(self, map_value: F) -> Spanned<U>
+    where
+        F: FnOnce(T) -> U,
+    {
+        Spanned { span: self.span, value: map_value(self.value) }
+    }
+
+    /// Construct a spanned value from a [`serde_spanned::Spanned`] deserialized from
+    /// the content of the given source.
+    ///
+    /// The span is [not validated](Span#unvalidated-positions).
+    #[cfg(feature = "serde_spanned")]
+    pub fn from_serde_spanned(source_index: SourceIndex, spanned: serde_spanned::Spanned<T>) -> Self {
+        let range = spanned.span();
+        let span = Span::new(Offset::new(source_index, range.start), range.end - range.start);
+        Self::new(span, spanned.into_inner())
+    }
+}
+
+/// A position in the global offset space of a [`SourceMap`](crate::SourceMap).
+///
+/// Global offsets are a compact alternative to [`Offset`] values, taking up only
+/// four bytes. They are produced with
+/// [`SourceMap::global_offset`](crate::SourceMap::global_offset) and resolved with
+/// [`SourceMap::resolve_global_offset`](crate::SourceMap::resolve_global_offset).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct GlobalOffset(u32);
+
+impl GlobalOffset {
+    /// Construct a global offset from its raw position.
+    pub fn from_u32(position: u32) -> Self {
+        Self(position)
+    }
+
+    /// The raw position in the global offset space.
+    pub fn to_u32(self) -> u32 {
+        self.0
+    }
+}
+
+/// A span in the global offset space of a [`SourceMap`](crate::SourceMap).
+///
+/// Global spans are a compact alternative to [`Span`] values, taking up only
+/// eight bytes. They are produced with
+/// [`SourceMap::global_span`](crate::SourceMap::global_span) and resolved with
+/// [`SourceMap::resolve_global_span`](crate::SourceMap::resolve_global_span).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct GlobalSpan {
+    start: u32,
+    byte_len: u32,
+}
+
+impl GlobalSpan {
+    /// Construct a global span from a start position and a length in bytes.
+    pub fn new(start: GlobalOffset, byte_len: u32) -> Self {
+        Self { start: start.0, byte_len }
+    }
+
+    /// The [`GlobalOffset`] at the start of the span.
+    pub fn start(&self) -> GlobalOffset {
+        GlobalOffset(self.start)
+    }
+
+    /// The [`GlobalOffset`] at the end of the span.
+    pub fn end(&self) -> GlobalOffset {
+        GlobalOffset(self.start + self.byte_len)
+    }
+
+    /// The length of the span in bytes.
+    pub fn byte_len(&self) -> u32 {
+        self.byte_len
+    }
 }
 
 /// An input traversal wrapper for contents in a [`SourceMap`](crate::SourceMap).
@@ -120,13 +501,14 @@ impl Span {
 #[derive(Debug, Clone)]
 pub struct Input<'src> {
     source_index: SourceIndex,
+    source: &'src str,
     content: &'src str,
     byte: usize,
 }
 
 impl<'src> Input<'src> {
     pub(crate) fn new(source_index: SourceIndex, content: &'src str) -> Self {
-        Self { source_index, content, byte: 0 }
+        Self { source_index, source: content, content, byte: 0 }
     }
 
     /// The byte-length of the remaining input content.
@@ -163,6 +545,7 @@ impl<'src> Input<'src> {
     pub fn skip(&self, byte_len: usize) -> Self {
         Self {
             source_index: self.source_index,
+            source: self.source,
             content: &self.content[byte_len..],
             byte: self.byte + byte_len,
         }
@@ -173,11 +556,49 @@ impl<'src> Input<'src> {
     pub fn truncate(&self, byte_len: usize) -> Self {
         Self {
             source_index: self.source_index,
+            source: self.source,
             content: &self.content[..byte_len],
             byte: self.byte,
         }
     }
 
+    /// Remove a number of bytes from the end of the input content.
+    ///
+    /// # Panics
+    ///
+    /// This function will panic if the number of bytes exceeds the input content, or
+    /// the new end is not on a character boundary.
+    #[must_use]
+    #[track_caller]
+    pub fn truncate_end(&self, byte_len: usize) -> Self {
+        let len = self.len().checked_sub(byte_len).expect("truncated past start of input");
+        self.truncate(len)
+    }
+
+    /// Skip all leading whitespace.
+    #[must_use]
+    pub fn trim_start(&self) -> Self {
+        self.skip(self.len() - self.content.trim_start().len())
+    }
+
+    /// Remove all trailing whitespace, keeping the start offset.
+    #[must_use]
+    pub fn trim_end(&self) -> Self {
+        self.truncate(self.content.trim_end().len())
+    }
+
+    /// Remove all leading and trailing whitespace.
+    #[must_use]
+    pub fn trim(&self) -> Self {
+        self.trim_start().trim_end()
+    }
+
+    /// Determine if the input content ends with a specific string.
+    #[must_use]
+    pub fn ends_with(&self, s: &str) -> bool {
+        self.content.ends_with(s)
+    }
+
     /// Split the input into two parts at a given byte position.
     #[must_use]
     pub fn split(&self, byte_len: usize) -> (Self, Self) {
@@ -190,20 +611,340 @@ impl<'src> Input<'src> {
         self.content.chars().next()
     }
 
+    /// Move the start of the input back by a number of bytes, into the already consumed
+    /// part of the content.
+    ///
+    /// # Panics
+    ///
+    /// This function will panic if the number of bytes exceeds the consumed content, or
+    /// the new start is not on a character boundary.
+    #[must_use]
+    #[track_caller]
+    pub fn skip_back(&self, byte_len: usize) -> Self {
+        let byte = self.byte.checked_sub(byte_len).expect("skipped back past start of content");
+        Self {
+            source_index: self.source_index,
+            source: self.source,
+            content: &self.source[byte..(self.byte + self.content.len())],
+            byte,
+        }
+    }
+
+    /// The [`char`] directly before the current position in the already consumed part of
+    /// the content.
+    #[must_use]
+    pub fn prev_char(&self) -> Option<char> {
+        self.source[..self.byte].chars().next_back()
+    }
+
+    /// Search the already consumed part of the content backwards for a [`char`] matching
+    /// a predicate.
+    ///
+    /// Returns the input starting at the last matching character before the current
+    /// position. This is useful for error recovery, like pointing past the end of the
+    /// previous token with `input.rfind(|c| !c.is_whitespace())?.skip_chars(1)`.
+    #[must_use]
+    pub fn rfind<F>(&self, predicate: F) -> Option<Self>
+    where
+        F: FnMut(char) -> bool,
+    {
+        let byte = self.source[..self.byte].rfind(predicate)?;
+        Some(self.skip_back(self.byte - byte))
+    }
+
     /// Try to skip a specific [`char`] in the input.
     #[must_use]
     pub fn skip_char(&self, c: char) -> Option<Self> {
         self.content.starts_with(c).then(|| self.skip(c.len_utf8()))
     }
 
+    /// Try to skip a specific string in the input.
+    #[must_use]
+    pub fn skip_str(&self, s: &str) -> Option<Self> {
+        self.content.starts_with(s).then(|| self.skip(s.len()))
+    }
+
+    /// Try to skip a keyword in the input.
+    ///
+    /// This works like [`skip_str`](Self::skip_str), but only matches if the keyword is
+    /// not directly followed by a character for which `is_ident_char` returns `true`.
+    /// This prevents `if` from matching the start of `iffy`.
+    #[must_use]
+    pub fn skip_keyword<F>(&self, word: &str, is_ident_char: F) -> Option<Self>
+    where
+        F: FnOnce(char) -> bool,
+    {
+        let rest = self.skip_str(word)?;
+        match rest.char() {
+            Some(c) if is_ident_char(c) => None,
+            _ => Some(rest),
+        }
+    }
+
     /// Try to consume any [`char`] in the input.
     #[must_use]
     pub fn take_char(&self) -> Option<(char, Self)> {
         self.char().map(|c| (c, self.skip(c.len_utf8())))
     }
 
+    /// Try to skip a number of [`char`]s in the input.
+    ///
+    /// Returns `None` if fewer characters remain.
+    #[must_use]
+    pub fn skip_chars(&self, count: usize) -> Option<Self> {
+        self.take_chars(count).map(|(_, rest)| rest)
+    }
+
+    /// Try to consume a number of [`char`]s in the input.
+    ///
+    /// Returns the consumed input and the rest, or `None` if fewer characters remain.
+    #[must_use]
+    pub fn take_chars(&self, count: usize) -> Option<(Self, Self)> {
+        let byte_len = match count.checked_sub(1) {
+            None => 0,
+            Some(last) => {
+                let (index, c) = self.content.char_indices().nth(last)?;
+                index + c.len_utf8()
+            },
+        };
+        Some(self.split(byte_len))
+    }
+
+    /// The input up to the next newline or the end of the input, excluding the newline.
+    #[must_use]
+    pub fn rest_of_line(&self) -> Self {
+        self.truncate(self.content.find('\n').unwrap_or(self.len()))
+    }
+
+    /// Skip past the next newline, or to the end of the input if there is none.
+    #[must_use]
+    pub fn skip_line(&self) -> Self {
+        self.skip(self.content.find('\n').map_or(self.len(), |index| index + 1))
+    }
+
+    /// Try to consume a line, including its newline if there is one.
+    ///
+    /// Returns the line excluding the newline, followed by the remaining input. Returns
+    /// `None` if the input is empty.
+    #[must_use]
+    pub fn take_line(&self) -> Option<(Self, Self)> {
+        (!self.is_empty()).then(|| (self.rest_of_line(), self.skip_line()))
+    }
+
+    /// Try to consume an identifier in the input.
+    ///
+    /// Identifiers start with a character for which [`is_ident_start`] returns `true`,
+    /// followed by any number of characters for which [`is_ident_continue`] returns
+    /// `true`. Returns the identifier followed by the remaining input.
+    #[must_use]
+    pub fn take_ident(&self) -> Option<(Self, Self)> {
+        self.take_ident_with(is_ident_start, is_ident_continue)
+    }
+
+    /// Try to consume an identifier with custom character classes.
+    ///
+    /// This works like [`take_ident`](Self::take_ident), but allows languages to accept
+    /// additional characters, like `$` or `-`.
+    #[must_use]
+    pub fn take_ident_with<S, C>(&self, is_start: S, mut is_continue: C) -> Option<(Self, Self)>
+    where
+        S: FnOnce(char) -> bool,
+        C: FnMut(char) -> bool,
+    {
+        let (first, rest) = self.take_char()?;
+        if !is_start(first) {
+            return None;
+        }
+        let byte_len = rest.content.find(|c| !is_continue(c)).unwrap_or(rest.len());
+        Some(self.split(first.len_utf8() + byte_len))
+    }
+
+    /// Try to consume a quoted string literal according to a [`StringSyntax`].
+    ///
+    /// Returns `None` if the input doesn't start with the quote character. Otherwise
+    /// returns the unescaped value followed by the input after the closing quote.
+    ///
+    /// # Errors
+    ///
+    /// A [`StringLiteralError::Unterminated`] is returned at the end of the input if the
+    /// literal is not closed, carrying the opening quote as context offset. An
+    /// [`StringLiteralError::InvalidEscape`] is returned at the start of an escape
+    /// sequence that is not part of the syntax.
+    pub fn take_string_literal(
+        &self,
+        syntax: StringSyntax,
+    ) -> Option<Result<(String, Self), SourceError<StringLiteralError>>> {
+        let mut rest = self.skip_char(syntax.quote)?;
+        let mut value = String::new();
+        Some(loop {
+            let Some((c, next)) = rest.take_char() else {
+                break Err(rest.error(StringLiteralError::Unterminated, "expected closing quote")
+                    .with_context(self.offset()));
+            };
+            if c == syntax.quote {
+                break Ok((value, next));
+            }
+            if Some(c) == syntax.escape {
+                let Some((escaped, after)) = next.take_char() else {
+                    break Err(next.error(StringLiteralError::Unterminated, "expected closing quote")
+                        .with_context(self.offset()));
+                };
+                let replacement = if escaped == syntax.quote || Some(escaped) == syntax.escape {
+                    Some(escaped)
+                } else {
+                    syntax.escapes.iter().find(|(from, _)| *from == escaped).map(|(_, to)| *to)
+                };
+                let Some(replacement) = replacement else {
+                    break Err(rest.error(
+                        StringLiteralError::InvalidEscape(escaped),
+                        "invalid escape sequence",
+                    ));
+                };
+                value.push(replacement);
+                rest = after;
+                continue;
+            }
+            value.push(c);
+            rest = next;
+        })
+    }
+
     /// Construct a [`SourceError`] for the current input position.
     pub fn error<E>(&self, error: E, offset_note: &'static str) -> SourceError<E> {
         SourceError::new(error, self.offset(), offset_note)
     }
+
+    /// Construct a [`SourceError`] covering a number of bytes from the current input
+    /// position.
+    pub fn error_len<E>(&self, byte_len: usize, error: E, offset_note: &'static str) -> SourceError<E> {
+        self.error(error, offset_note).with_byte_len(byte_len)
+    }
+
+    /// Construct a [`SourceError`] covering a [`Span`] in the source of the input.
+    ///
+    /// # Panics
+    ///
+    /// This function will panic if the span belongs to a different source.
+    #[track_caller]
+    pub fn error_span<E>(&self, span: Span, error: E, offset_note: &'static str) -> SourceError<E> {
+        assert_eq!(
+            span.source_index(),
+            self.offset().source_index(),
+            "error span must belong to input source",
+        );
+        SourceError::from_span(error, span, offset_note)
+    }
+}
+
+/// Determine if a character can start an identifier.
+///
+/// This is the case for `_` and characters with the Unicode `XID_Start` property.
+pub fn is_ident_start(c: char) -> bool {
+    if c.is_ascii() {
+        c.is_ascii_alphabetic() || c == '_'
+    } else {
+        unicode_ident::is_xid_start(c)
+    }
+}
+
+/// Determine if a character can continue an identifier.
+///
+/// This is the case for characters with the Unicode `XID_Continue` property, which
+/// includes `_` and digits.
+pub fn is_ident_continue(c: char) -> bool {
+    if c.is_ascii() {
+        c.is_ascii_alphanumeric() || c == '_'
+    } else {
+        unicode_ident::is_xid_continue(c)
+    }
+}
+
+/// The syntax of string literals consumed with [`Input::take_string_literal`].
+///
+/// The [`Default`] implementation returns [`StringSyntax::DOUBLE_QUOTED`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct StringSyntax {
+    quote: char,
+    escape: Option<char>,
+    escapes: &'static [(char, char)],
+}
+
+impl StringSyntax {
+    /// Double-quoted literals with `\` escapes for `\n`, `\r`, `\t`, and `\0`, in
+    /// addition to the quote and the escape character themselves.
+    pub const DOUBLE_QUOTED: Self = Self {
+        quote: '"',
+        escape: Some('\\'),
+        escapes: &[('n', '\n'), ('r', '\r'), ('t', '\t'), ('0', '\0')],
+    };
+
+    /// Construct a syntax for literals with the given quote and no escape sequences.
+    pub fn new(quote: char) -> Self {
+        Self { quote, escape: None, escapes: &[] }
+    }
+
+    /// Use a different quote character.
+    pub fn with_quote(mut self, quote: char) -> Self {
+        self.quote = quote;
+        self
+    }
+
+    /// The quote character opening and closing literals.
+    pub fn quote(&self) -> char {
+        self.quote
+    }
+
+    /// Set the character starting escape sequences, if any.
+    ///
+    /// The escape character followed by the quote or the escape character itself
+    /// always produces that character.
+    pub fn with_escape(mut self, escape: Option<char>) -> Self {
+        self.escape = escape;
+        self
+    }
+
+    /// The character starting escape sequences, if any.
+    pub fn escape(&self) -> Option<char> {
+        self.escape
+    }
+
+    /// Set the additional escape sequences as pairs of the character following the
+    /// escape character and its replacement.
+    pub fn with_escapes(mut self, escapes: &'static [(char, char)]) -> Self {
+        self.escapes = escapes;
+        self
+    }
+
+    /// The additional escape sequences.
+    pub fn escapes(&self) -> &'static [(char, char)] {
+        self.escapes
+    }
+}
+
+impl Default for StringSyntax {
+    fn default() -> Self {
+        Self::DOUBLE_QUOTED
+    }
+}
+
+/// Errors that can occur while consuming a string literal with
+/// [`Input::take_string_literal`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StringLiteralError {
+    /// The input ended before the closing quote.
+    Unterminated,
+    /// The escape character was followed by a character not forming a valid escape
+    /// sequence.
+    InvalidEscape(char),
+}
+
+impl std::error::Error for StringLiteralError {}
+
+impl std::fmt::Display for StringLiteralError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            StringLiteralError::Unterminated => write!(f, "Unterminated string literal"),
+            StringLiteralError::InvalidEscape(c) => write!(f, "Invalid escape sequence `{c}`"),
+        }
+    }
 }
@@ -0,0 +1,73 @@
+use std::collections::HashMap;
+
+use crate::{Span, SourceMap};
+
+
+/// A table of interned [`Span`] values belonging to a single [`SourceMap`].
+///
+/// Interning produces small [`SpanId`] handles that can be stored instead of full
+/// spans. Interning the same span multiple times produces the same handle, which
+/// makes this useful for spans that are repeated often, like those of desugared or
+/// generated nodes.
+#[derive(Debug, Clone)]
+pub struct SpanInterner {
+    map_id: u32,
+    spans: Vec<Span>,
+    indices: HashMap<Span, u32>,
+}
+
+impl SpanInterner {
+    /// Construct an empty interner for spans of the given [`SourceMap`].
+    pub fn new(map: &SourceMap) -> Self {
+        Self { map_id: map.id(), spans: Vec::new(), indices: HashMap::new() }
+    }
+
+    /// Intern a [`Span`], returning its [`SpanId`].
+    ///
+    /// # Panics
+    ///
+    /// This function will panic if the span does not belong to the map of the
+    /// interner, or if the maximum number of interned spans is exceeded.
+    #[track_caller]
+    pub fn intern(&mut self, span: Span) -> SpanId {
+        assert_eq!(self.map_id, span.source_index().map_id, "span must belong to interner map");
+        if let Some(index) = self.indices.get(&span).copied() {
+            return SpanId(index);
+        }
+        let index: u32 = self.spans.len().try_into().expect("maximum interner size exceeded");
+        self.spans.push(span);
+        self.indices.insert(span, index);
+        SpanId(index)
+    }
+
+    /// Look up the [`Span`] of a [`SpanId`].
+    ///
+    /// # Panics
+    ///
+    /// This function will panic if the handle was not produced by this interner.
+    #[track_caller]
+    pub fn span(&self, id: SpanId) -> Span {
+        *self.spans.get(id.0 as usize).expect("span id must belong to interner")
+    }
+
+    /// The number of interned spans.
+    pub fn len(&self) -> usize {
+        self.spans.len()
+    }
+
+    /// Whether no spans have been interned yet.
+    pub fn is_empty(&self) -> bool {
+        self.spans.is_empty()
+    }
+}
+
+/// A handle for a [`Span`] in a [`SpanInterner`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct SpanId(u32);
+
+impl SpanId {
+    /// The raw index of the handle in its interner.
+    pub fn to_u32(self) -> u32 {
+        self.0
+    }
+}
@@ -13,15 +13,95 @@
 //! * You can also construct [`ContextError`] values with multiple error origins by passing
 //!   [`ContextErrorOrigin`] values to [`ContextError::with_origins`] to build errors that
 //!   involve multiple origins, like conflicts.
+//! * Other lines of an origin's source can be marked by attaching [`ContextErrorAnnotation`]
+//!   values created with [`SourceMap::context_error_annotation`].
+//! * Contents kept in other storages can be used by implementing [`SourceProvider`],
+//!   which [`SourceError::into_context_error`] resolves errors through.
+//! * Binary contents can be stored in a [`ByteSourceMap`] and traversed with [`ByteInput`]
+//!   wrappers, sharing the same offset and error types.
+//! * The output can be customized by passing [`DisplayOptions`] to
+//!   [`ContextError::display_with_options`].
+//! * Errors can be shown as friendly, prose-oriented reports with [`FriendlyReport`].
+//! * Batches of errors can be emitted in machine-readable formats like checkstyle XML
+//!   with [`display_checkstyle`], as single lines with [`display_short`], or as Vim
+//!   quickfix lines with [`display_quickfix`]. An entire source can be shown with all
+//!   of its errors interleaved with [`display_annotated_source`].
+//! * Findings can be silenced with markers in the sources, which are collected by
+//!   [`Suppressions::scan`].
+//! * With the `test-util` feature, rendered diagnostics for a directory of fixtures can
+//!   be compared against expected outputs with [`Fixtures`].
 
 pub use map::*;
 pub use error::*;
 pub use input::*;
 pub use helpers::*;
+pub use options::*;
+pub use bytes::*;
+pub use emit::*;
+pub use interner::*;
+pub use span_set::*;
+pub use span_tree::*;
+pub use search::*;
+pub use codec::*;
+pub use indent::*;
+pub use location::*;
+pub use suppress::*;
+pub use preprocess::*;
+pub use provider::*;
+pub use report::*;
+#[cfg(feature = "test-util")]
+pub use fixtures::*;
+#[cfg(feature = "embed")]
+pub use src_ctx_macros::embed_sources;
+#[cfg(feature = "insta")]
+#[doc(hidden)]
+pub use insta as __insta;
+
+/// Assert an [`insta`] snapshot of the full context of a [`ContextError`].
+///
+/// The error is rendered with [`DisplayOptions::stable`]. All arguments after the
+/// error are passed on to [`insta::assert_snapshot`], allowing for named and inline
+/// snapshots.
+///
+/// ```ignore
+/// src_ctx::assert_context_snapshot!(error, @"...");
+/// ```
+#[cfg(feature = "insta")]
+#[macro_export]
+macro_rules! assert_context_snapshot {
+    ($error:expr $(,)?) => {
+        $crate::__insta::assert_snapshot!($error.render_string(&$crate::DisplayOptions::stable()))
+    };
+    ($error:expr, $($rest:tt)+) => {
+        $crate::__insta::assert_snapshot!(
+            $error.render_string(&$crate::DisplayOptions::stable()),
+            $($rest)+
+        )
+    };
+}
 
 
 mod display;
 mod map;
 mod error;
 mod input;
-mod helpers;
\ No newline at end of file
+mod helpers;
+mod options;
+mod bytes;
+mod emit;
+mod interner;
+mod span_set;
+mod span_tree;
+mod search;
+mod codec;
+mod indent;
+mod location;
+mod suppress;
+mod preprocess;
+mod provider;
+mod report;
+mod cache;
+#[cfg(feature = "test-util")]
+mod fixtures;
+#[cfg(any(feature = "serde_json", feature = "toml", feature = "serde_yaml", feature = "pest"))]
+mod formats;
\ No newline at end of file
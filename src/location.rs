@@ -0,0 +1,164 @@
+use std::fmt;
+use std::num::NonZeroUsize;
+
+use crate::{ContextErrorOrigin, Offset, SourceIndex, SourceMap};
+use crate::provider::displayed_position;
+
+
+/// A one-based line number, as shown in diagnostics.
+///
+/// Use [`to_zero_based`](Self::to_zero_based) for tools that count lines from zero.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Line(NonZeroUsize);
+
+/// A one-based column number, counted in bytes from the start of the line, as shown in
+/// diagnostics.
+///
+/// Use [`to_zero_based`](Self::to_zero_based) for tools that count columns from zero.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Column(NonZeroUsize);
+
+impl Line {
+    /// The first line.
+    pub const FIRST: Self = Self(NonZeroUsize::MIN);
+
+    /// Construct a line from a one-based number.
+    ///
+    /// Returns `None` if the number is zero.
+    pub fn from_one_based(number: usize) -> Option<Self> {
+        NonZeroUsize::new(number).map(Self)
+    }
+
+    /// Construct a line from a zero-based number.
+    ///
+    /// # Panics
+    ///
+    /// This function will panic if the number is [`usize::MAX`].
+    #[track_caller]
+    pub fn from_zero_based(number: usize) -> Self {
+        Self(NonZeroUsize::new(number + 1).expect("line number must not overflow"))
+    }
+
+    /// The one-based line number.
+    pub fn to_one_based(self) -> usize {
+        self.0.get()
+    }
+
+    /// The zero-based line number.
+    pub fn to_zero_based(self) -> usize {
+        self.0.get() - 1
+    }
+}
+
+impl fmt::Display for Line {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+impl Column {
+    /// The first column.
+    pub const FIRST: Self = Self(NonZeroUsize::MIN);
+
+    /// Construct a column from a one-based number.
+    ///
+    /// Returns `None` if the number is zero.
+    pub fn from_one_based(number: usize) -> Option<Self> {
+        NonZeroUsize::new(number).map(Self)
+    }
+
+    /// Construct a column from a zero-based number.
+    ///
+    /// # Panics
+    ///
+    /// This function will panic if the number is [`usize::MAX`].
+    #[track_caller]
+    pub fn from_zero_based(number: usize) -> Self {
+        Self(NonZeroUsize::new(number + 1).expect("column number must not overflow"))
+    }
+
+    /// The one-based column number.
+    pub fn to_one_based(self) -> usize {
+        self.0.get()
+    }
+
+    /// The zero-based column number.
+    pub fn to_zero_based(self) -> usize {
+        self.0.get() - 1
+    }
+}
+
+impl fmt::Display for Column {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+/// A [`Line`] and [`Column`] position in a source.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Location {
+    line: Line,
+    column: Column,
+}
+
+impl Location {
+    /// Construct a location from a [`Line`] and [`Column`].
+    pub fn new(line: Line, column: Column) -> Self {
+        Self { line, column }
+    }
+
+    /// The line of the location.
+    pub fn line(&self) -> Line {
+        self.line
+    }
+
+    /// The column of the location.
+    pub fn column(&self) -> Column {
+        self.column
+    }
+}
+
+impl SourceMap {
+    /// Determine the [`Location`] of an [`Offset`].
+    ///
+    /// The location is the same as the one shown in diagnostics for the offset, which is
+    /// in the original content of [preprocessed](Self::with_preprocessor) entries. An offset
+    /// at the end of a content with a trailing newline is located past the last character
+    /// instead of on the empty final line.
+    ///
+    /// # Panics
+    ///
+    /// This function will panic if the offset does not belong to this map.
+    #[track_caller]
+    pub fn locate(&self, offset: Offset) -> Location {
+        let (_, byte) = displayed_position(self, offset);
+        let line_starts = self.original_line_starts(offset.source_index());
+        let line = line_starts.partition_point(|&start| start <= byte) - 1;
+        Location::new(Line::from_zero_based(line), Column::from_zero_based(byte - line_starts[line]))
+    }
+
+    /// The [`Offset`] at the start of a [`Line`] in the content associated with a
+    /// [`SourceIndex`].
+    ///
+    /// Lines are counted in the original content of [preprocessed](Self::with_preprocessor)
+    /// entries, the same as by [`SourceMap::locate`]. Returns `None` if the content has
+    /// fewer lines.
+    ///
+    /// # Panics
+    ///
+    /// This function will panic if the index does not belong to this map.
+    #[track_caller]
+    pub fn line_offset(&self, idx: SourceIndex, line: Line) -> Option<Offset> {
+        let start = *self.original_line_starts(idx).get(line.to_zero_based())?;
+        Some(self.preprocessed_offset(idx, start))
+    }
+}
+
+impl ContextErrorOrigin {
+    /// The [`Location`] of the error position, unless it is in binary content or the origin
+    /// has no position.
+    pub fn location(&self) -> Option<Location> {
+        let (line, column) = self.line_and_column()?;
+        Some(Location::new(Line::from_one_based(line)?, Column::from_one_based(column)?))
+    }
+}
@@ -1,16 +1,32 @@
 use std::collections::HashMap;
-use std::path::Path;
-use std::sync::Arc;
+use std::ops::Range;
+use std::path::{Component, Path, PathBuf};
+use std::sync::{Arc, OnceLock};
 use std::sync::atomic::{AtomicU32, Ordering};
+use std::time::SystemTime;
 
-use crate::{ContextErrorLocation, Offset, Span, ContextErrorOrigin, Input};
+use crate::{
+    Offset, Span, ContextErrorOrigin, ContextErrorAnnotation, Input, GlobalOffset, GlobalSpan,
+};
+use crate::display::display_fn;
+use crate::cache::{ContentCache, SourceContent};
+use crate::preprocess::{Original, Preprocessed, Preprocessor};
+use crate::provider::{SourceProvider, capture_location, displayed_position};
 
 
 /// An identifier for a specific source in a [`SourceMap`].
 #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
 pub struct SourceIndex {
-    map_id: u32,
-    data_index: u32,
+    pub(crate) map_id: u32,
+    pub(crate) data_index: u32,
+    pub(crate) generation: u32,
+}
+
+impl SourceIndex {
+    /// An index for a storage without generations, which never removes entries.
+    pub(crate) fn new(map_id: u32, data_index: u32) -> Self {
+        Self { map_id, data_index, generation: 0 }
+    }
 }
 
 /// A map storing source contents and their [`Origin`].
@@ -19,7 +35,8 @@ pub struct SourceIndex {
 /// with a map it didn't originate from.
 ///
 /// Because every map and index have an associated internal ID, maps are
-/// not clonable as this would invalidate all prior indices.
+/// not clonable as this would invalidate all prior indices. Use [`SourceMap::fork`]
+/// to create a copy that keeps accepting the indices of the original.
 ///
 /// # Panics
 ///
@@ -29,6 +46,19 @@ pub struct SourceMap {
     id: u32,
     origin_indices: HashMap<Origin, u32>,
     data: Vec<SourceData>,
+    global_ranges: Vec<(u32, u32)>,
+    global_end: Option<u32>,
+    lineage: Vec<(u32, u32)>,
+    next_anonymous: usize,
+    generation: u32,
+    canonical_paths: bool,
+    case_insensitive_paths: bool,
+    max_file_size: Option<u64>,
+    directory_order: DirectoryOrder,
+    #[cfg(feature = "ignore")]
+    respect_ignore_files: bool,
+    content_cache: ContentCache,
+    preprocessor: Option<Preprocessor>,
 }
 
 impl Default for SourceMap {
@@ -37,6 +67,26 @@ impl Default for SourceMap {
     }
 }
 
+impl std::fmt::Debug for SourceMap {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let mut debug = f.debug_struct("SourceMap");
+        debug
+            .field("id", &self.id)
+            .field("entries", &self.data)
+            .field("lineage", &self.lineage)
+            .field("canonical_paths", &self.canonical_paths)
+            .field("case_insensitive_paths", &self.case_insensitive_paths)
+            .field("max_file_size", &self.max_file_size)
+            .field("directory_order", &self.directory_order);
+        #[cfg(feature = "ignore")]
+        debug.field("respect_ignore_files", &self.respect_ignore_files);
+        debug
+            .field("content_cache_limit", &self.content_cache.limit())
+            .field("preprocessor", &self.preprocessor.is_some())
+            .finish()
+    }
+}
+
 impl SourceMap {
     /// Construct an empty [`SourceMap`].
     pub fn new() -> Self {
@@ -44,12 +94,335 @@ impl SourceMap {
             id: fetch_next_source_map_id(),
             origin_indices: HashMap::new(),
             data: Vec::new(),
+            global_ranges: Vec::new(),
+            global_end: Some(0),
+            lineage: Vec::new(),
+            next_anonymous: 0,
+            generation: 0,
+            canonical_paths: false,
+            case_insensitive_paths: false,
+            max_file_size: None,
+            directory_order: DirectoryOrder::default(),
+            #[cfg(feature = "ignore")]
+            respect_ignore_files: false,
+            content_cache: ContentCache::default(),
+            preprocessor: None,
+        }
+    }
+
+    /// Identify file entries by their canonicalized paths.
+    ///
+    /// When enabled, paths like `./src/a.ext` and `src/a.ext` will refer to the same
+    /// entry. Paths that can't be canonicalized, for example because they don't exist,
+    /// are normalized lexically instead. The origins will still carry the path as
+    /// originally given.
+    ///
+    /// This should be set before any entries are inserted.
+    pub fn with_canonical_paths(mut self, enabled: bool) -> Self {
+        self.canonical_paths = enabled;
+        self
+    }
+
+    /// Compare file paths case-insensitively.
+    ///
+    /// When enabled, paths like `src/a.ext` and `SRC/A.ext` will refer to the same
+    /// entry, which matches the behavior of the file systems commonly used on Windows
+    /// and macOS. The origins will still carry the path as originally given.
+    ///
+    /// If a directory load finds several files differing only in case, the first one is
+    /// inserted and the others are reported as [previous](LoadReport::previous) entries.
+    ///
+    /// This should be set before any entries are inserted.
+    pub fn with_case_insensitive_paths(mut self, enabled: bool) -> Self {
+        self.case_insensitive_paths = enabled;
+        self
+    }
+
+    /// Limit the size of files loaded from the file system.
+    ///
+    /// Loading a file larger than the given number of bytes will result in a
+    /// [`LoadError::TooLarge`] error, or a [`SkipReason::TooLarge`] skip when loading
+    /// with a report.
+    pub fn with_max_file_size(mut self, limit: Option<u64>) -> Self {
+        self.max_file_size = limit;
+        self
+    }
+
+    /// Set the order in which files found by directory loads are inserted.
+    ///
+    /// By default, files are inserted [sorted by path](DirectoryOrder::Path), so the
+    /// assigned [`SourceIndex`] values are the same on every machine.
+    pub fn with_directory_order(mut self, order: DirectoryOrder) -> Self {
+        self.directory_order = order;
+        self
+    }
+
+    /// Skip paths excluded by ignore files when loading directory trees.
+    ///
+    /// When enabled, `.gitignore` and `.ignore` files found during traversal, as well
+    /// as `.git/info/exclude`, are respected, so directories like `target/` are not
+    /// searched. Ignore files apply even outside of Git repositories, while global Git
+    /// configuration is not consulted. Ignored paths are not part of a [`LoadReport`].
+    /// Hidden files are still loaded.
+    #[cfg(feature = "ignore")]
+    pub fn with_respect_ignore_files(mut self, enabled: bool) -> Self {
+        self.respect_ignore_files = enabled;
+        self
+    }
+
+    /// Register a [`Preprocessor`] applied to the contents of all subsequently inserted
+    /// or loaded entries.
+    ///
+    /// The entries store the preprocessed content, while diagnostics are shown with
+    /// the lines, columns, and contents of the original. Preprocessed contents loaded
+    /// from files are not [evicted](Self::with_content_cache_limit).
+    pub fn with_preprocessor<F>(mut self, preprocessor: F) -> Self
+    where
+        F: Fn(&Origin, &str) -> Option<Preprocessed> + Send + Sync + 'static,
+    {
+        self.preprocessor = Some(Arc::new(preprocessor));
+        self
+    }
+
+    /// Limit the number of bytes kept in memory for contents loaded from files.
+    ///
+    /// Once the limit is exceeded, the least recently used file contents are evicted
+    /// when entries are inserted, or when [`trim_content_cache`](Self::trim_content_cache)
+    /// is called. Evicted contents are transparently reloaded from their files on the
+    /// next access. The most recently used content is never evicted.
+    ///
+    /// The limit is only enforced at these points. Contents borrowed from the map can't be
+    /// evicted while the borrows are alive, so reloads through shared references, like
+    /// when rendering a batch of errors, can exceed the limit until the cache is trimmed.
+    ///
+    /// Only contents loaded from files while a limit is set, and
+    /// [compressed](Self::with_compressed_contents) contents can be evicted. File paths
+    /// are made absolute on load, so reloads don't depend on the working directory.
+    ///
+    /// A hash of file contents is kept to ensure reloaded contents are unchanged. If a
+    /// file changed or was removed after its content was evicted, it is replaced by a
+    /// placeholder of spaces with the same length and line breaks. Positions stay valid
+    /// and diagnostics still render, just without the source text.
+    ///
+    /// This should be set before any entries are inserted.
+    pub fn with_content_cache_limit(mut self, limit: Option<usize>) -> Self {
+        self.content_cache.set_limit(limit);
+        self
+    }
+
+    /// Store the contents of inserted entries compressed.
+    ///
+    /// The decompressed contents are kept as a hot cache that is trimmed to the
+    /// [content cache limit](Self::with_content_cache_limit), and decompressed again
+    /// on the next access after being evicted. Without a limit, contents are never
+    /// evicted.
+    ///
+    /// This should be set before any entries are inserted.
+    #[cfg(feature = "lz4")]
+    pub fn with_compressed_contents(mut self, enabled: bool) -> Self {
+        self.content_cache.set_compress(enabled);
+        self
+    }
+
+    /// The number of bytes currently kept in memory for evictable contents.
+    pub fn cached_content_bytes(&self) -> usize {
+        self.content_cache.bytes()
+    }
+
+    /// Evict the least recently used file contents until the
+    /// [content cache limit](Self::with_content_cache_limit) is no longer exceeded.
+    ///
+    /// This can be called after read-only passes over many entries to release the
+    /// contents they reloaded.
+    pub fn trim_content_cache(&mut self) {
+        while self.content_cache.is_exceeded() {
+            let Some((access, position)) = self.content_cache.pop_least_recent() else {
+                break;
+            };
+            let Some(data) = self.data.get_mut(position) else {
+                continue;
+            };
+            if data.content.last_access() != access {
+                self.content_cache.queue(position, &data.content);
+                continue;
+            }
+            if !data.content.is_evictable() {
+                continue;
+            }
+            if self.content_cache.is_most_recent(access) {
+                self.content_cache.queue(position, &data.content);
+                break;
+            }
+            self.content_cache.evict(&mut data.content);
+            data.line_starts.take();
+        }
+    }
+
+    fn entry_content(&self, position: usize) -> &str {
+        self.data[position].content.get(&self.content_cache, position)
+    }
+
+    fn origin_key(&self, origin: &Origin) -> Origin {
+        let Origin::File(path) = origin else {
+            return origin.clone();
+        };
+        let mut path: PathBuf = if self.canonical_paths {
+            std::fs::canonicalize(path).unwrap_or_else(|_| normalize_path_lexically(path))
+        } else {
+            path.to_path_buf()
+        };
+        if self.case_insensitive_paths {
+            path = path.to_string_lossy().to_lowercase().into();
         }
+        Origin::File(path.into())
+    }
+
+    /// Construct a [`Display`](std::fmt::Display) proxy listing all entries of the map.
+    ///
+    /// Each entry is listed on its own line with its position, [`Origin`], content size,
+    /// content hash as produced by [`Fingerprint::hash_content`], and [`SourceCategory`].
+    pub fn dump(&self) -> impl std::fmt::Display + '_ {
+        display_fn(move |f| {
+            writeln!(f, "source map {} with {} entries", self.id, self.data.len())?;
+            for (position, data) in self.data.iter().enumerate() {
+                write!(f, "  {position}: {:?}, {} bytes, ", data.origin, data.content.len())?;
+                let content = self.entry_content(position);
+                write!(f, "hash {:016x}", Fingerprint::hash_content(content.as_bytes()))?;
+                writeln!(f, ", {:?}", data.category)?;
+            }
+            Ok(())
+        })
     }
 
     /// Verify that an [`SourceIndex`] belongs to this map.
+    ///
+    /// Indices of the maps this map was [forked](Self::fork) from also belong to this
+    /// map, as long as they refer to entries present at the time of the fork.
+    ///
+    /// Indices of entries removed by a rolled back [`Transaction`] don't belong to the map
+    /// anymore, even if new entries were inserted in their place.
     pub fn contains(&self, idx: SourceIndex) -> bool {
-        self.id == idx.map_id
+        self.is_own_index(idx) && self.data.get(idx.data_index as usize)
+            .is_some_and(|data| data.generation == idx.generation)
+    }
+
+    /// Whether an index was produced by this map or a map it was forked from, regardless
+    /// of whether its entry still exists.
+    fn is_own_index(&self, idx: SourceIndex) -> bool {
+        self.id == idx.map_id || self.lineage.iter().any(|&(map_id, len)| {
+            map_id == idx.map_id && idx.data_index < len
+        })
+    }
+
+    fn source_index(&self, position: usize) -> SourceIndex {
+        SourceIndex {
+            map_id: self.id,
+            data_index: position as u32,
+            generation: self.data[position].generation,
+        }
+    }
+
+    /// Create a new map containing all entries of this map.
+    ///
+    /// The contents are shared with this map instead of being copied. All indices of
+    /// this map remain valid for the fork, while entries inserted into the fork are not
+    /// visible in this map. This allows speculative passes to add scratch sources without
+    /// affecting the original map.
+    ///
+    /// Indices produced by the fork belong only to the fork, including those returned
+    /// for entries that were present at the time of the fork.
+    pub fn fork(&self) -> Self {
+        let len = u32::try_from(self.data.len()).expect("maximum map size exceeded");
+        let mut lineage = self.lineage.clone();
+        lineage.push((self.id, len));
+        Self {
+            id: fetch_next_source_map_id(),
+            origin_indices: self.origin_indices.clone(),
+            data: self.data.clone(),
+            global_ranges: self.global_ranges.clone(),
+            global_end: self.global_end,
+            lineage,
+            next_anonymous: self.next_anonymous,
+            generation: self.generation,
+            canonical_paths: self.canonical_paths,
+            case_insensitive_paths: self.case_insensitive_paths,
+            max_file_size: self.max_file_size,
+            directory_order: self.directory_order,
+            #[cfg(feature = "ignore")]
+            respect_ignore_files: self.respect_ignore_files,
+            content_cache: self.content_cache.clone(),
+            preprocessor: self.preprocessor.clone(),
+        }
+    }
+
+    /// Create a new map containing only the entries of the given indices, in the given
+    /// order.
+    ///
+    /// The new map has its own internal ID and uses the same configuration as this map.
+    /// The returned [`IndexTranslation`] maps indices, offsets, and spans of this map to
+    /// the new map. Indices given multiple times are only extracted once. This is useful
+    /// to store a minimal set of the sources involved in a bug report.
+    ///
+    /// # Panics
+    ///
+    /// This function will panic if any of the indices do not belong to this map.
+    #[track_caller]
+    pub fn extract<I>(&self, indices: I) -> (SourceMap, IndexTranslation)
+    where
+        I: IntoIterator<Item = SourceIndex>,
+    {
+        let mut map = Self {
+            id: fetch_next_source_map_id(),
+            origin_indices: HashMap::new(),
+            data: Vec::new(),
+            global_ranges: Vec::new(),
+            global_end: Some(0),
+            lineage: Vec::new(),
+            next_anonymous: self.next_anonymous,
+            generation: self.generation,
+            canonical_paths: self.canonical_paths,
+            case_insensitive_paths: self.case_insensitive_paths,
+            max_file_size: self.max_file_size,
+            directory_order: self.directory_order,
+            #[cfg(feature = "ignore")]
+            respect_ignore_files: self.respect_ignore_files,
+            content_cache: self.content_cache.without_contents(),
+            preprocessor: self.preprocessor.clone(),
+        };
+        let mut translation = IndexTranslation { indices: HashMap::new() };
+        for idx in indices {
+            assert!(self.contains(idx), "extracted index must belong to source map");
+            if translation.indices.contains_key(&idx) {
+                continue;
+            }
+            let mut data = self.data[idx.data_index as usize].clone();
+            let index: u32 = map.data.len().try_into().expect("maximum map size exceeded");
+            data.global_start = map.reserve_global_range(index, data.content.len());
+            map.content_cache.adopt(index as usize, &data.content);
+            map.origin_indices.insert(map.origin_key(&data.origin), index);
+            map.data.push(data);
+            translation.indices.insert(idx, map.source_index(index as usize));
+        }
+        (map, translation)
+    }
+
+    /// Create a [`SourceMapReader`] sharing the current entries of this map.
+    ///
+    /// The reader is a [fork](Self::fork) of this map, so all current indices remain
+    /// valid for it. Entries inserted into this map afterwards are not visible through
+    /// the reader.
+    ///
+    /// All [evicted](Self::with_content_cache_limit) contents are reloaded and the line
+    /// indices of all entries are computed up front, so resolving positions through the
+    /// reader doesn't need to allocate.
+    pub fn freeze(&self) -> SourceMapReader {
+        let map = self.fork();
+        for position in 0..map.data.len() {
+            let idx = map.source_index(position);
+            map.line_starts(idx);
+            map.original_line_starts(idx);
+        }
+        SourceMapReader { map: Arc::new(map) }
     }
 
     /// Retrieve the [`Origin`] associated with a [`SourceIndex`].
@@ -59,10 +432,69 @@ impl SourceMap {
     /// This function will panic if the index does not belong to this map.
     #[track_caller]
     pub fn origin(&self, idx: SourceIndex) -> &Origin {
-        assert_eq!(self.id, idx.map_id, "origin index must belong to source map");
+        assert!(self.contains(idx), "origin index must belong to source map");
         &self.data[idx.data_index as usize].origin
     }
 
+    /// Retrieve the [`Origin`] associated with a [`SourceIndex`], if the index belongs
+    /// to this map.
+    pub fn try_origin(&self, idx: SourceIndex) -> Option<&Origin> {
+        self.try_data(idx).map(|data| &data.origin)
+    }
+
+    /// Retrieve the content associated with a [`SourceIndex`], if the index belongs
+    /// to this map.
+    pub fn try_content(&self, idx: SourceIndex) -> Option<&str> {
+        self.try_data(idx).map(|_| self.entry_content(idx.data_index as usize))
+    }
+
+    /// Construct an [`Input`] for the content associated with a [`SourceIndex`], if
+    /// the index belongs to this map.
+    pub fn try_input(&self, idx: SourceIndex) -> Option<Input<'_>> {
+        self.try_content(idx).map(|content| Input::new(idx, content))
+    }
+
+    /// Retrieve the string corresponding to a [`Span`] in the map, if the span belongs
+    /// to this map and lies on character boundaries inside of the content.
+    pub fn try_span_str(&self, span: Span) -> Option<&str> {
+        self.get_span_str(span).ok()
+    }
+
+    /// Retrieve the string corresponding to a [`Span`] in the map, describing why the
+    /// span could not be resolved otherwise.
+    ///
+    /// # Errors
+    ///
+    /// A [`SpanResolveError`] is returned if the span belongs to a different map or a
+    /// removed entry, exceeds the content, or doesn't lie on character boundaries.
+    pub fn get_span_str(&self, span: Span) -> Result<&str, SpanResolveError> {
+        let idx = span.source_index();
+        if !self.is_own_index(idx) {
+            return Err(SpanResolveError::ForeignMap);
+        }
+        let content = self.try_content(idx).ok_or(SpanResolveError::MissingEntry)?;
+        let range = span.byte_range();
+        if range.end > content.len() {
+            return Err(SpanResolveError::OutOfBounds { end: range.end, len: content.len() });
+        }
+        for byte in [range.start, range.end] {
+            if !content.is_char_boundary(byte) {
+                return Err(SpanResolveError::NotCharBoundary { byte });
+            }
+        }
+        Ok(&content[range])
+    }
+
+    fn try_data(&self, idx: SourceIndex) -> Option<&SourceData> {
+        if !self.contains(idx) {
+            return None;
+        }
+        self.data.get(idx.data_index as usize)
+    }
+
+    pub(crate) fn id(&self) -> u32 {
+        self.id
+    }
 
     /// Retrieve the content associated with a [`SourceIndex`].
     ///
@@ -71,19 +503,212 @@ impl SourceMap {
     /// This function will panic if the index does not belong to this map.
     #[track_caller]
     pub fn content(&self, idx: SourceIndex) -> &str {
-        assert_eq!(self.id, idx.map_id, "content index must belong to source map");
-        &self.data[idx.data_index as usize].content
+        assert!(self.contains(idx), "content index must belong to source map");
+        self.entry_content(idx.data_index as usize)
     }
 
-    /// Construct an [`Input`] for the content associated with a [`SourceIndex`].
+    /// Convert an [`Offset`] into a [`GlobalOffset`].
+    ///
+    /// Every entry in the map occupies a disjoint range of a global `u32` position
+    /// space, in order of insertion. [Replaced](Self::replace) entries move to a new
+    /// range, so the global offsets of other entries never change. Returns `None` if
+    /// the entry lies beyond the range of the global space.
+    ///
+    /// # Panics
+    ///
+    /// This function will panic if the offset does not belong to this map.
+    #[track_caller]
+    pub fn global_offset(&self, offset: Offset) -> Option<GlobalOffset> {
+        let index = offset.source_index();
+        assert!(self.contains(index), "offset must belong to source map");
+        let start = self.data[index.data_index as usize].global_start?;
+        Some(GlobalOffset::from_u32(start + offset.byte() as u32))
+    }
+
+    /// Reserve the global range following all previous ranges for an entry with a given
+    /// length.
+    ///
+    /// Once an entry doesn't fit into the global space, no further ranges are reserved.
+    fn reserve_global_range(&mut self, data_index: u32, len: usize) -> Option<u32> {
+        let start = self.global_end?;
+        let end = u32::try_from(len).ok().and_then(|len| start.checked_add(len));
+        self.global_end = end.and_then(|end| end.checked_add(1));
+        end?;
+        self.global_ranges.push((start, data_index));
+        Some(start)
+    }
+
+    /// Convert a [`Span`] into a [`GlobalSpan`].
+    ///
+    /// Returns `None` if the entry lies beyond the range of the global space.
+    ///
+    /// # Panics
+    ///
+    /// This function will panic if the span does not belong to this map.
+    #[track_caller]
+    pub fn global_span(&self, span: Span) -> Option<GlobalSpan> {
+        let start = self.global_offset(span.start())?;
+        Some(GlobalSpan::new(start, span.byte_len() as u32))
+    }
+
+    /// Resolve a [`GlobalOffset`] back into an [`Offset`].
+    ///
+    /// Returns `None` if the position does not lie in any entry of this map. Since
+    /// global offsets don't carry the identity of their map, resolving an offset
+    /// produced by a different map will not be detected.
+    pub fn resolve_global_offset(&self, offset: GlobalOffset) -> Option<Offset> {
+        let position = offset.to_u32();
+        let range = self.global_ranges
+            .partition_point(|&(start, _)| start <= position)
+            .checked_sub(1)?;
+        let (start, data_index) = self.global_ranges[range];
+        let data = &self.data[data_index as usize];
+        let byte = (position - start) as usize;
+        if byte > data.content.len() {
+            return None;
+        }
+        Some(Offset::new(self.source_index(data_index as usize), byte))
+    }
+
+    /// Resolve a [`GlobalSpan`] back into a [`Span`].
+    ///
+    /// Returns `None` if the span does not lie fully in a single entry of this map.
+    pub fn resolve_global_span(&self, span: GlobalSpan) -> Option<Span> {
+        let start = self.resolve_global_offset(span.start())?;
+        if start.byte() + span.byte_len() as usize > self.content(start.source_index()).len() {
+            return None;
+        }
+        Some(Span::new(start, span.byte_len() as usize))
+    }
+
+    /// The byte-positions of the starts of all lines in an entry.
+    ///
+    /// The line index is computed on first use and cached.
+    #[track_caller]
+    pub(crate) fn line_starts(&self, idx: SourceIndex) -> &[usize] {
+        assert!(self.contains(idx), "content index must belong to source map");
+        let data = &self.data[idx.data_index as usize];
+        data.line_starts.get_or_init(|| line_starts_of(self.content(idx)))
+    }
+
+    /// Retrieve the [`SourceCategory`] associated with a [`SourceIndex`].
     ///
     /// # Panics
     ///
     /// This function will panic if the index does not belong to this map.
     #[track_caller]
+    pub fn category(&self, idx: SourceIndex) -> SourceCategory {
+        assert!(self.contains(idx), "category index must belong to source map");
+        self.data[idx.data_index as usize].category
+    }
+
+    /// Change the [`SourceCategory`] associated with a [`SourceIndex`].
+    ///
+    /// New entries start out as [`SourceCategory::User`].
+    ///
+    /// # Panics
+    ///
+    /// This function will panic if the index does not belong to this map.
+    #[track_caller]
+    pub fn set_category(&mut self, idx: SourceIndex, category: SourceCategory) {
+        assert!(self.contains(idx), "category index must belong to source map");
+        self.data[idx.data_index as usize].category = category;
+    }
+
+    /// Register a named anchor at an [`Offset`] in the source associated with a
+    /// [`SourceIndex`].
+    ///
+    /// Anchors allow marking structural positions in an early pass, like the end of a
+    /// header, to reference them in diagnostics of later passes without scanning the
+    /// content again. A previous anchor with the same name is replaced.
+    ///
+    /// # Panics
+    ///
+    /// This function will panic if the index does not belong to this map, or if the
+    /// offset belongs to a different source.
+    #[track_caller]
+    pub fn set_anchor(&mut self, idx: SourceIndex, name: &'static str, offset: Offset) {
+        assert!(self.contains(idx), "anchor index must belong to source map");
+        assert_eq!(idx, offset.source_index(), "anchor offset must belong to anchor source");
+        let anchors = &mut self.data[idx.data_index as usize].anchors;
+        match anchors.iter_mut().find(|(prev, _)| *prev == name) {
+            Some((_, byte)) => *byte = offset.byte(),
+            None => anchors.push((name, offset.byte())),
+        }
+    }
+
+    /// Retrieve the [`Offset`] of a named anchor in the source associated with a
+    /// [`SourceIndex`], if it was [registered](Self::set_anchor).
+    ///
+    /// # Panics
+    ///
+    /// This function will panic if the index does not belong to this map.
+    #[track_caller]
+    pub fn anchor(&self, idx: SourceIndex, name: &str) -> Option<Offset> {
+        self.anchors(idx).find(|(prev, _)| *prev == name).map(|(_, offset)| offset)
+    }
+
+    /// Remove a named anchor from the source associated with a [`SourceIndex`], returning
+    /// its [`Offset`] if it was registered.
+    ///
+    /// # Panics
+    ///
+    /// This function will panic if the index does not belong to this map.
+    #[track_caller]
+    pub fn remove_anchor(&mut self, idx: SourceIndex, name: &str) -> Option<Offset> {
+        assert!(self.contains(idx), "anchor index must belong to source map");
+        let anchors = &mut self.data[idx.data_index as usize].anchors;
+        let position = anchors.iter().position(|(prev, _)| *prev == name)?;
+        Some(Offset::new(idx, anchors.remove(position).1))
+    }
+
+    /// An iterator over all named anchors in the source associated with a
+    /// [`SourceIndex`], in registration order.
+    ///
+    /// # Panics
+    ///
+    /// This function will panic if the index does not belong to this map.
+    #[track_caller]
+    pub fn anchors(&self, idx: SourceIndex) -> impl Iterator<Item = (&'static str, Offset)> + '_ {
+        assert!(self.contains(idx), "anchor index must belong to source map");
+        self.data[idx.data_index as usize].anchors.iter()
+            .map(move |&(name, byte)| (name, Offset::new(idx, byte)))
+    }
+
+    /// Construct an [`Input`] for the content associated with a [`SourceIndex`].
+    ///
+    /// # Panics
+    ///
+    /// This function will panic if the index does not belong to this map, or if an
+    /// [evicted](Self::with_content_cache_limit) content could not be reloaded.
+    #[track_caller]
     pub fn input(&self, idx: SourceIndex) -> Input<'_> {
-        assert_eq!(self.id, idx.map_id, "input index must belong to source map");
-        Input::new(idx, &self.data[idx.data_index as usize].content)
+        assert!(self.contains(idx), "input index must belong to source map");
+        Input::new(idx, self.content(idx))
+    }
+
+    /// The number of entries in this map.
+    pub fn len(&self) -> usize {
+        self.data.len()
+    }
+
+    /// Determine if this map has no entries.
+    pub fn is_empty(&self) -> bool {
+        self.data.is_empty()
+    }
+
+    /// An iterator over the [`SourceIndex`], [`Origin`], and content of all entries
+    /// in this map, in insertion order.
+    ///
+    /// # Panics
+    ///
+    /// The iterator will panic if an [evicted](Self::with_content_cache_limit) content
+    /// could not be reloaded.
+    pub fn entries(&self) -> impl Iterator<Item = (SourceIndex, &Origin, &str)> + '_ {
+        self.data.iter().enumerate().map(|(index, data)| {
+            let index = self.source_index(index);
+            (index, &data.origin, self.content(index))
+        })
     }
 
     /// An iterator over all [`Origin`]s in this map.
@@ -93,8 +718,8 @@ impl SourceMap {
 
     /// Find the [`SourceIndex`] for a given [`Origin`] if there is one.
     pub fn origin_index(&self, origin: &Origin) -> Option<SourceIndex> {
-        self.origin_indices.get(origin).map(|index| {
-            SourceIndex { map_id: self.id, data_index: *index }
+        self.origin_indices.get(&self.origin_key(origin)).map(|&index| {
+            self.source_index(index as usize)
         })
     }
 
@@ -102,7 +727,7 @@ impl SourceMap {
     pub fn files(&self) -> impl Iterator<Item = &Path> + '_ {
         self.origins().filter_map(|origin| match origin {
             Origin::File(path) => Some(path.as_ref()),
-            Origin::Named(_) => None,
+            Origin::Named(_) | Origin::Url(_) | Origin::Stdin | Origin::Generated { .. } => None,
         })
     }
 
@@ -111,18 +736,7 @@ impl SourceMap {
     where
         P: AsRef<Path>,
     {
-        let path = path.as_ref();
-        for (origin, index) in &self.origin_indices {
-            if let Origin::File(origin_file) = origin {
-                if origin_file.as_ref() == path {
-                    return Some(SourceIndex {
-                        map_id: self.id,
-                        data_index: *index,
-                    });
-                }
-            }
-        }
-        None
+        self.origin_index(&Origin::from_file(path))
     }
 
     /// Determine if a file path is contained in this map.
@@ -130,8 +744,7 @@ impl SourceMap {
     where
         P: AsRef<Path>,
     {
-        let path = path.as_ref();
-        self.files().any(|origin_path| origin_path == path)
+        self.file_index(path).is_some()
     }
 
     /// Try to insert a new source entry into the map.
@@ -139,13 +752,132 @@ impl SourceMap {
     /// Returns a [`Insert::Previous`] if an entry with the same origin already exists
     /// in the map.
     pub fn insert(&mut self, origin: Origin, content: Box<str>) -> Insert {
-        if let Some(prev_index) = self.origin_indices.get(&origin).copied() {
-            return Insert::Previous(SourceIndex { map_id: self.id, data_index: prev_index });
+        self.insert_content(origin, content, false)
+    }
+
+    fn insert_content(&mut self, origin: Origin, content: Box<str>, from_file: bool) -> Insert {
+        let key = self.origin_key(&origin);
+        if let Some(prev_index) = self.origin_indices.get(&key).copied() {
+            return Insert::Previous(self.source_index(prev_index as usize));
         }
+        let (content, original) = self.preprocess(&origin, content);
+        let file = match &origin {
+            Origin::File(path) if from_file && original.is_none() => Some(&**path),
+            _ => None,
+        };
+        let content = self.content_cache.content(content.into(), file);
         let index: u32 = self.data.len().try_into().expect("maximum map size exceeded");
-        self.origin_indices.insert(origin.clone(), index);
-        self.data.push(SourceData { origin, content });
-        Insert::Inserted(SourceIndex { map_id: self.id, data_index: index })
+        self.content_cache.queue(index as usize, &content);
+        self.origin_indices.insert(key, index);
+        let global_start = self.reserve_global_range(index, content.len());
+        self.data.push(SourceData {
+            origin,
+            content,
+            category: SourceCategory::default(),
+            global_start,
+            line_starts: OnceLock::new(),
+            original,
+            version: 0,
+            anchors: Vec::new(),
+            generation: self.generation,
+        });
+        self.trim_content_cache();
+        Insert::Inserted(self.source_index(index as usize))
+    }
+
+    fn preprocess(&self, origin: &Origin, content: Box<str>) -> (Box<str>, Option<Arc<Original>>) {
+        match self.preprocessor.as_ref().and_then(|preprocess| preprocess(origin, &content)) {
+            Some(preprocessed) => {
+                let (preprocessed, segments) = preprocessed.into_parts();
+                (preprocessed.into(), Some(Arc::new(Original::new(content.into(), segments))))
+            },
+            None => (content, None),
+        }
+    }
+
+    /// Replace the content associated with a [`SourceIndex`], incrementing its
+    /// [version](Self::version).
+    ///
+    /// The entry keeps its [`Origin`] and [`SourceCategory`], and the content is
+    /// [preprocessed](Self::with_preprocessor) like on insertion. Offsets and spans into
+    /// the previous content should not be used with the new content. The entry moves to
+    /// a new range of [global offsets](Self::global_offset), so global offsets into the
+    /// previous content no longer resolve, while those of other entries are unaffected.
+    /// All [anchors](Self::set_anchor) of the entry are removed.
+    ///
+    /// # Panics
+    ///
+    /// This function will panic if the index does not belong to this map.
+    #[track_caller]
+    pub fn replace(&mut self, idx: SourceIndex, content: Box<str>) {
+        assert!(self.contains(idx), "replaced index must belong to source map");
+        let position = idx.data_index as usize;
+        let (content, original) = self.preprocess(&self.data[position].origin, content);
+        let content = self.content_cache.content(content.into(), None);
+        self.content_cache.queue(position, &content);
+        let data = &mut self.data[position];
+        let prev_content = std::mem::replace(&mut data.content, content);
+        data.original = original;
+        data.line_starts = OnceLock::new();
+        data.anchors.clear();
+        data.version += 1;
+        self.content_cache.remove(&prev_content);
+        self.global_ranges.retain(|&(_, data_index)| data_index as usize != position);
+        let len = self.data[position].content.len();
+        self.data[position].global_start = self.reserve_global_range(position as u32, len);
+        self.trim_content_cache();
+    }
+
+    /// The version of the content associated with a [`SourceIndex`].
+    ///
+    /// Versions start at `0` on insertion and are incremented every time the content is
+    /// [replaced](Self::replace).
+    ///
+    /// # Panics
+    ///
+    /// This function will panic if the index does not belong to this map.
+    #[track_caller]
+    pub fn version(&self, idx: SourceIndex) -> u32 {
+        assert!(self.contains(idx), "version index must belong to source map");
+        self.data[idx.data_index as usize].version
+    }
+
+    /// Start a [`Transaction`] staging insertions into the map.
+    ///
+    /// Entries inserted through the transaction are only kept if the transaction is
+    /// [committed](Transaction::commit). Otherwise they are removed once the transaction
+    /// is dropped.
+    pub fn transaction(&mut self) -> Transaction<'_> {
+        let len = self.data.len();
+        let next_anonymous = self.next_anonymous;
+        Transaction { map: self, len, next_anonymous, committed: false }
+    }
+
+    fn truncate(&mut self, len: usize) {
+        if len >= self.data.len() {
+            return;
+        }
+        for data in &self.data[len..] {
+            self.content_cache.remove(&data.content);
+        }
+        self.data.truncate(len);
+        self.global_ranges.retain(|&(_, data_index)| (data_index as usize) < len);
+        self.generation = self.generation.wrapping_add(1);
+        self.origin_indices.retain(|_, index| (*index as usize) < len);
+    }
+
+    /// Insert a new source entry under a generated unique [`Origin::Named`] origin.
+    ///
+    /// The generated names take the form of `<anonymous-N>`. Names already present
+    /// in the map are skipped.
+    pub fn insert_anonymous(&mut self, content: Box<str>) -> SourceIndex {
+        loop {
+            let origin = Origin::from_named(format!("<anonymous-{}>", self.next_anonymous));
+            self.next_anonymous += 1;
+            if self.origin_index(&origin).is_none() {
+                return self.insert(origin, content).try_into_inserted().unwrap();
+            }
+        }
     }
 
     fn read_file<P>(&self, path: P) -> Result<Box<str>, ReadError>
@@ -156,41 +888,208 @@ impl SourceMap {
         if let Some(prev_index) = self.file_index(path) {
             return Err(ReadError::Previous(prev_index));
         }
-        let content = std::fs::read_to_string(path)
-            .map_err(|error| ReadError::Read(path.into(), error.into()))?;
-        Ok(content.into())
+        let read_error = |error: std::io::Error| {
+            ReadError::Load(LoadError::Read { file: path.into(), error: error.into() })
+        };
+        if let Some(limit) = self.max_file_size {
+            let size = std::fs::metadata(path).map_err(read_error)?.len();
+            if size > limit {
+                return Err(ReadError::Load(LoadError::TooLarge { file: path.into(), size, limit }));
+            }
+        }
+        let content = std::fs::read(path).map_err(read_error)?;
+        match String::from_utf8(content) {
+            Ok(content) => Ok(content.into()),
+            Err(error) => {
+                let valid = &error.as_bytes()[..error.utf8_error().valid_up_to()];
+                let valid = std::str::from_utf8(valid).unwrap();
+                let line_start = valid.rfind('\n').map_or(0, |index| index + 1);
+                Err(ReadError::Load(LoadError::InvalidUtf8 {
+                    file: path.into(),
+                    valid_up_to: valid.len(),
+                    line: 1 + valid.matches('\n').count(),
+                    column: 1 + (valid.len() - line_start),
+                }))
+            },
+        }
+    }
+
+    /// Try to load a file into the source map.
+    ///
+    /// Returns a [`Insert::Previous`] if a file with the same path already exists
+    /// in the map before attempting to load the path.
+    ///
+    /// # Errors
+    ///
+    /// An error will be returned if the file could not be read.
+    pub fn load_file<P>(&mut self, path: P) -> Result<Insert, LoadError>
+    where
+        P: AsRef<Path>,
+    {
+        let path = path.as_ref();
+        let content = match self.read_file(path) {
+            Ok(content) => content,
+            Err(error) => {
+                return match error {
+                    ReadError::Previous(index) => Ok(Insert::Previous(index)),
+                    ReadError::Load(error) => Err(error),
+                };
+            },
+        };
+        let origin = Origin::File(path.into());
+        Ok(Insert::Inserted(self.insert_content(origin, content, true).try_into_inserted().unwrap()))
+    }
+
+    /// Compute a [`Fingerprint`] of a file on disk.
+    ///
+    /// The fingerprint can be persisted and later checked with
+    /// [`verify_fingerprint`](Self::verify_fingerprint) to determine whether the file
+    /// needs to be loaded again.
+    ///
+    /// # Errors
+    ///
+    /// An error will be returned if the file could not be read.
+    pub fn fingerprint_file<P>(&self, path: P) -> Result<Fingerprint, LoadError>
+    where
+        P: AsRef<Path>,
+    {
+        let path = path.as_ref();
+        let read_error = |error: std::io::Error| {
+            LoadError::Read { file: path.into(), error: error.into() }
+        };
+        let modified = std::fs::metadata(path).map_err(read_error)?.modified().ok();
+        let content = std::fs::read(path).map_err(read_error)?;
+        Ok(Fingerprint::new(Fingerprint::hash_content(&content), content.len() as u64, modified))
+    }
+
+    /// Check whether a file on disk still matches a previously computed [`Fingerprint`].
+    ///
+    /// Files with a different length never match. Files with the same length and
+    /// modification time are assumed to match without reading their content. In all
+    /// other cases the content hashes are compared.
+    ///
+    /// # Errors
+    ///
+    /// An error will be returned if the file could not be read.
+    pub fn verify_fingerprint<P>(&self, path: P, fingerprint: &Fingerprint) -> Result<bool, LoadError>
+    where
+        P: AsRef<Path>,
+    {
+        let path = path.as_ref();
+        let read_error = |error: std::io::Error| {
+            LoadError::Read { file: path.into(), error: error.into() }
+        };
+        let metadata = std::fs::metadata(path).map_err(read_error)?;
+        if metadata.len() != fingerprint.len {
+            return Ok(false);
+        }
+        let modified = metadata.modified().ok();
+        if modified.is_some() && modified == fingerprint.modified {
+            return Ok(true);
+        }
+        let content = std::fs::read(path).map_err(read_error)?;
+        Ok(Fingerprint::hash_content(&content) == fingerprint.content_hash)
+    }
+
+    /// Try to fetch content from a URL into the source map.
+    ///
+    /// Returns a [`Insert::Previous`] if an entry with the same URL already exists
+    /// in the map before attempting to fetch the content.
+    ///
+    /// # Errors
+    ///
+    /// An error will be returned if the content could not be fetched.
+    #[cfg(feature = "http")]
+    pub fn load_url(&mut self, url: &str) -> Result<Insert, LoadError> {
+        let origin = Origin::from_url(url);
+        if let Some(prev_index) = self.origin_index(&origin) {
+            return Ok(Insert::Previous(prev_index));
+        }
+        let content = ureq::get(url).call()
+            .and_then(|mut response| response.body_mut().read_to_string())
+            .map_err(|error| LoadError::Fetch { url: url.into(), error: error.into() })?;
+        Ok(self.insert(origin, content.into()))
+    }
+
+    /// Try to load all files with a specific extension below a root path.
+    ///
+    /// Returns a [`Vec`] of insertion outcomes. The outcome will be an [`Insert::Previous`]
+    /// if a file with the same path was already loaded into the map, or if an earlier file
+    /// of the same search resolved to the same path, for example through a symlink with
+    /// [canonical paths](Self::with_canonical_paths). Files are inserted in the configured
+    /// [directory order](Self::with_directory_order).
+    ///
+    /// # Errors
+    ///
+    /// An error will be returned if the directory tree could not be fully searched or
+    /// a file could not be loaded.
+    ///
+    /// No map insertions will be performed until all file
+    /// loads are complete. An error will thus not result in an inconsistent set of
+    /// loaded entries in the map.
+    pub fn load_directory<P>(&mut self, root: P, extension: &str) -> Result<Vec<Insert>, LoadError>
+    where
+        P: AsRef<Path>,
+    {
+        let open = self.read_directory(root.as_ref(), extension, false, None, &mut |_| {})?;
+        Ok(open.into_iter().filter_map(|open| match open {
+            DirectoryEntry::Open(origin, content) => {
+                Some(self.insert_content(origin, content, true))
+            },
+            DirectoryEntry::Previous(index) => Some(Insert::Previous(index)),
+            DirectoryEntry::Skipped(..) => None,
+        }).collect())
+    }
+
+    /// Try to load all files with a specific extension below a root path, reporting
+    /// on every encountered path.
+    ///
+    /// This works like [`load_directory`](Self::load_directory), but returns a
+    /// [`LoadReport`] that also includes the paths that were skipped, together with
+    /// the [`SkipReason`]. Files that are not valid UTF-8 or exceed the
+    /// [maximum file size](Self::with_max_file_size) are skipped instead of causing
+    /// an error.
+    ///
+    /// # Errors
+    ///
+    /// An error will be returned if the directory tree could not be fully searched or
+    /// a file could not be loaded.
+    ///
+    /// No map insertions will be performed until all file
+    /// loads are complete. An error will thus not result in an inconsistent set of
+    /// loaded entries in the map.
+    pub fn load_directory_report<P>(&mut self, root: P, extension: &str) -> Result<LoadReport, LoadError>
+    where
+        P: AsRef<Path>,
+    {
+        let open = self.read_directory(root.as_ref(), extension, true, None, &mut |_| {})?;
+        Ok(self.insert_directory_entries(open, Vec::new()))
     }
 
-    /// Try to load a file into the source map.
-    ///
-    /// Returns a [`Insert::Previous`] if a file with the same path already exists
-    /// in the map before attempting to load the path.
-    ///
-    /// # Errors
+    /// Try to load all files with a specific extension below a root path, continuing
+    /// on errors.
     ///
-    /// An error will be returned if the file could not be read.
-    pub fn load_file<P>(&mut self, path: P) -> Result<Insert, LoadError>
+    /// This works like [`load_directory_report`](Self::load_directory_report), but
+    /// errors occuring while searching the tree or reading specific files are collected
+    /// in the [`LoadReport`] instead of aborting the whole operation. All files that
+    /// could be loaded will be inserted.
+    pub fn load_directory_lenient<P>(&mut self, root: P, extension: &str) -> LoadReport
     where
         P: AsRef<Path>,
     {
-        let path = path.as_ref();
-        let content = match self.read_file(path) {
-            Ok(content) => content,
-            Err(error) => {
-                return match error {
-                    ReadError::Previous(index) => Ok(Insert::Previous(index)),
-                    ReadError::Read(file, error) => Err(LoadError::Read { file, error }),
-                };
-            },
-        };
-        let origin = Origin::File(path.into());
-        Ok(Insert::Inserted(self.insert(origin, content).try_into_inserted().unwrap()))
+        let mut errors = Vec::new();
+        let open = self
+            .read_directory(root.as_ref(), extension, true, Some(&mut errors), &mut |_| {})
+            .expect("errors are collected");
+        self.insert_directory_entries(open, errors)
     }
 
-    /// Try to load all files with a specific extension below a root path.
+    /// Try to load all files with a specific extension below a root path, calling a
+    /// progress callback for every encountered path.
     ///
-    /// Returns a [`Vec`] of insertion outcomes. The outcome will be an [`Insert::Previous`]
-    /// if a file with the same path was already loaded into the map.
+    /// This works like [`load_directory_report`](Self::load_directory_report), but
+    /// calls the given function with the path of every non-directory entry before it
+    /// is read, allowing the progress of large loads to be displayed.
     ///
     /// # Errors
     ///
@@ -200,53 +1099,187 @@ impl SourceMap {
     /// No map insertions will be performed until all file
     /// loads are complete. An error will thus not result in an inconsistent set of
     /// loaded entries in the map.
-    pub fn load_directory<P>(&mut self, root: P, extension: &str) -> Result<Vec<Insert>, LoadError>
+    pub fn load_directory_with_progress<P, F>(
+        &mut self,
+        root: P,
+        extension: &str,
+        mut progress: F,
+    ) -> Result<LoadReport, LoadError>
     where
         P: AsRef<Path>,
+        F: FnMut(&Path),
     {
-        let root = root.as_ref();
+        let open = self.read_directory(root.as_ref(), extension, true, None, &mut progress)?;
+        Ok(self.insert_directory_entries(open, Vec::new()))
+    }
+
+    fn insert_directory_entries(
+        &mut self,
+        open: Vec<DirectoryEntry>,
+        errors: Vec<LoadError>,
+    ) -> LoadReport {
+        let mut report = LoadReport { errors, ..LoadReport::default() };
+        for open in open {
+            match open {
+                DirectoryEntry::Open(origin, content) => {
+                    match self.insert_content(origin, content, true) {
+                        Insert::Inserted(index) => report.inserted.push(index),
+                        Insert::Previous(index) => report.previous.push(index),
+                    }
+                },
+                DirectoryEntry::Previous(index) => report.previous.push(index),
+                DirectoryEntry::Skipped(path, reason) => report.skipped.push((path, reason)),
+            }
+        }
+        report
+    }
+
+    fn read_directory(
+        &self,
+        root: &Path,
+        extension: &str,
+        report_skipped: bool,
+        mut errors: Option<&mut Vec<LoadError>>,
+        progress: &mut dyn FnMut(&Path),
+    ) -> Result<Vec<DirectoryEntry>, LoadError> {
         let mut open = Vec::new();
-        for entry in walkdir::WalkDir::new(root).follow_links(true) {
-            let entry = entry.map_err(|error| LoadError::Find {
-                root: root.into(),
-                extension: extension.into(),
-                error: error.into(),
-            })?;
-            let path = entry.path();
-            if !(
-                path.is_file()
-                && entry.file_name().to_str().map_or(false, |name| name.ends_with(extension))
-            ) {
+        let mut fail = |error: LoadError| match errors.as_deref_mut() {
+            Some(errors) => {
+                errors.push(error);
+                Ok(())
+            },
+            None => Err(error),
+        };
+        for entry in self.walk_directory(root, extension) {
+            let path = match entry {
+                Ok(path) => path,
+                Err(error) => {
+                    fail(error)?;
+                    continue;
+                },
+            };
+            let path = path.as_path();
+            progress(path);
+            if !path.is_file() {
+                open.push(DirectoryEntry::Skipped(path.into(), SkipReason::NotFile));
+                continue;
+            }
+            let file_name = path.file_name().and_then(|name| name.to_str());
+            if !file_name.is_some_and(|name| name.ends_with(extension)) {
+                open.push(DirectoryEntry::Skipped(path.into(), SkipReason::Extension));
                 continue;
             }
             open.push(match self.read_file(path) {
-                Ok(content) => Ok((Origin::File(path.into()), content)),
+                Ok(content) => DirectoryEntry::Open(Origin::File(path.into()), content),
                 Err(error) => match error {
-                    ReadError::Previous(index) => Err(index),
-                    ReadError::Read(file, error) => {
-                        return Err(LoadError::Read { file, error });
+                    ReadError::Previous(index) => DirectoryEntry::Previous(index),
+                    ReadError::Load(LoadError::InvalidUtf8 { file, .. }) if report_skipped => {
+                        DirectoryEntry::Skipped(file, SkipReason::Binary)
+                    },
+                    ReadError::Load(LoadError::TooLarge { file, .. }) if report_skipped => {
+                        DirectoryEntry::Skipped(file, SkipReason::TooLarge)
+                    },
+                    ReadError::Load(error) => {
+                        fail(error)?;
+                        continue;
                     },
                 },
             });
         }
-        Ok(open.into_iter().map(|open| match open {
-            Ok((origin, content)) => {
-                Insert::Inserted(self.insert(origin, content).try_into_inserted().unwrap())
-            },
-            Err(index) => Insert::Previous(index),
-        }).collect())
+        Ok(open)
+    }
+
+    /// Find all non-directory paths below a root path.
+    fn walk_directory<'a>(
+        &self,
+        root: &'a Path,
+        extension: &'a str,
+    ) -> Box<dyn Iterator<Item = Result<PathBuf, LoadError>> + 'a> {
+        let sorted = self.directory_order == DirectoryOrder::Path;
+        #[cfg(feature = "ignore")]
+        if self.respect_ignore_files {
+            let mut walk = ignore::WalkBuilder::new(root);
+            walk.follow_links(true).hidden(false).git_global(false).require_git(false);
+            if sorted {
+                walk.sort_by_file_name(|a, b| a.cmp(b));
+            }
+            let walk = walk.build();
+            return Box::new(walk.filter_map(move |entry| match entry {
+                Ok(entry) => {
+                    let is_dir = entry.file_type().is_some_and(|file_type| file_type.is_dir());
+                    (!is_dir).then(|| Ok(entry.into_path()))
+                },
+                Err(error) => Some(Err(LoadError::FindIgnoring {
+                    root: root.into(),
+                    extension: extension.into(),
+                    error: error.into(),
+                })),
+            }));
+        }
+        let mut walk = walkdir::WalkDir::new(root).follow_links(true);
+        if sorted {
+            walk = walk.sort_by_file_name();
+        }
+        Box::new(walk.into_iter().filter_map(move |entry| match entry {
+            Ok(entry) => (!entry.file_type().is_dir()).then(|| Ok(entry.into_path())),
+            Err(error) => Some(Err(LoadError::Find {
+                root: root.into(),
+                extension: extension.into(),
+                error: error.into(),
+            })),
+        }))
     }
 
     /// Retrieve the string corresponding to a [`Span`] in the map.
     ///
+    /// Use [`get_span_str`](Self::get_span_str) for spans that might not be valid,
+    /// for example because they were deserialized from a cache.
+    ///
     /// # Panics
     ///
-    /// This function will panic if the given span does not belong to this map.
+    /// This function will panic if the given span does not belong to this map, or
+    /// does not lie on character boundaries inside of the content.
     pub fn span_str(&self, span: Span) -> &str {
         let content = self.content(span.source_index());
         &content[span.byte_range()]
     }
 
+    /// Construct a [`Span`] from a byte range in the content associated with a
+    /// [`SourceIndex`].
+    ///
+    /// Returns `None` if the range is reversed, exceeds the content, or doesn't lie on
+    /// character boundaries.
+    ///
+    /// # Panics
+    ///
+    /// This function will panic if the index does not belong to this map.
+    #[track_caller]
+    pub fn span_from_range(&self, idx: SourceIndex, range: Range<usize>) -> Option<Span> {
+        let content = self.content(idx);
+        content.get(range.clone())?;
+        Some(Span::new(Offset::new(idx, range.start), range.end - range.start))
+    }
+
+    /// Lex the content of an entry with a [`logos`] lexer, producing [`Spanned`] tokens.
+    ///
+    /// # Panics
+    ///
+    /// This function will panic if the index does not belong to this map.
+    #[cfg(feature = "logos")]
+    #[track_caller]
+    pub fn lex_spanned<'a, T>(
+        &'a self,
+        index: SourceIndex,
+    ) -> impl Iterator<Item = crate::Spanned<Result<T, T::Error>>> + 'a
+    where
+        T: logos::Logos<'a, Source = str> + 'a,
+        T::Extras: Default,
+    {
+        T::lexer(self.content(index)).spanned().map(move |(token, span)| {
+            crate::Spanned::new(Span::from_logos(index, span), token)
+        })
+    }
+
     /// Calculate an offsets byte-position relative to the beginning of the line
     /// it is on.
     pub fn byte_offset_on_line(&self, offset: Offset) -> usize {
@@ -265,18 +1298,42 @@ impl SourceMap {
         Span::new(Offset::new(offset.source_index(), start), end - start)
     }
 
-    pub(crate) fn context_error_location(&self, offset: Offset) -> ContextErrorLocation {
-        let line = self.line_span(offset);
-        let start = line.start().byte();
-        let end = line.end().byte();
-        let content = self.content(offset.source_index());
-        let line_number = content[..offset.byte()].split('\n').count();
-        let column_number = 1 + (offset.byte() - start);
-        ContextErrorLocation::new(
-            content[start..end].into(),
-            line_number,
-            column_number,
-        )
+    /// The original content of a preprocessed entry.
+    #[track_caller]
+    pub(crate) fn original(&self, idx: SourceIndex) -> Option<&Original> {
+        assert!(self.contains(idx), "content index must belong to source map");
+        self.data[idx.data_index as usize].original.as_deref()
+    }
+
+    /// Capture a [`ContextErrorOrigin`] for a map entry as a whole, without a position.
+    ///
+    /// See [`ContextErrorOrigin::without_position`] for details.
+    ///
+    /// # Panics
+    ///
+    /// This function will panic if the index does not belong to this map.
+    #[track_caller]
+    pub fn context_error_source_origin(&self, idx: SourceIndex, note: &'static str) -> ContextErrorOrigin {
+        SourceProvider::context_error_source_origin(self, idx, note)
+    }
+
+    /// Capture a [`ContextErrorOrigin`] for the whole line containing a given [`Offset`].
+    ///
+    /// The full line is highlighted and the origin is located by its line number only.
+    /// This is intended for rules like maximum line lengths or trailing whitespace. Use
+    /// [`SourceMap::line_offset`] to find the offset of a line by its number.
+    ///
+    /// # Panics
+    ///
+    /// This function will panic if the offset does not belong to this map.
+    #[track_caller]
+    pub fn context_error_line_origin(&self, offset: Offset, note: &'static str) -> ContextErrorOrigin {
+        let start = self.line_span(offset).start();
+        let (content, byte) = displayed_position(self, start);
+        let text = content[byte..].split('\n').next().unwrap_or_default();
+        self.context_error_origin(start, note, None)
+            .with_highlight_len(text.strip_suffix('\r').unwrap_or(text).len())
+            .with_whole_line(true)
     }
 
     /// Capture a [`ContextErrorOrigin`] for a given [`Offset`].
@@ -286,24 +1343,307 @@ impl SourceMap {
         note: &'static str,
         context: Option<Offset>,
     ) -> ContextErrorOrigin {
-        let location = self.context_error_location(offset);
-        let context = context.map(|offset| self.context_error_location(offset));
-        ContextErrorOrigin::new(
+        SourceProvider::context_error_origin(self, offset, note, context)
+    }
+
+    /// Capture a [`ContextErrorOrigin`] for a given [`Offset`] together with the stack
+    /// of positions it was reached from.
+    ///
+    /// The stack starts with the innermost position, such as the include directive
+    /// for the source of the error position. Each entry is attached as a trace frame
+    /// with [`ContextErrorOrigin::with_trace_frame`]. Frames on the same line share
+    /// the captured line content.
+    ///
+    /// # Panics
+    ///
+    /// This function will panic if any of the offsets do not belong to this map.
+    pub fn context_error_origin_with_stack(
+        &self,
+        offset: Offset,
+        note: &'static str,
+        stack: &[Offset],
+    ) -> ContextErrorOrigin {
+        let mut lines = Vec::new();
+        let location = capture_location(self, offset, &mut lines);
+        let mut origin = ContextErrorOrigin::new(
             self.origin(offset.source_index()).clone(),
             note,
             location,
-            context,
-        )
+            None,
+        ).with_category(self.category(offset.source_index()));
+        for &frame_offset in stack {
+            let location = capture_location(self, frame_offset, &mut lines);
+            origin = origin.with_trace_frame(ContextErrorOrigin::new(
+                self.origin(frame_offset.source_index()).clone(),
+                "",
+                location,
+                None,
+            ).with_category(self.category(frame_offset.source_index())));
+        }
+        origin
+    }
+
+    /// Capture a [`ContextErrorAnnotation`] for a given [`Offset`].
+    ///
+    /// The annotation can be attached to a [`ContextErrorOrigin`] of the same source
+    /// with [`ContextErrorOrigin::with_annotation`].
+    pub fn context_error_annotation(
+        &self,
+        offset: Offset,
+        note: &'static str,
+    ) -> ContextErrorAnnotation {
+        SourceProvider::context_error_annotation(self, offset, note)
+    }
+}
+
+impl std::ops::Index<Span> for SourceMap {
+    type Output = str;
+
+    /// Retrieve the string corresponding to a [`Span`] in the map.
+    ///
+    /// This is the same as [`SourceMap::span_str`].
+    #[track_caller]
+    fn index(&self, span: Span) -> &Self::Output {
+        self.span_str(span)
+    }
+}
+
+/// A set of staged insertions into a [`SourceMap`].
+///
+/// Transactions are created with [`SourceMap::transaction`] and give full access to
+/// the map. All entries inserted during the transaction are removed again when it is
+/// dropped without being [committed](Self::commit). Other modifications, like changes
+/// to the categories of existing entries, are not reverted.
+///
+/// Any [`SourceIndex`] values produced for entries that were removed don't
+/// [belong](SourceMap::contains) to the map anymore, and will be rejected even if new
+/// entries are inserted in their place.
+pub struct Transaction<'a> {
+    map: &'a mut SourceMap,
+    len: usize,
+    next_anonymous: usize,
+    committed: bool,
+}
+
+impl Transaction<'_> {
+    /// Keep all entries inserted during the transaction.
+    pub fn commit(mut self) {
+        self.committed = true;
+    }
+
+    /// Remove all entries inserted during the transaction.
+    ///
+    /// This is the same as dropping the transaction.
+    pub fn rollback(self) {}
+}
+
+impl std::ops::Deref for Transaction<'_> {
+    type Target = SourceMap;
+
+    fn deref(&self) -> &Self::Target {
+        self.map
+    }
+}
+
+impl std::ops::DerefMut for Transaction<'_> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        self.map
+    }
+}
+
+/// A cheaply clonable read-only view of a [`SourceMap`], created with
+/// [`SourceMap::freeze`].
+///
+/// Readers are [`Send`] and [`Sync`], allowing worker threads to resolve offsets and
+/// render diagnostics concurrently while the original map stays mutable. All read
+/// access is provided by dereferencing to the frozen [`SourceMap`].
+#[derive(Debug, Clone)]
+pub struct SourceMapReader {
+    map: Arc<SourceMap>,
+}
+
+impl std::ops::Deref for SourceMapReader {
+    type Target = SourceMap;
+
+    fn deref(&self) -> &Self::Target {
+        &self.map
+    }
+}
+
+/// The mapping from the indices of a [`SourceMap`] to those of a map created with
+/// [`SourceMap::extract`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct IndexTranslation {
+    indices: HashMap<SourceIndex, SourceIndex>,
+}
+
+impl IndexTranslation {
+    /// The index in the extracted map of a [`SourceIndex`], if it was extracted.
+    pub fn index(&self, idx: SourceIndex) -> Option<SourceIndex> {
+        self.indices.get(&idx).copied()
+    }
+
+    /// The [`Offset`] in the extracted map, if its source was extracted.
+    pub fn offset(&self, offset: Offset) -> Option<Offset> {
+        Some(Offset::new(self.index(offset.source_index())?, offset.byte()))
+    }
+
+    /// The [`Span`] in the extracted map, if its source was extracted.
+    pub fn span(&self, span: Span) -> Option<Span> {
+        Some(Span::new(self.offset(span.start())?, span.byte_len()))
+    }
+
+    /// The number of extracted entries.
+    pub fn len(&self) -> usize {
+        self.indices.len()
+    }
+
+    /// Whether no entries were extracted.
+    pub fn is_empty(&self) -> bool {
+        self.indices.is_empty()
+    }
+}
+
+impl Drop for Transaction<'_> {
+    fn drop(&mut self) {
+        if !self.committed {
+            self.map.truncate(self.len);
+            self.map.next_anonymous = self.next_anonymous;
+        }
+    }
+}
+
+enum DirectoryEntry {
+    Open(Origin, Box<str>),
+    Previous(SourceIndex),
+    Skipped(Arc<Path>, SkipReason),
+}
+
+/// A fingerprint of a file's content and metadata, produced by
+/// [`SourceMap::fingerprint_file`].
+///
+/// The content hash is a 64-bit FNV-1a hash, which is stable across runs and
+/// platforms, so fingerprints can be persisted between builds.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Fingerprint {
+    content_hash: u64,
+    len: u64,
+    modified: Option<SystemTime>,
+}
+
+impl Fingerprint {
+    /// Reconstruct a fingerprint from previously persisted parts.
+    pub fn new(content_hash: u64, len: u64, modified: Option<SystemTime>) -> Self {
+        Self { content_hash, len, modified }
+    }
+
+    /// The hash of the file content.
+    pub fn content_hash(&self) -> u64 {
+        self.content_hash
+    }
+
+    /// The length of the file content in bytes.
+    pub fn len(&self) -> u64 {
+        self.len
+    }
+
+    /// Whether the file was empty.
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// The modification time of the file, if the platform provides one.
+    pub fn modified(&self) -> Option<SystemTime> {
+        self.modified
+    }
+
+    /// Compute the content hash for the given content.
+    pub fn hash_content(content: &[u8]) -> u64 {
+        const OFFSET_BASIS: u64 = 0xcbf2_9ce4_8422_2325;
+        const PRIME: u64 = 0x0000_0100_0000_01b3;
+
+        content.iter().fold(OFFSET_BASIS, |hash, &byte| (hash ^ u64::from(byte)).wrapping_mul(PRIME))
+    }
+}
+
+/// The outcome of loading a directory tree with [`SourceMap::load_directory_report`],
+/// [`SourceMap::load_directory_lenient`], or [`SourceMap::load_directory_with_progress`].
+#[derive(Debug, Clone, Default)]
+pub struct LoadReport {
+    inserted: Vec<SourceIndex>,
+    previous: Vec<SourceIndex>,
+    skipped: Vec<(Arc<Path>, SkipReason)>,
+    errors: Vec<LoadError>,
+}
+
+impl LoadReport {
+    /// The indices of all newly inserted entries.
+    pub fn inserted(&self) -> &[SourceIndex] {
+        &self.inserted
+    }
+
+    /// The indices of all files that were already loaded into the map, either before
+    /// or earlier in the same load.
+    pub fn previous(&self) -> &[SourceIndex] {
+        &self.previous
+    }
+
+    /// All paths that were skipped, together with the reason.
+    pub fn skipped(&self) -> &[(Arc<Path>, SkipReason)] {
+        &self.skipped
     }
+
+    /// All errors that were encountered.
+    ///
+    /// This can only be non-empty for reports from
+    /// [`SourceMap::load_directory_lenient`].
+    pub fn errors(&self) -> &[LoadError] {
+        &self.errors
+    }
+}
+
+/// The order in which files found by directory loads are inserted into a [`SourceMap`].
+///
+/// Set with [`SourceMap::with_directory_order`]. The order also applies to the
+/// paths in a [`LoadReport`] and to progress callbacks.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum DirectoryOrder {
+    /// Sort by path, comparing the components of paths in order.
+    ///
+    /// This is independent of the platform and file system. Files and directories are
+    /// not grouped, so `a/b.ext` comes before `a.ext`, which comes before `b/a.ext`.
+    #[default]
+    Path,
+    /// Keep the order in which the file system returns directory entries.
+    ///
+    /// This order can differ between machines, but avoids sorting large directories.
+    FileSystem,
+}
+
+/// The reason a path was skipped during a directory load.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum SkipReason {
+    /// The path doesn't denote a regular file.
+    NotFile,
+    /// The file name doesn't have the requested extension.
+    Extension,
+    /// The file content is not valid UTF-8.
+    Binary,
+    /// The file exceeds the [maximum file size](SourceMap::with_max_file_size).
+    TooLarge,
 }
 
 pub(super) enum ReadError {
     Previous(SourceIndex),
-    Read(Arc<Path>, Arc<std::io::Error>),
+    Load(LoadError),
 }
 
 /// Errors that can occur while loading [`SourceMap`] entries from the file system.
+///
+/// Some variants only exist with specific features enabled, so the enum is
+/// non-exhaustive to keep features additive.
 #[derive(Debug, Clone)]
+#[non_exhaustive]
 pub enum LoadError {
     /// An error occured while trying to find files in a directory tree.
     Find {
@@ -314,6 +1654,17 @@ pub enum LoadError {
         /// The error that occured during traversal.
         error: Arc<walkdir::Error>,
     },
+    /// An error occured while trying to find files in a directory tree while
+    /// [respecting ignore files](SourceMap::with_respect_ignore_files).
+    #[cfg(feature = "ignore")]
+    FindIgnoring {
+        /// The root of the directory tree we searched in.
+        root: Arc<Path>,
+        /// The extension of the files we're trying to load.
+        extension: Arc<str>,
+        /// The error that occured during traversal or while parsing an ignore file.
+        error: Arc<ignore::Error>,
+    },
     /// An error occured while reading a file.
     Read {
         /// The file we tried to read.
@@ -321,13 +1672,46 @@ pub enum LoadError {
         /// The error that occured during reading.
         error: Arc<std::io::Error>,
     },
+    /// A file did not contain valid UTF-8.
+    InvalidUtf8 {
+        /// The file we tried to read.
+        file: Arc<Path>,
+        /// The number of bytes at the start of the file that are valid UTF-8.
+        valid_up_to: usize,
+        /// The line number of the first invalid byte.
+        line: usize,
+        /// The column number of the first invalid byte, counted in bytes.
+        column: usize,
+    },
+    /// A file exceeded the [maximum file size](SourceMap::with_max_file_size).
+    TooLarge {
+        /// The file we tried to read.
+        file: Arc<Path>,
+        /// The size of the file in bytes.
+        size: u64,
+        /// The maximum allowed size in bytes.
+        limit: u64,
+    },
+    /// An error occured while fetching content from a URL.
+    #[cfg(feature = "http")]
+    Fetch {
+        /// The URL we tried to fetch.
+        url: Arc<str>,
+        /// The error that occured during the request.
+        error: Arc<ureq::Error>,
+    },
 }
 
 impl std::error::Error for LoadError {
     fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
         match self {
             LoadError::Find { error, .. } => Some(error),
+            #[cfg(feature = "ignore")]
+            LoadError::FindIgnoring { error, .. } => Some(error),
             LoadError::Read { error, .. } => Some(error),
+            LoadError::InvalidUtf8 { .. } | LoadError::TooLarge { .. } => None,
+            #[cfg(feature = "http")]
+            LoadError::Fetch { error, .. } => Some(error),
         }
     }
 }
@@ -338,16 +1722,122 @@ impl std::fmt::Display for LoadError {
             LoadError::Find { root, extension, .. } => {
                 write!(f, "Failed to fully search `{}` for `*{extension}` files", root.display())
             },
+            #[cfg(feature = "ignore")]
+            LoadError::FindIgnoring { root, extension, .. } => {
+                write!(f, "Failed to fully search `{}` for `*{extension}` files", root.display())
+            },
             LoadError::Read { file, .. } => {
                 write!(f, "Failed to read from file `{}`", file.display())
             },
+            LoadError::InvalidUtf8 { file, line, column, .. } => {
+                write!(
+                    f,
+                    "File `{}` contains invalid UTF-8 at line {line}, column {column}",
+                    file.display(),
+                )
+            },
+            LoadError::TooLarge { file, size, limit } => {
+                write!(
+                    f,
+                    "File `{}` is {size} bytes in size, exceeding the limit of {limit} bytes",
+                    file.display(),
+                )
+            },
+            #[cfg(feature = "http")]
+            LoadError::Fetch { url, .. } => {
+                write!(f, "Failed to fetch from `{url}`")
+            },
+        }
+    }
+}
+
+/// Errors that can occur while resolving a [`Span`] with [`SourceMap::get_span_str`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SpanResolveError {
+    /// The span belongs to a different map.
+    ForeignMap,
+    /// The span belongs to an entry that is no longer in the map, for example after
+    /// a [`Transaction`] was rolled back.
+    MissingEntry,
+    /// The span exceeds the content of its entry.
+    OutOfBounds {
+        /// The byte-position of the end of the span.
+        end: usize,
+        /// The length of the content in bytes.
+        len: usize,
+    },
+    /// The start or end of the span is not on a character boundary.
+    NotCharBoundary {
+        /// The byte-position that is not on a character boundary.
+        byte: usize,
+    },
+}
+
+impl std::error::Error for SpanResolveError {}
+
+impl std::fmt::Display for SpanResolveError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SpanResolveError::ForeignMap => {
+                write!(f, "Span belongs to a different source map")
+            },
+            SpanResolveError::MissingEntry => {
+                write!(f, "Span belongs to an entry that is no longer in the source map")
+            },
+            SpanResolveError::OutOfBounds { end, len } => {
+                write!(f, "Span ends at byte {end}, exceeding the content length of {len} bytes")
+            },
+            SpanResolveError::NotCharBoundary { byte } => {
+                write!(f, "Span boundary at byte {byte} is not on a character boundary")
+            },
         }
     }
 }
 
+#[derive(Clone)]
 struct SourceData {
     origin: Origin,
-    content: Box<str>,
+    content: SourceContent,
+    category: SourceCategory,
+    global_start: Option<u32>,
+    line_starts: OnceLock<Arc<[usize]>>,
+    original: Option<Arc<Original>>,
+    version: u32,
+    anchors: Vec<(&'static str, usize)>,
+    generation: u32,
+}
+
+
+impl std::fmt::Debug for SourceData {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("SourceData")
+            .field("origin", &self.origin)
+            .field("len", &self.content.len())
+            .field("category", &self.category)
+            .finish()
+    }
+}
+
+/// The category of a [`SourceMap`] entry.
+///
+/// Categories can be used with
+/// [`DisplayOptions::with_category_display`](crate::DisplayOptions::with_category_display)
+/// to de-emphasize origins in sources not under the user's control.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
+pub enum SourceCategory {
+    /// Code written by the user.
+    #[default]
+    User,
+    /// Third-party code included from elsewhere.
+    Vendored,
+    /// Code produced by a generator.
+    Generated,
+    /// Code provided by the tool itself, like a standard library.
+    Internal,
+}
+
+impl SourceCategory {
+    pub(crate) const COUNT: usize = 4;
 }
 
 /// The outcome of an insertion into a [`SourceMap`].
@@ -374,11 +1864,21 @@ impl Insert {
 
 /// The origin of a [`SourceMap`] entry.
 #[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[non_exhaustive]
 pub enum Origin {
     /// The entry is designated as having come from this file.
     File(Arc<Path>),
     /// The entry came from a named source instead of a file path.
     Named(Arc<str>),
+    /// The entry was fetched from this URL.
+    Url(Arc<str>),
+    /// The entry was read from the standard input.
+    Stdin,
+    /// The entry is code generated by a named generator.
+    Generated {
+        /// The name of the generator that produced the code.
+        generator: Arc<str>,
+    },
 }
 
 impl Origin {
@@ -397,9 +1897,47 @@ impl Origin {
     {
         Self::Named(name.as_ref().into())
     }
+
+    /// Convenience constructor from anything that can be a URL.
+    pub fn from_url<U>(url: U) -> Self
+    where
+        U: AsRef<str>,
+    {
+        Self::Url(url.as_ref().into())
+    }
+
+    /// Convenience constructor from anything that can be a generator name.
+    pub fn from_generated<N>(generator: N) -> Self
+    where
+        N: AsRef<str>,
+    {
+        Self::Generated { generator: generator.as_ref().into() }
+    }
+}
+
+fn normalize_path_lexically(path: &Path) -> PathBuf {
+    let mut normalized = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::CurDir => {},
+            Component::ParentDir if matches!(
+                normalized.components().next_back(),
+                Some(Component::Normal(_)),
+            ) => {
+                normalized.pop();
+            },
+            component => normalized.push(component),
+        }
+    }
+    normalized
+}
+
+/// The byte-positions of the starts of all lines in a content.
+pub(crate) fn line_starts_of(content: &str) -> Arc<[usize]> {
+    std::iter::once(0).chain(content.match_indices('\n').map(|(index, _)| index + 1)).collect()
 }
 
-fn fetch_next_source_map_id() -> u32 {
+pub(crate) fn fetch_next_source_map_id() -> u32 {
     static NEXT: AtomicU32 = AtomicU32::new(0);
     NEXT.fetch_update(Ordering::SeqCst, Ordering::SeqCst, |next| next.checked_add(1))
         .expect("source map id sequence exhausted")
@@ -0,0 +1,537 @@
+use std::fmt::{self, Write};
+use std::io::IsTerminal;
+use std::path::{Path, PathBuf};
+
+use crate::SourceCategory;
+use crate::display::{count_digits, display_fn, paint, Style};
+
+
+/// Options controlling the display of [`ContextError`](crate::ContextError) and
+/// [`ContextErrorOrigin`](crate::ContextErrorOrigin) values.
+///
+/// The default options produce the same output as the plain
+/// [`Display`](std::fmt::Display) implementations.
+#[derive(Debug, Clone, Default)]
+pub struct DisplayOptions {
+    strings: DisplayStrings,
+    verbosity: Verbosity,
+    category_displays: [CategoryDisplay; SourceCategory::COUNT],
+    forward_slashes: bool,
+    lowercase_drive_letters: bool,
+    path_base: Option<PathBuf>,
+    colors: bool,
+    zero_based_columns: bool,
+    location_ranges: bool,
+    column_ruler: bool,
+    highlight_widths: bool,
+    tilde_underlines: bool,
+    max_cause_depth: Option<usize>,
+    dedup_causes: bool,
+    wrap_width: Option<usize>,
+    gutter_width: Option<usize>,
+}
+
+impl DisplayOptions {
+    /// Construct a default set of options.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Construct a set of options producing output that is stable across platforms
+    /// and environments.
+    ///
+    /// This uses the default options with [forward slashes](Self::with_forward_slashes),
+    /// [lowercase drive letters](Self::with_lowercase_drive_letters), paths
+    /// [relative](Self::with_path_base) to the current directory, a
+    /// [gutter width](Self::with_gutter_width) of four digits, and without
+    /// [colors](Self::with_colors), which makes the output suitable for snapshot tests.
+    pub fn stable() -> Self {
+        Self::default()
+            .with_forward_slashes(true)
+            .with_lowercase_drive_letters(true)
+            .with_path_base(std::env::current_dir().ok())
+            .with_gutter_width(Some(4))
+            .with_colors(false)
+    }
+
+    /// Use a different set of [`DisplayStrings`] for the fixed parts of the output.
+    pub fn with_strings(mut self, strings: DisplayStrings) -> Self {
+        self.strings = strings;
+        self
+    }
+
+    /// The [`DisplayStrings`] used for the fixed parts of the output.
+    pub fn strings(&self) -> &DisplayStrings {
+        &self.strings
+    }
+
+    /// Set the [`Verbosity`] of the output.
+    pub fn with_verbosity(mut self, verbosity: Verbosity) -> Self {
+        self.verbosity = verbosity;
+        self
+    }
+
+    /// The [`Verbosity`] of the output.
+    pub fn verbosity(&self) -> Verbosity {
+        self.verbosity
+    }
+
+    /// Limit the number of error sources shown in the cause chain with
+    /// [`Verbosity::Full`].
+    ///
+    /// A limit of zero skips the cause chain entirely.
+    pub fn with_max_cause_depth(mut self, limit: Option<usize>) -> Self {
+        self.max_cause_depth = limit;
+        self
+    }
+
+    /// The maximum number of error sources shown in the cause chain.
+    pub fn max_cause_depth(&self) -> Option<usize> {
+        self.max_cause_depth
+    }
+
+    /// Skip error sources in the cause chain with the same message as the error or
+    /// cause directly before them.
+    ///
+    /// This avoids repeated lines for wrapping errors that display the message of their
+    /// source. Skipped sources don't count towards the
+    /// [maximum cause depth](Self::with_max_cause_depth).
+    pub fn with_cause_deduplication(mut self, enabled: bool) -> Self {
+        self.dedup_causes = enabled;
+        self
+    }
+
+    /// Whether error sources with the same message as the one before them are skipped.
+    pub fn cause_deduplication(&self) -> bool {
+        self.dedup_causes
+    }
+
+    /// Soft-wrap error messages and notes to the given width in characters.
+    ///
+    /// Continuation lines of messages are indented to align with the text after the
+    /// `error:` or `cause:` label, and continuation lines of notes are aligned with the
+    /// text after the markers. Words longer than the available width are not split.
+    /// Source lines and the [short](Verbosity::Short) output are never wrapped.
+    pub fn with_wrap_width(mut self, width: Option<usize>) -> Self {
+        self.wrap_width = width;
+        self
+    }
+
+    /// The width messages and notes are wrapped to.
+    pub fn wrap_width(&self) -> Option<usize> {
+        self.wrap_width
+    }
+
+    /// Pad line numbers in the gutter of snippets to at least the given number of digits.
+    ///
+    /// By default, the gutter is as wide as the largest displayed line number, so the
+    /// layout changes when a highlight moves to a line with more digits.
+    pub fn with_gutter_width(mut self, width: Option<usize>) -> Self {
+        self.gutter_width = width;
+        self
+    }
+
+    /// The minimum number of digits line numbers are padded to.
+    pub fn gutter_width(&self) -> Option<usize> {
+        self.gutter_width
+    }
+
+    /// The number of digits line numbers up to a maximum are padded to.
+    pub(crate) fn line_number_width(&self, max_line_number: usize) -> usize {
+        count_digits(max_line_number).max(self.gutter_width.unwrap_or(0))
+    }
+
+    /// Set how origins in sources of a [`SourceCategory`] are displayed.
+    pub fn with_category_display(mut self, category: SourceCategory, display: CategoryDisplay) -> Self {
+        self.category_displays[category as usize] = display;
+        self
+    }
+
+    /// How origins in sources of a [`SourceCategory`] are displayed.
+    pub fn category_display(&self, category: SourceCategory) -> CategoryDisplay {
+        self.category_displays[category as usize]
+    }
+
+    /// Display file paths with `/` separators regardless of platform.
+    pub fn with_forward_slashes(mut self, enabled: bool) -> Self {
+        self.forward_slashes = enabled;
+        self
+    }
+
+    /// Whether file paths are displayed with `/` separators regardless of platform.
+    pub fn forward_slashes(&self) -> bool {
+        self.forward_slashes
+    }
+
+    /// Display drive letters in file paths, as in `C:\`, in lowercase.
+    pub fn with_lowercase_drive_letters(mut self, enabled: bool) -> Self {
+        self.lowercase_drive_letters = enabled;
+        self
+    }
+
+    /// Whether drive letters in file paths are displayed in lowercase.
+    pub fn lowercase_drive_letters(&self) -> bool {
+        self.lowercase_drive_letters
+    }
+
+    /// Display file paths below the given directory relative to it.
+    ///
+    /// Other paths, including relative ones, are displayed as they are.
+    pub fn with_path_base(mut self, base: Option<PathBuf>) -> Self {
+        self.path_base = base;
+        self
+    }
+
+    /// The directory file paths are displayed relative to.
+    pub fn path_base(&self) -> Option<&Path> {
+        self.path_base.as_deref()
+    }
+
+    /// Use ANSI escape codes for colored output.
+    pub fn with_colors(mut self, enabled: bool) -> Self {
+        self.colors = enabled;
+        self
+    }
+
+    /// Use colored output depending on a [`ColorChoice`] for the given output stream.
+    ///
+    /// This is the same as passing the result of [`ColorChoice::enabled_for`] to
+    /// [`with_colors`](Self::with_colors).
+    pub fn with_color_choice<S>(self, choice: ColorChoice, stream: &S) -> Self
+    where
+        S: IsTerminal,
+    {
+        self.with_colors(choice.enabled_for(stream))
+    }
+
+    /// Whether ANSI escape codes are used for colored output.
+    pub fn colors(&self) -> bool {
+        self.colors
+    }
+
+    /// Report column numbers counted from zero instead of one.
+    ///
+    /// This applies to all textual and machine-readable outputs, for interoperability
+    /// with tools like language servers. Line numbers are not affected. A [`Location`]
+    /// can be converted with [`Column::to_zero_based`] for the same effect.
+    ///
+    /// [`Location`]: crate::Location
+    /// [`Column::to_zero_based`]: crate::Column::to_zero_based
+    pub fn with_zero_based_columns(mut self, enabled: bool) -> Self {
+        self.zero_based_columns = enabled;
+        self
+    }
+
+    /// Whether column numbers are reported counted from zero.
+    pub fn zero_based_columns(&self) -> bool {
+        self.zero_based_columns
+    }
+
+    /// Report the extent of highlights covering more than one character in locations.
+    ///
+    /// When enabled, locations in in-line output and `-->` headers include the column
+    /// after the highlight, as in `path:2:3-2:9`, or ``in `name`, line 2, column 3 to
+    /// line 2, column 9`` for named origins.
+    pub fn with_location_ranges(mut self, enabled: bool) -> Self {
+        self.location_ranges = enabled;
+        self
+    }
+
+    /// Whether locations include the extent of highlights.
+    pub fn location_ranges(&self) -> bool {
+        self.location_ranges
+    }
+
+    /// Show a column ruler above the source lines of snippets.
+    ///
+    /// The ruler marks every fifth column with `+` and every tenth column with the last
+    /// digit of its tens, as in `....+....1....+....2`, which helps with fixed-width and
+    /// alignment-sensitive formats. Columns are counted in characters.
+    pub fn with_column_ruler(mut self, enabled: bool) -> Self {
+        self.column_ruler = enabled;
+        self
+    }
+
+    /// Whether snippets include a column ruler.
+    pub fn column_ruler(&self) -> bool {
+        self.column_ruler
+    }
+
+    /// Report the width of primary highlights in bytes and characters after their note,
+    /// as in `^^^ note (3 bytes, 3 chars)`.
+    pub fn with_highlight_widths(mut self, enabled: bool) -> Self {
+        self.highlight_widths = enabled;
+        self
+    }
+
+    /// Whether the widths of primary highlights are reported.
+    pub fn highlight_widths(&self) -> bool {
+        self.highlight_widths
+    }
+
+    /// Underline primary highlights covering more than one character with `^~~~~`
+    /// instead of `^^^^^`, marking where the highlight starts.
+    pub fn with_tilde_underlines(mut self, enabled: bool) -> Self {
+        self.tilde_underlines = enabled;
+        self
+    }
+
+    /// Whether primary highlights are underlined with tildes.
+    pub fn tilde_underlines(&self) -> bool {
+        self.tilde_underlines
+    }
+
+    /// Convert a one-based column number into the reported column number.
+    pub(crate) fn reported_column(&self, column_number: usize) -> usize {
+        if self.zero_based_columns {
+            column_number - 1
+        } else {
+            column_number
+        }
+    }
+
+    pub(crate) fn paint<'a, T>(&self, style: Style, value: T) -> impl fmt::Display + 'a
+    where
+        T: fmt::Display + 'a,
+    {
+        paint(self.colors, style, value)
+    }
+
+    pub(crate) fn display_path<'a>(&'a self, path: &'a Path) -> impl fmt::Display + 'a {
+        let path = self.path_base.as_deref()
+            .and_then(|base| path.strip_prefix(base).ok())
+            .unwrap_or(path);
+        display_fn(move |f| {
+            if !(self.forward_slashes || self.lowercase_drive_letters) {
+                return write!(f, "{}", path.display());
+            }
+            let path = path.to_string_lossy();
+            let mut chars = path.chars();
+            if self.lowercase_drive_letters {
+                if let [drive @ b'A'..=b'Z', b':', ..] = path.as_bytes() {
+                    f.write_char(drive.to_ascii_lowercase() as char)?;
+                    chars.next();
+                }
+            }
+            for c in chars {
+                f.write_char(if self.forward_slashes && c == '\\' { '/' } else { c })?;
+            }
+            Ok(())
+        })
+    }
+}
+
+/// When to use colored output.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
+pub enum ColorChoice {
+    /// Always use colors.
+    Always,
+    /// Never use colors.
+    #[default]
+    Never,
+    /// Use colors if the output stream is a terminal.
+    ///
+    /// A non-empty `NO_COLOR` environment variable disables colors, and a
+    /// `CLICOLOR_FORCE` environment variable other than `0` enables colors regardless
+    /// of the output stream.
+    Auto,
+}
+
+impl ColorChoice {
+    /// Whether colors should be used for the given output stream.
+    pub fn enabled_for<S>(self, stream: &S) -> bool
+    where
+        S: IsTerminal,
+    {
+        match self {
+            Self::Always => true,
+            Self::Never => false,
+            Self::Auto => {
+                if std::env::var_os("NO_COLOR").is_some_and(|value| !value.is_empty()) {
+                    false
+                } else if std::env::var_os("CLICOLOR_FORCE").is_some_and(|value| value != "0") {
+                    true
+                } else {
+                    stream.is_terminal()
+                }
+            },
+        }
+    }
+}
+
+/// How origins in sources of a specific [`SourceCategory`] are displayed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
+pub enum CategoryDisplay {
+    /// Display the origin location and its source context.
+    #[default]
+    Full,
+    /// Only display the origin location.
+    Location,
+    /// Don't display the origin at all.
+    Hidden,
+}
+
+/// The amount of detail included in the output.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
+pub enum Verbosity {
+    /// A single line with the location of the first origin followed by the error,
+    /// as in `path:2:3: error: ...`.
+    Short,
+    /// The error and the source contexts, without the error source chain.
+    Medium,
+    /// The error, its source chain, and the source contexts.
+    #[default]
+    Full,
+}
+
+/// The fixed strings used in diagnostic output.
+///
+/// This allows localizing the output without having to reimplement the display logic.
+/// The [`Default`] implementation returns [`DisplayStrings::ENGLISH`].
+///
+/// New strings can be added in future versions, so custom strings are built from an
+/// existing set with the `with_*` methods, as in
+/// `DisplayStrings::ENGLISH.with_error("fehler")`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct DisplayStrings {
+    /// Label for the main error message, as in `error: ...`.
+    pub error: &'static str,
+    /// Label for error source messages, as in `cause: ...`.
+    pub cause: &'static str,
+    /// Prefix for file locations in in-line output, as in `at path:2:3`.
+    pub at: &'static str,
+    /// Prefix for named locations in in-line output, as in ``in `name`, line 2, column 3``.
+    pub in_named: &'static str,
+    /// Description of generated code locations, as in ``in generated code from `x`, ...``.
+    pub generated: &'static str,
+    /// Conjunction for the last of multiple in-line locations, as in `at a:1:1 and b:1:1`.
+    pub and: &'static str,
+    /// Label for byte offsets in named locations of binary sources.
+    pub offset: &'static str,
+    /// Label for line numbers in named locations.
+    pub line: &'static str,
+    /// Label for column numbers in named locations.
+    pub column: &'static str,
+    /// Conjunction for the end of location ranges in named locations, as in
+    /// `column 3 to line 2, column 9`.
+    pub to: &'static str,
+    /// Description of trace frames, as in `= included from a.ext:10:1`.
+    pub included_from: &'static str,
+    /// Label for the concluding hint of a [`FriendlyReport`](crate::FriendlyReport).
+    pub hint: &'static str,
+    /// Unit for highlight widths in bytes, as in `(3 bytes, 3 chars)`.
+    pub bytes: &'static str,
+    /// Unit for highlight widths in characters, as in `(3 bytes, 3 chars)`.
+    pub chars: &'static str,
+}
+
+impl DisplayStrings {
+    /// The default english strings.
+    pub const ENGLISH: Self = Self {
+        error: "error",
+        cause: "cause",
+        at: "at",
+        in_named: "in",
+        generated: "generated code from",
+        and: "and",
+        offset: "offset",
+        line: "line",
+        column: "column",
+        to: "to",
+        included_from: "included from",
+        hint: "hint",
+        bytes: "bytes",
+        chars: "chars",
+    };
+
+    /// Replace the [`error`](Self::error) string.
+    pub const fn with_error(mut self, error: &'static str) -> Self {
+        self.error = error;
+        self
+    }
+
+    /// Replace the [`cause`](Self::cause) string.
+    pub const fn with_cause(mut self, cause: &'static str) -> Self {
+        self.cause = cause;
+        self
+    }
+
+    /// Replace the [`at`](Self::at) string.
+    pub const fn with_at(mut self, at: &'static str) -> Self {
+        self.at = at;
+        self
+    }
+
+    /// Replace the [`in_named`](Self::in_named) string.
+    pub const fn with_in_named(mut self, in_named: &'static str) -> Self {
+        self.in_named = in_named;
+        self
+    }
+
+    /// Replace the [`generated`](Self::generated) string.
+    pub const fn with_generated(mut self, generated: &'static str) -> Self {
+        self.generated = generated;
+        self
+    }
+
+    /// Replace the [`and`](Self::and) string.
+    pub const fn with_and(mut self, and: &'static str) -> Self {
+        self.and = and;
+        self
+    }
+
+    /// Replace the [`offset`](Self::offset) string.
+    pub const fn with_offset(mut self, offset: &'static str) -> Self {
+        self.offset = offset;
+        self
+    }
+
+    /// Replace the [`line`](Self::line) string.
+    pub const fn with_line(mut self, line: &'static str) -> Self {
+        self.line = line;
+        self
+    }
+
+    /// Replace the [`column`](Self::column) string.
+    pub const fn with_column(mut self, column: &'static str) -> Self {
+        self.column = column;
+        self
+    }
+
+    /// Replace the [`to`](Self::to) string.
+    pub const fn with_to(mut self, to: &'static str) -> Self {
+        self.to = to;
+        self
+    }
+
+    /// Replace the [`included_from`](Self::included_from) string.
+    pub const fn with_included_from(mut self, included_from: &'static str) -> Self {
+        self.included_from = included_from;
+        self
+    }
+
+    /// Replace the [`hint`](Self::hint) string.
+    pub const fn with_hint(mut self, hint: &'static str) -> Self {
+        self.hint = hint;
+        self
+    }
+
+    /// Replace the [`bytes`](Self::bytes) string.
+    pub const fn with_bytes(mut self, bytes: &'static str) -> Self {
+        self.bytes = bytes;
+        self
+    }
+
+    /// Replace the [`chars`](Self::chars) string.
+    pub const fn with_chars(mut self, chars: &'static str) -> Self {
+        self.chars = chars;
+        self
+    }
+}
+
+impl Default for DisplayStrings {
+    fn default() -> Self {
+        Self::ENGLISH
+    }
+}
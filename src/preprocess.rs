@@ -0,0 +1,219 @@
+use std::sync::{Arc, OnceLock};
+
+use crate::{Offset, Origin, SourceIndex, SourceMap};
+use crate::map::line_starts_of;
+
+
+/// A function preparing contents on insertion into a [`SourceMap`].
+///
+/// The function receives the [`Origin`] and original content of every inserted entry,
+/// and returns `None` to keep the content unchanged. Preprocessors are registered with
+/// [`SourceMap::with_preprocessor`].
+pub type Preprocessor = Arc<dyn Fn(&Origin, &str) -> Option<Preprocessed> + Send + Sync>;
+
+/// A preprocessed content together with the positions of its parts in the original
+/// content.
+///
+/// The content is built from [kept](Self::keep) parts of the original content and
+/// [inserted](Self::insert) text, like expanded includes. Positions in inserted text
+/// are mapped to the original position the text was inserted at.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct Preprocessed {
+    content: String,
+    segments: Segments,
+}
+
+/// The positions of the parts of a preprocessed content in the original content.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub(crate) struct Segments {
+    segments: Vec<Segment>,
+    len: usize,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Segment {
+    start: usize,
+    original_start: usize,
+    kept: bool,
+}
+
+impl Preprocessed {
+    /// Construct an empty preprocessed content.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Append a part of the original content starting at a byte-position.
+    pub fn keep(&mut self, original_start: usize, text: &str) {
+        self.segments.push(original_start, true, text.len());
+        self.content.push_str(text);
+    }
+
+    /// Append text that is not part of the original content, inserted at a
+    /// byte-position in the original content.
+    pub fn insert(&mut self, original_position: usize, text: &str) {
+        self.segments.push(original_position, false, text.len());
+        self.content.push_str(text);
+    }
+
+    /// The preprocessed content.
+    pub fn content(&self) -> &str {
+        &self.content
+    }
+
+    /// Map a byte-position in the preprocessed content to the original content.
+    pub fn original_byte(&self, byte: usize) -> usize {
+        self.segments.original_byte(byte)
+    }
+
+    /// Map a byte-position in the original content to the preprocessed content.
+    ///
+    /// Positions in removed parts of the original content are mapped to the start of the
+    /// next kept part, or to the end of the preprocessed content if there is none.
+    pub fn preprocessed_byte(&self, original_byte: usize) -> usize {
+        self.segments.preprocessed_byte(original_byte)
+    }
+
+    /// Split into the preprocessed content and the positions of its parts.
+    pub(crate) fn into_parts(self) -> (String, Segments) {
+        (self.content, self.segments)
+    }
+
+    /// Remove a leading `#!` line, keeping its line break so line numbers still match.
+    ///
+    /// Returns `None` if the content doesn't start with a `#!` line.
+    pub fn strip_shebang(content: &str) -> Option<Self> {
+        if !content.starts_with("#!") {
+            return None;
+        }
+        let start = content.find('\n').unwrap_or(content.len());
+        let mut preprocessed = Self::new();
+        preprocessed.keep(start, &content[start..]);
+        Some(preprocessed)
+    }
+
+    /// Remove all carriage returns.
+    ///
+    /// Returns `None` if the content doesn't contain any.
+    pub fn strip_carriage_returns(content: &str) -> Option<Self> {
+        if !content.contains('\r') {
+            return None;
+        }
+        let mut preprocessed = Self::new();
+        let mut start = 0;
+        for part in content.split('\r') {
+            if !part.is_empty() {
+                preprocessed.keep(start, part);
+            }
+            start += part.len() + 1;
+        }
+        Some(preprocessed)
+    }
+}
+
+impl Segments {
+    fn push(&mut self, original_start: usize, kept: bool, len: usize) {
+        self.segments.push(Segment { start: self.len, original_start, kept });
+        self.len += len;
+    }
+
+    fn original_byte(&self, byte: usize) -> usize {
+        let index = self.segments.partition_point(|segment| segment.start <= byte);
+        let Some(segment) = index.checked_sub(1).map(|index| self.segments[index]) else {
+            return byte;
+        };
+        match segment.kept {
+            true => segment.original_start + (byte - segment.start),
+            false => segment.original_start,
+        }
+    }
+
+    fn preprocessed_byte(&self, original_byte: usize) -> usize {
+        let kept = self.segments.iter().enumerate().filter(|(_, segment)| segment.kept);
+        for (index, segment) in kept {
+            let end = self.segments.get(index + 1).map_or(self.len, |next| next.start);
+            let original_end = segment.original_start + (end - segment.start);
+            if original_byte < segment.original_start {
+                return segment.start;
+            }
+            if original_byte < original_end {
+                return segment.start + (original_byte - segment.original_start);
+            }
+        }
+        self.len
+    }
+}
+
+/// The original content of a preprocessed entry.
+///
+/// The preprocessed content itself is only stored as the content of the entry.
+#[derive(Debug)]
+pub(crate) struct Original {
+    pub(crate) content: Arc<str>,
+    segments: Segments,
+    line_starts: OnceLock<Arc<[usize]>>,
+}
+
+impl Original {
+    pub(crate) fn new(content: Arc<str>, segments: Segments) -> Self {
+        Self { content, segments, line_starts: OnceLock::new() }
+    }
+}
+
+impl SourceMap {
+    /// The original content associated with a [`SourceIndex`], before it was
+    /// preprocessed.
+    ///
+    /// This is the same as [`SourceMap::content`] for entries that were not changed by
+    /// a [`Preprocessor`].
+    ///
+    /// # Panics
+    ///
+    /// This function will panic if the index does not belong to this map.
+    #[track_caller]
+    pub fn original_content(&self, idx: SourceIndex) -> &str {
+        match self.original(idx) {
+            Some(original) => &original.content,
+            None => self.content(idx),
+        }
+    }
+
+    /// Map an [`Offset`] to its byte-position in the [original content](Self::original_content).
+    ///
+    /// # Panics
+    ///
+    /// This function will panic if the offset does not belong to this map.
+    #[track_caller]
+    pub fn original_byte(&self, offset: Offset) -> usize {
+        match self.original(offset.source_index()) {
+            Some(original) => original.segments.original_byte(offset.byte()),
+            None => offset.byte(),
+        }
+    }
+
+    /// The [`Offset`] in the content associated with a [`SourceIndex`] for a
+    /// byte-position in its [original content](Self::original_content).
+    #[track_caller]
+    pub(crate) fn preprocessed_offset(&self, idx: SourceIndex, original_byte: usize) -> Offset {
+        match self.original(idx) {
+            Some(original) => {
+                Offset::new(idx, original.segments.preprocessed_byte(original_byte))
+            },
+            None => Offset::new(idx, original_byte),
+        }
+    }
+
+    /// The byte-positions of the starts of all lines in the
+    /// [original content](Self::original_content).
+    ///
+    /// The line index is computed on first use and cached.
+    #[track_caller]
+    pub(crate) fn original_line_starts(&self, idx: SourceIndex) -> &[usize] {
+        match self.original(idx) {
+            Some(original) => {
+                original.line_starts.get_or_init(|| line_starts_of(&original.content))
+            },
+            None => self.line_starts(idx),
+        }
+    }
+}
@@ -0,0 +1,305 @@
+use std::borrow::Cow;
+use std::sync::Arc;
+
+use crate::{
+    ContextErrorAnnotation, ContextErrorLocation, ContextErrorOrigin, Input, Offset, Origin,
+    SourceCategory, SourceIndex, SourceMap, SourceMapReader,
+};
+use crate::map::{fetch_next_source_map_id, line_starts_of};
+
+
+/// A storage of source contents that diagnostics can be captured from.
+///
+/// This is implemented by [`SourceMap`], and can be implemented for other storages,
+/// like databases or virtual file systems, to capture diagnostics without copying the
+/// contents into a map. Sources of such storages are identified by [`SourceIndex`]
+/// values constructed with a [`ProviderId`]. Their contents can be traversed with
+/// [`SourceProvider::input`], and errors can be resolved with
+/// [`SourceError::into_context_error`](crate::SourceError::into_context_error).
+///
+/// Captured [`ContextErrorOrigin`] values carry the lines they refer to, so the
+/// storage is not needed anymore for rendering.
+pub trait SourceProvider {
+    /// The [`Origin`] of the source associated with a [`SourceIndex`].
+    fn origin(&self, idx: SourceIndex) -> &Origin;
+
+    /// The content of the source associated with a [`SourceIndex`].
+    fn content(&self, idx: SourceIndex) -> &str;
+
+    /// The byte-positions of the starts of all lines in the content.
+    ///
+    /// The default implementation scans the content on every call. Storages with a
+    /// precomputed line index should return it instead.
+    fn line_starts(&self, idx: SourceIndex) -> Cow<'_, [usize]> {
+        Cow::Owned(line_starts_of(self.content(idx)).to_vec())
+    }
+
+    /// The [`SourceCategory`] of the source, which defaults to [`SourceCategory::User`].
+    fn category(&self, idx: SourceIndex) -> SourceCategory {
+        let _ = idx;
+        SourceCategory::default()
+    }
+
+    /// The content shown in diagnostics, if it differs from the [content](Self::content)
+    /// offsets refer to, like the original of a preprocessed content.
+    ///
+    /// Defaults to the content itself.
+    fn original_content(&self, idx: SourceIndex) -> &str {
+        self.content(idx)
+    }
+
+    /// Map an [`Offset`] to its byte-position in the
+    /// [original content](Self::original_content).
+    ///
+    /// Defaults to the byte-position of the offset.
+    fn original_byte(&self, offset: Offset) -> usize {
+        offset.byte()
+    }
+
+    /// The byte-positions of the starts of all lines in the
+    /// [original content](Self::original_content).
+    ///
+    /// Defaults to the [line starts](Self::line_starts) of the content if the original
+    /// content is the content itself, and scans the original content otherwise.
+    fn original_line_starts(&self, idx: SourceIndex) -> Cow<'_, [usize]> {
+        let original = self.original_content(idx);
+        if std::ptr::eq(original, self.content(idx)) {
+            self.line_starts(idx)
+        } else {
+            Cow::Owned(line_starts_of(original).to_vec())
+        }
+    }
+
+    /// Construct an [`Input`] for the content associated with a [`SourceIndex`].
+    fn input(&self, idx: SourceIndex) -> Input<'_> {
+        Input::new(idx, self.content(idx))
+    }
+
+    /// Capture a [`ContextErrorOrigin`] for a given [`Offset`].
+    fn context_error_origin(
+        &self,
+        offset: Offset,
+        note: &'static str,
+        context: Option<Offset>,
+    ) -> ContextErrorOrigin {
+        let location = capture_location(self, offset, &mut Vec::new());
+        let context = context.map(|offset| capture_location(self, offset, &mut Vec::new()));
+        ContextErrorOrigin::new(
+            self.origin(offset.source_index()).clone(),
+            note,
+            location,
+            context,
+        ).with_category(self.category(offset.source_index()))
+    }
+
+    /// Capture a [`ContextErrorOrigin`] for a source as a whole, without a position.
+    ///
+    /// See [`ContextErrorOrigin::without_position`] for details.
+    fn context_error_source_origin(&self, idx: SourceIndex, note: &'static str) -> ContextErrorOrigin {
+        ContextErrorOrigin::without_position(self.origin(idx).clone(), note)
+            .with_category(self.category(idx))
+    }
+
+    /// Capture a [`ContextErrorAnnotation`] for a given [`Offset`].
+    ///
+    /// The annotation can be attached to a [`ContextErrorOrigin`] of the same source
+    /// with [`ContextErrorOrigin::with_annotation`].
+    fn context_error_annotation(&self, offset: Offset, note: &'static str) -> ContextErrorAnnotation {
+        ContextErrorAnnotation::new(
+            self.origin(offset.source_index()).clone(),
+            note,
+            capture_location(self, offset, &mut Vec::new()),
+        )
+    }
+}
+
+impl<P> SourceProvider for &P
+where
+    P: SourceProvider + ?Sized,
+{
+    fn origin(&self, idx: SourceIndex) -> &Origin {
+        (**self).origin(idx)
+    }
+
+    fn content(&self, idx: SourceIndex) -> &str {
+        (**self).content(idx)
+    }
+
+    fn line_starts(&self, idx: SourceIndex) -> Cow<'_, [usize]> {
+        (**self).line_starts(idx)
+    }
+
+    fn category(&self, idx: SourceIndex) -> SourceCategory {
+        (**self).category(idx)
+    }
+
+    fn original_content(&self, idx: SourceIndex) -> &str {
+        (**self).original_content(idx)
+    }
+
+    fn original_byte(&self, offset: Offset) -> usize {
+        (**self).original_byte(offset)
+    }
+
+    fn original_line_starts(&self, idx: SourceIndex) -> Cow<'_, [usize]> {
+        (**self).original_line_starts(idx)
+    }
+}
+
+impl SourceProvider for SourceMap {
+    #[track_caller]
+    fn origin(&self, idx: SourceIndex) -> &Origin {
+        SourceMap::origin(self, idx)
+    }
+
+    #[track_caller]
+    fn content(&self, idx: SourceIndex) -> &str {
+        SourceMap::content(self, idx)
+    }
+
+    #[track_caller]
+    fn line_starts(&self, idx: SourceIndex) -> Cow<'_, [usize]> {
+        Cow::Borrowed(SourceMap::line_starts(self, idx))
+    }
+
+    #[track_caller]
+    fn category(&self, idx: SourceIndex) -> SourceCategory {
+        SourceMap::category(self, idx)
+    }
+
+    #[track_caller]
+    fn original_content(&self, idx: SourceIndex) -> &str {
+        SourceMap::original_content(self, idx)
+    }
+
+    #[track_caller]
+    fn original_byte(&self, offset: Offset) -> usize {
+        SourceMap::original_byte(self, offset)
+    }
+
+    #[track_caller]
+    fn original_line_starts(&self, idx: SourceIndex) -> Cow<'_, [usize]> {
+        Cow::Borrowed(SourceMap::original_line_starts(self, idx))
+    }
+}
+
+impl SourceProvider for SourceMapReader {
+    #[track_caller]
+    fn origin(&self, idx: SourceIndex) -> &Origin {
+        SourceMap::origin(self, idx)
+    }
+
+    #[track_caller]
+    fn content(&self, idx: SourceIndex) -> &str {
+        SourceMap::content(self, idx)
+    }
+
+    #[track_caller]
+    fn line_starts(&self, idx: SourceIndex) -> Cow<'_, [usize]> {
+        Cow::Borrowed(SourceMap::line_starts(self, idx))
+    }
+
+    #[track_caller]
+    fn category(&self, idx: SourceIndex) -> SourceCategory {
+        SourceMap::category(self, idx)
+    }
+
+    #[track_caller]
+    fn original_content(&self, idx: SourceIndex) -> &str {
+        SourceMap::original_content(self, idx)
+    }
+
+    #[track_caller]
+    fn original_byte(&self, offset: Offset) -> usize {
+        SourceMap::original_byte(self, offset)
+    }
+
+    #[track_caller]
+    fn original_line_starts(&self, idx: SourceIndex) -> Cow<'_, [usize]> {
+        Cow::Borrowed(SourceMap::original_line_starts(self, idx))
+    }
+}
+
+/// An identifier for the sources of a custom [`SourceProvider`].
+///
+/// Every identifier is unique, and distinct from the internal IDs of all [`SourceMap`]s,
+/// so indices of different providers and maps can't be confused.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct ProviderId(u32);
+
+impl Default for ProviderId {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ProviderId {
+    /// Allocate a new unique identifier.
+    ///
+    /// # Panics
+    ///
+    /// A panic will occur if the number of allocated identifiers and maps exceeds
+    /// [`u32::MAX`].
+    pub fn new() -> Self {
+        Self(fetch_next_source_map_id())
+    }
+
+    /// The [`SourceIndex`] for the source with a provider-specific index.
+    pub fn source_index(self, index: u32) -> SourceIndex {
+        SourceIndex::new(self.0, index)
+    }
+
+    /// The provider-specific index of a [`SourceIndex`], or `None` if it belongs to a
+    /// different provider or map.
+    pub fn index_of(self, idx: SourceIndex) -> Option<u32> {
+        (idx.map_id == self.0).then_some(idx.data_index)
+    }
+}
+
+/// The content and byte-position an [`Offset`] is shown at in diagnostics.
+///
+/// Offsets are mapped into the original content. An offset at the end of a content
+/// with a trailing newline is moved to the end of the last line, so the position is
+/// shown past the last character.
+pub(crate) fn displayed_position<P>(provider: &P, offset: Offset) -> (&str, usize)
+where
+    P: SourceProvider + ?Sized,
+{
+    let content = provider.original_content(offset.source_index());
+    let byte = provider.original_byte(offset);
+    if byte != content.len() {
+        return (content, byte);
+    }
+    match content.strip_suffix('\n') {
+        Some(rest) => (content, rest.strip_suffix('\r').unwrap_or(rest).len()),
+        None => (content, byte),
+    }
+}
+
+/// Resolve a location, reusing previously captured lines of the same source.
+pub(crate) fn capture_location<P>(
+    provider: &P,
+    offset: Offset,
+    lines: &mut Vec<(Offset, Arc<str>)>,
+) -> ContextErrorLocation
+where
+    P: SourceProvider + ?Sized,
+{
+    let idx = offset.source_index();
+    let (content, byte) = displayed_position(provider, offset);
+    let line_starts = provider.original_line_starts(idx);
+    let line = line_starts.partition_point(|&start| start <= byte) - 1;
+    let (line_number, start) = (line + 1, line_starts[line]);
+    let end = content[byte..].find('\n').map(|end| end + byte).unwrap_or(content.len());
+    let column_number = 1 + (byte - start);
+    let line_start = Offset::new(idx, start);
+    let text = match lines.iter().find(|(prev_start, _)| *prev_start == line_start) {
+        Some((_, text)) => text.clone(),
+        None => {
+            let text: Arc<str> = content[start..end].into();
+            lines.push((line_start, text.clone()));
+            text
+        },
+    };
+    ContextErrorLocation::new(text, line_number, column_number)
+}
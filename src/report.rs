@@ -0,0 +1,132 @@
+use std::fmt;
+
+use crate::{ContextError, DisplayOptions};
+use crate::display::{display_fn, wrap_text, Style};
+use crate::error::fmt_labeled;
+
+
+/// A friendly report for a [`ContextError`], inspired by the error reports of Elm.
+///
+/// Instead of the terse output of [`ContextError::display_with_options`], reports start
+/// with a header bar carrying a title and the location of the first origin, followed by
+/// the error message and further paragraphs of prose, the source contexts, and a
+/// concluding hint:
+///
+/// ```text
+/// -- UNKNOWN NAME ------------------------------------------------------ src/a.ext
+///
+/// I cannot find a `cont` variable.
+///
+/// --> src/a.ext:2:9
+///  2 | let y = cont;
+///    |         ^^^^ used here
+///
+/// hint: There is a `count` variable in scope.
+/// ```
+///
+/// The header bar spans the [wrap width](DisplayOptions::with_wrap_width), or 80
+/// columns without one. Paragraphs and the hint are wrapped like other messages.
+#[derive(Debug, Clone)]
+pub struct FriendlyReport<'a, E> {
+    error: &'a ContextError<E>,
+    title: &'a str,
+    paragraphs: Vec<&'a str>,
+    hint: Option<&'a str>,
+}
+
+impl<'a, E> FriendlyReport<'a, E> {
+    /// Construct a report for an error with a title, which is shown in uppercase.
+    pub fn new(error: &'a ContextError<E>, title: &'a str) -> Self {
+        Self { error, title, paragraphs: Vec::new(), hint: None }
+    }
+
+    /// Add a paragraph of prose shown after the error message.
+    pub fn with_paragraph(mut self, paragraph: &'a str) -> Self {
+        self.paragraphs.push(paragraph);
+        self
+    }
+
+    /// Conclude the report with a hint.
+    pub fn with_hint(mut self, hint: &'a str) -> Self {
+        self.hint = Some(hint);
+        self
+    }
+
+    /// The reported error.
+    pub fn error(&self) -> &'a ContextError<E> {
+        self.error
+    }
+
+    /// The title of the report.
+    pub fn title(&self) -> &'a str {
+        self.title
+    }
+
+    /// The paragraphs shown after the error message.
+    pub fn paragraphs(&self) -> &[&'a str] {
+        &self.paragraphs
+    }
+
+    /// The concluding hint, if any was given.
+    pub fn hint(&self) -> Option<&'a str> {
+        self.hint
+    }
+
+    /// Construct a [`Display`](std::fmt::Display) proxy using the given
+    /// [`DisplayOptions`].
+    pub fn display_with_options<'b>(&'b self, options: &'b DisplayOptions) -> impl fmt::Display + 'b
+    where
+        E: fmt::Display,
+    {
+        display_fn(move |f| self.fmt_with_options(f, options))
+    }
+
+    fn fmt_with_options(&self, f: &mut fmt::Formatter<'_>, options: &DisplayOptions) -> fmt::Result
+    where
+        E: fmt::Display,
+    {
+        let width = options.wrap_width().unwrap_or(80);
+        let title = self.title.to_uppercase();
+        let location = self.error.visible_origins(options).next()
+            .map(|origin| origin.display_name(options).to_string());
+        let title_width = title.chars().count() + 4;
+        let location_width = location.as_ref().map_or(0, |name| name.chars().count() + 1);
+        let bar = "-".repeat(width.saturating_sub(title_width + location_width).max(2));
+        let header = match &location {
+            Some(location) => format!("-- {title} {bar} {location}"),
+            None => format!("-- {title} {bar}"),
+        };
+        writeln!(f, "{}", options.paint(Style::Gutter, header))?;
+
+        let error = self.error.error().to_string();
+        for paragraph in [error.as_str()].into_iter().chain(self.paragraphs.iter().copied()) {
+            writeln!(f)?;
+            match options.wrap_width() {
+                Some(width) => {
+                    for line in wrap_text(paragraph, width) {
+                        writeln!(f, "{line}")?;
+                    }
+                },
+                None => writeln!(f, "{paragraph}")?,
+            }
+        }
+        for origin in self.error.visible_origins(options) {
+            writeln!(f)?;
+            origin.fmt_with_options(f, options)?;
+        }
+        if let Some(hint) = self.hint {
+            writeln!(f)?;
+            fmt_labeled(f, options, Style::Cause, options.strings().hint, &hint)?;
+        }
+        Ok(())
+    }
+}
+
+impl<E> fmt::Display for FriendlyReport<'_, E>
+where
+    E: fmt::Display,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.fmt_with_options(f, &DisplayOptions::default())
+    }
+}
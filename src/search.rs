@@ -0,0 +1,106 @@
+use crate::{Offset, SourceIndex, SourceMap, Span, is_ident_continue};
+
+
+/// Options for searching source contents with [`SourceMap::search`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct SearchOptions {
+    case_insensitive: bool,
+    whole_word: bool,
+}
+
+impl SearchOptions {
+    /// Construct options for case-sensitive searches matching anywhere.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Compare characters without regard to their case.
+    pub fn with_case_insensitive(mut self, enabled: bool) -> Self {
+        self.case_insensitive = enabled;
+        self
+    }
+
+    /// Whether characters are compared without regard to their case.
+    pub fn case_insensitive(&self) -> bool {
+        self.case_insensitive
+    }
+
+    /// Only match if the match is not directly preceded or followed by a character for
+    /// which [`is_ident_continue`] returns `true`.
+    pub fn with_whole_word(mut self, enabled: bool) -> Self {
+        self.whole_word = enabled;
+        self
+    }
+
+    /// Whether only whole words are matched.
+    pub fn whole_word(&self) -> bool {
+        self.whole_word
+    }
+
+    fn find(&self, content: &str, query: &str) -> Vec<(usize, usize)> {
+        let mut matches = Vec::new();
+        if query.is_empty() {
+            return matches;
+        }
+        let mut search_start = 0;
+        while let Some((start, end)) = self.find_next(content, query, search_start) {
+            let is_word = !self.whole_word || {
+                let before = content[..start].chars().next_back();
+                let after = content[end..].chars().next();
+                !before.is_some_and(is_ident_continue) && !after.is_some_and(is_ident_continue)
+            };
+            if is_word {
+                matches.push((start, end));
+                search_start = end;
+            } else {
+                search_start = start + content[start..].chars().next().map_or(1, char::len_utf8);
+            }
+        }
+        matches
+    }
+
+    fn find_next(&self, content: &str, query: &str, search_start: usize) -> Option<(usize, usize)> {
+        if !self.case_insensitive {
+            let start = search_start + content[search_start..].find(query)?;
+            return Some((start, start + query.len()));
+        }
+        content[search_start..].char_indices().find_map(|(index, _)| {
+            let start = search_start + index;
+            let mut rest = content[start..].chars();
+            let mut byte_len = 0;
+            for expected in query.chars() {
+                let c = rest.next()?;
+                if c != expected && !c.to_lowercase().eq(expected.to_lowercase()) {
+                    return None;
+                }
+                byte_len += c.len_utf8();
+            }
+            Some((start, start + byte_len))
+        })
+    }
+}
+
+impl SourceMap {
+    /// Find all non-overlapping occurrences of a query in the content associated with a
+    /// [`SourceIndex`], in source order.
+    ///
+    /// An empty query never matches.
+    ///
+    /// # Panics
+    ///
+    /// This function will panic if the index does not belong to this map.
+    #[track_caller]
+    pub fn search(&self, idx: SourceIndex, query: &str, options: SearchOptions) -> Vec<Span> {
+        options.find(self.content(idx), query).into_iter()
+            .map(|(start, end)| Span::new(Offset::new(idx, start), end - start))
+            .collect()
+    }
+
+    /// Find all non-overlapping occurrences of a query in all entries of this map, in
+    /// insertion and source order.
+    ///
+    /// See [`SourceMap::search`] for details.
+    pub fn search_all(&self, query: &str, options: SearchOptions) -> Vec<Span> {
+        self.entries().flat_map(|(index, _, _)| self.search(index, query, options)).collect()
+    }
+}
@@ -0,0 +1,160 @@
+use std::ops::Range;
+
+use crate::{Offset, SourceIndex, Span};
+
+
+/// A set of disjoint, sorted [`Span`]s in a single source.
+///
+/// Inserted spans are merged with overlapping and adjacent spans, so the set always
+/// holds the smallest number of spans covering the same content. Empty spans are
+/// ignored. This is useful for coverage tracking, folding ranges, or remembering
+/// which parts of a source were already reported.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct SpanSet {
+    source_index: SourceIndex,
+    ranges: Vec<Range<usize>>,
+}
+
+impl SpanSet {
+    /// Construct an empty set for spans in the source of a [`SourceIndex`].
+    pub fn new(source_index: SourceIndex) -> Self {
+        Self { source_index, ranges: Vec::new() }
+    }
+
+    /// The [`SourceIndex`] of the source the spans belong to.
+    pub fn source_index(&self) -> SourceIndex {
+        self.source_index
+    }
+
+    /// Add the content covered by a [`Span`] to the set.
+    ///
+    /// # Panics
+    ///
+    /// This function will panic if the span belongs to a different source.
+    #[track_caller]
+    pub fn insert(&mut self, span: Span) {
+        let range = self.range(span);
+        if range.is_empty() {
+            return;
+        }
+        let first = self.ranges.partition_point(|prev| prev.end < range.start);
+        let last = self.ranges.partition_point(|next| next.start <= range.end);
+        let merged = match (self.ranges[first..last].first(), self.ranges[first..last].last()) {
+            (Some(first), Some(last)) => first.start.min(range.start)..last.end.max(range.end),
+            _ => range,
+        };
+        self.ranges.splice(first..last, [merged]);
+    }
+
+    /// Remove the content covered by a [`Span`] from the set.
+    ///
+    /// # Panics
+    ///
+    /// This function will panic if the span belongs to a different source.
+    #[track_caller]
+    pub fn remove(&mut self, span: Span) {
+        let range = self.range(span);
+        if range.is_empty() {
+            return;
+        }
+        let first = self.ranges.partition_point(|prev| prev.end <= range.start);
+        let last = self.ranges.partition_point(|next| next.start < range.end);
+        let (Some(first_range), Some(last_range)) =
+            (self.ranges[first..last].first(), self.ranges[first..last].last())
+        else {
+            return;
+        };
+        let before = first_range.start..range.start;
+        let after = range.end..last_range.end;
+        let rest = [before, after].into_iter().filter(|rest| !rest.is_empty());
+        self.ranges.splice(first..last, rest);
+    }
+
+    /// Add all spans of another set.
+    ///
+    /// # Panics
+    ///
+    /// This function will panic if the other set belongs to a different source.
+    #[track_caller]
+    pub fn union(&mut self, other: &Self) {
+        for span in other.iter() {
+            self.insert(span);
+        }
+    }
+
+    /// Remove all spans of another set.
+    ///
+    /// # Panics
+    ///
+    /// This function will panic if the other set belongs to a different source.
+    #[track_caller]
+    pub fn subtract(&mut self, other: &Self) {
+        for span in other.iter() {
+            self.remove(span);
+        }
+    }
+
+    /// Determine if the content at an [`Offset`] is covered by the set.
+    ///
+    /// Offsets in other sources are never covered.
+    pub fn contains(&self, offset: Offset) -> bool {
+        let index = self.ranges.partition_point(|prev| prev.end <= offset.byte());
+        offset.source_index() == self.source_index
+            && self.ranges.get(index).is_some_and(|range| range.start <= offset.byte())
+    }
+
+    /// Determine if all content of a [`Span`] is covered by the set.
+    ///
+    /// Spans in other sources are never covered. Empty spans are covered if their
+    /// position lies inside of a span in the set.
+    pub fn contains_span(&self, span: Span) -> bool {
+        if span.source_index() != self.source_index {
+            return false;
+        }
+        let range = span.byte_range();
+        let index = self.ranges.partition_point(|prev| prev.end <= range.start);
+        self.ranges.get(index).is_some_and(|covering| {
+            covering.start <= range.start && range.end <= covering.end
+        })
+    }
+
+    /// Determine if any content of a [`Span`] is covered by the set.
+    ///
+    /// Spans in other sources never overlap.
+    pub fn overlaps(&self, span: Span) -> bool {
+        if span.source_index() != self.source_index {
+            return false;
+        }
+        let range = span.byte_range();
+        let index = self.ranges.partition_point(|prev| prev.end <= range.start);
+        self.ranges.get(index).is_some_and(|next| next.start < range.end)
+    }
+
+    /// An iterator over all spans in the set, in source order.
+    pub fn iter(&self) -> impl Iterator<Item = Span> + '_ {
+        self.ranges.iter().map(|range| {
+            Span::new(Offset::new(self.source_index, range.start), range.end - range.start)
+        })
+    }
+
+    /// The number of disjoint spans in the set.
+    pub fn len(&self) -> usize {
+        self.ranges.len()
+    }
+
+    /// Whether the set covers no content.
+    pub fn is_empty(&self) -> bool {
+        self.ranges.is_empty()
+    }
+
+    /// The total number of bytes covered by the set.
+    pub fn byte_len(&self) -> usize {
+        self.ranges.iter().map(|range| range.end - range.start).sum()
+    }
+
+    #[track_caller]
+    fn range(&self, span: Span) -> Range<usize> {
+        assert_eq!(self.source_index, span.source_index(), "span must belong to set source");
+        span.byte_range()
+    }
+}
@@ -0,0 +1,143 @@
+use crate::{Offset, SourceIndex, Span};
+
+
+/// An interval index over a fixed collection of [`Span`]s, like the spans of all nodes
+/// in a syntax tree.
+///
+/// The tree answers queries for the spans containing an [`Offset`] or overlapping a
+/// [`Span`] in logarithmic time plus the number of results, which is useful for IDE
+/// features like hover or selection ranges. Spans from multiple sources can be mixed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SpanTree {
+    spans: Vec<Span>,
+    max_ends: Vec<Key>,
+}
+
+/// A position ordered by source first, so spans from multiple sources can be compared.
+type Key = (SourceIndex, usize);
+
+impl SpanTree {
+    /// Build a tree from a collection of spans.
+    ///
+    /// Duplicate spans are kept, and will be returned multiple times by queries.
+    pub fn new<I>(spans: I) -> Self
+    where
+        I: IntoIterator<Item = Span>,
+    {
+        let mut spans = spans.into_iter().collect::<Vec<_>>();
+        spans.sort_by_key(|span| (start_key(span.start()), std::cmp::Reverse(span.byte_len())));
+        let mut tree = Self { max_ends: spans.iter().copied().map(end_key).collect(), spans };
+        tree.build(0, tree.spans.len());
+        tree
+    }
+
+    fn build(&mut self, lo: usize, hi: usize) -> Option<Key> {
+        if lo >= hi {
+            return None;
+        }
+        let mid = lo + (hi - lo) / 2;
+        let mut max_end = end_key(self.spans[mid]);
+        for child in [self.build(lo, mid), self.build(mid + 1, hi)].into_iter().flatten() {
+            max_end = max_end.max(child);
+        }
+        self.max_ends[mid] = max_end;
+        Some(max_end)
+    }
+
+    /// The smallest span containing the content at an [`Offset`].
+    ///
+    /// Of multiple smallest spans, the last one in source order is returned. Empty spans
+    /// never contain any content.
+    pub fn innermost(&self, offset: Offset) -> Option<Span> {
+        let mut innermost: Option<Span> = None;
+        self.visit_containing(offset, &mut |span| {
+            if innermost.is_none_or(|prev| span.byte_len() <= prev.byte_len()) {
+                innermost = Some(span);
+            }
+        });
+        innermost
+    }
+
+    /// All spans containing the content at an [`Offset`], in source order.
+    ///
+    /// For nested spans, this is from the outermost to the innermost span.
+    pub fn containing(&self, offset: Offset) -> Vec<Span> {
+        let mut spans = Vec::new();
+        self.visit_containing(offset, &mut |span| spans.push(span));
+        spans
+    }
+
+    /// All spans sharing content with a [`Span`], in source order.
+    ///
+    /// For an empty span, these are all spans around its position.
+    pub fn overlapping(&self, span: Span) -> Vec<Span> {
+        let mut spans = Vec::new();
+        self.visit(0, self.spans.len(), start_key(span.start()), end_key(span), &mut |span| {
+            spans.push(span);
+        });
+        spans
+    }
+
+    /// An iterator over all spans in the tree, in source order.
+    pub fn iter(&self) -> impl Iterator<Item = Span> + '_ {
+        self.spans.iter().copied()
+    }
+
+    /// The number of spans in the tree.
+    pub fn len(&self) -> usize {
+        self.spans.len()
+    }
+
+    /// Whether the tree contains no spans.
+    pub fn is_empty(&self) -> bool {
+        self.spans.is_empty()
+    }
+
+    fn visit_containing<F>(&self, offset: Offset, found: &mut F)
+    where
+        F: FnMut(Span),
+    {
+        let end = (offset.source_index(), offset.byte() + 1);
+        self.visit(0, self.spans.len(), start_key(offset), end, found);
+    }
+
+    /// Visit all spans starting before `end` and ending after `start`, in source order.
+    fn visit<F>(&self, lo: usize, hi: usize, start: Key, end: Key, found: &mut F)
+    where
+        F: FnMut(Span),
+    {
+        if lo >= hi {
+            return;
+        }
+        let mid = lo + (hi - lo) / 2;
+        if self.max_ends[mid] <= start {
+            return;
+        }
+        self.visit(lo, mid, start, end, found);
+        let span = self.spans[mid];
+        if start_key(span.start()) >= end {
+            return;
+        }
+        if end_key(span) > start {
+            found(span);
+        }
+        self.visit(mid + 1, hi, start, end, found);
+    }
+}
+
+impl FromIterator<Span> for SpanTree {
+    fn from_iter<I>(spans: I) -> Self
+    where
+        I: IntoIterator<Item = Span>,
+    {
+        Self::new(spans)
+    }
+}
+
+fn start_key(offset: Offset) -> Key {
+    (offset.source_index(), offset.byte())
+}
+
+fn end_key(span: Span) -> Key {
+    (span.source_index(), span.end().byte())
+}
@@ -0,0 +1,141 @@
+use crate::{ContextError, ContextErrorOrigin, Line, Offset, Origin, SourceIndex, SourceMap};
+
+
+/// A set of suppression markers found in the sources of a [`SourceMap`].
+///
+/// Markers start with a configurable prefix, like `# src-ctx:`, and are followed by
+/// either `allow(...)` to suppress the listed codes on the next line, or `allow-file(...)`
+/// to suppress them for the whole source:
+///
+/// ```text
+/// # src-ctx: allow-file(deprecated)
+/// # src-ctx: allow(unused, shadowed)
+/// let x = 23;
+/// ```
+///
+/// Markers can appear anywhere on a line, so they can follow a comment syntax of the
+/// source language. Malformed markers are ignored.
+#[derive(Debug, Clone)]
+pub struct Suppressions {
+    prefix: String,
+    entries: Vec<Suppression>,
+}
+
+impl Suppressions {
+    /// Construct an empty set of suppressions for markers starting with a prefix.
+    pub fn new(prefix: &str) -> Self {
+        Self { prefix: prefix.into(), entries: Vec::new() }
+    }
+
+    /// Construct a set of suppressions from the markers in all sources of a map.
+    pub fn scan(map: &SourceMap, prefix: &str) -> Self {
+        let mut suppressions = Self::new(prefix);
+        for (index, _, _) in map.entries() {
+            suppressions.scan_source(map, index);
+        }
+        suppressions
+    }
+
+    /// Add the markers found in the content associated with a [`SourceIndex`].
+    ///
+    /// # Panics
+    ///
+    /// This function will panic if the index does not belong to the map.
+    #[track_caller]
+    pub fn scan_source(&mut self, map: &SourceMap, idx: SourceIndex) {
+        let origin = map.origin(idx);
+        let mut line_start = 0;
+        for (line_index, line) in map.content(idx).split_inclusive('\n').enumerate() {
+            let marker = line.split_once(self.prefix.as_str()).and_then(|(before, rest)| {
+                let rest = rest.trim_start();
+                let next_line = Some(Line::from_zero_based(line_index + 1));
+                parse_allow(rest, "allow-file").map(|codes| (None, codes))
+                    .or_else(|| parse_allow(rest, "allow").map(|codes| (next_line, codes)))
+                    .map(|(line, codes)| (before.len(), line, codes))
+            });
+            if let Some((marker_start, suppressed_line, codes)) = marker {
+                let offset = Offset::new(idx, line_start + marker_start);
+                for code in codes.split(',').map(str::trim).filter(|code| !code.is_empty()) {
+                    self.entries.push(Suppression {
+                        origin: origin.clone(),
+                        line: suppressed_line,
+                        code: code.into(),
+                        offset,
+                    });
+                }
+            }
+            line_start += line.len();
+        }
+    }
+
+    /// The prefix of suppression markers.
+    pub fn prefix(&self) -> &str {
+        &self.prefix
+    }
+
+    /// All found [`Suppression`] entries, in the order they were found.
+    pub fn entries(&self) -> &[Suppression] {
+        &self.entries
+    }
+
+    /// Determine if a code is suppressed at the position of a [`ContextErrorOrigin`].
+    pub fn is_suppressed(&self, origin: &ContextErrorOrigin, code: &str) -> bool {
+        let line = origin.location().map(|location| location.line());
+        self.entries.iter().any(|entry| {
+            entry.code() == code
+                && entry.origin() == origin.origin()
+                && entry.line().is_none_or(|entry_line| Some(entry_line) == line)
+        })
+    }
+
+    /// Remove all errors with a code that is suppressed at their first origin.
+    ///
+    /// The `code` function determines the code of an error. Errors without a code or
+    /// without origins are always kept.
+    pub fn retain_unsuppressed<E, F>(&self, errors: &mut Vec<ContextError<E>>, mut code: F)
+    where
+        F: FnMut(&E) -> Option<&str>,
+    {
+        errors.retain(|error| {
+            let Some(origin) = error.error_origins().first() else {
+                return true;
+            };
+            code(error.error()).is_none_or(|code| !self.is_suppressed(origin, code))
+        });
+    }
+}
+
+fn parse_allow<'a>(marker: &'a str, name: &str) -> Option<&'a str> {
+    marker.strip_prefix(name)?.trim_start().strip_prefix('(')?.split_once(')').map(|(codes, _)| codes)
+}
+
+/// A single suppressed code found by [`Suppressions`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Suppression {
+    origin: Origin,
+    line: Option<Line>,
+    code: Box<str>,
+    offset: Offset,
+}
+
+impl Suppression {
+    /// The [`Origin`] of the source the code is suppressed in.
+    pub fn origin(&self) -> &Origin {
+        &self.origin
+    }
+
+    /// The suppressed [`Line`], or `None` if the code is suppressed for the whole source.
+    pub fn line(&self) -> Option<Line> {
+        self.line
+    }
+
+    /// The suppressed code.
+    pub fn code(&self) -> &str {
+        &self.code
+    }
+
+    /// The [`Offset`] of the marker.
+    pub fn offset(&self) -> Offset {
+        self.offset
+    }
+}
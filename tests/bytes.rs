@@ -0,0 +1,79 @@
+use src_ctx::{ByteSourceMap, Origin, Insert, normalize};
+use test_util::{Error, test_byte_map, test_map};
+
+
+mod test_util;
+
+#[test]
+fn byte_inputs() {
+    let (map, index) = test_byte_map(b"\x00\x01abc");
+    let input = map.input(index);
+
+    assert_eq!(input.len(), 5);
+    assert_eq!(input.content(), b"\x00\x01abc");
+    assert!(input.offset().is_at_start());
+    assert_eq!(input.end().offset().byte(), 5);
+
+    assert_eq!(input.byte(), Some(0));
+    assert_eq!(input.end().byte(), None);
+    assert_eq!(input.skip_byte(0).unwrap().offset().byte(), 1);
+    assert!(input.skip_byte(1).is_none());
+    assert_eq!(input.skip_bytes(b"\x00\x01").unwrap().content(), b"abc");
+    assert!(input.skip_bytes(b"abc").is_none());
+
+    let (taken, rest) = input.take_byte().unwrap();
+    assert_eq!(taken, 0);
+    assert_eq!(rest.offset().byte(), 1);
+    let (taken, rest) = input.take_bytes(2).unwrap();
+    assert_eq!(taken, b"\x00\x01");
+    assert_eq!(rest.content(), b"abc");
+    assert!(input.take_bytes(6).is_none());
+
+    let span = input.skip(2).offset().span(input.end().offset());
+    assert_eq!(map.span_bytes(span), b"abc");
+}
+
+#[test]
+fn byte_entries() {
+    let mut map = ByteSourceMap::new();
+    let index = map.insert(Origin::from_named("test"), b"content".as_slice().into())
+        .try_into_inserted().unwrap();
+    assert!(map.contains(index));
+    assert_eq!(map.origin(index), &Origin::from_named("test"));
+    assert_eq!(map.origin_index(&Origin::from_named("test")), Some(index));
+    assert_eq!(
+        map.insert(Origin::from_named("test"), b"other".as_slice().into()),
+        Insert::Previous(index),
+    );
+
+    let (text_map, text_index) = test_map("content");
+    assert!(! map.contains(text_index));
+    assert!(! text_map.contains(index));
+}
+
+#[test]
+fn byte_context_errors() {
+    let content: Vec<u8> = (0..40).collect();
+    let (map, index) = test_byte_map(&content);
+    let input = map.input(index);
+
+    let error = input.skip(0x22).error(Error("test-error"), "test-note")
+        .with_context(input.skip(3).offset())
+        .into_byte_context_error(&map);
+    assert_eq!(&format!("{error}"), "test-error at test+0x22");
+    assert_eq!(&format!("{}", error.display_with_context()), &normalize("
+        |error: test-error
+        |--> test+0x22
+        | 00000000 | 00 01 02 03 04 05 06 07 08 09 0a 0b 0c 0d 0e 0f | ................
+        |          | ...
+        | 00000020 | 20 21 22 23 24 25 26 27                         |  !\"#$%&'
+        |          |       ^^ test-note
+    "));
+
+    let mut map = ByteSourceMap::new();
+    let index = map.insert(Origin::from_named("test"), b"abc".as_slice().into())
+        .try_into_inserted().unwrap();
+    let error = map.input(index).end().error(Error("test-error"), "test-note")
+        .into_byte_context_error(&map);
+    assert_eq!(&format!("{error}"), "test-error in `test`, offset 0x3");
+}
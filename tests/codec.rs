@@ -0,0 +1,64 @@
+use src_ctx::{LinePosition, LineRange, PositionEncoding};
+use test_util::test_map;
+
+
+mod test_util;
+
+fn pos(line: usize, character: usize) -> LinePosition {
+    LinePosition { line, character }
+}
+
+#[test]
+fn line_positions() {
+    let (map, index) = test_map("ab\r\nä𝄞c\n");
+    let input = map.input(index);
+    let offset = input.skip(4 + 2 + 4).offset();
+
+    assert_eq!(map.line_position(input.offset(), PositionEncoding::Utf16), pos(0, 0));
+    assert_eq!(map.line_position(input.skip(2).offset(), PositionEncoding::Utf16), pos(0, 2));
+    assert_eq!(map.line_position(offset, PositionEncoding::Utf8), pos(1, 6));
+    assert_eq!(map.line_position(offset, PositionEncoding::Utf16), pos(1, 3));
+    assert_eq!(map.line_position(offset, PositionEncoding::Utf32), pos(1, 2));
+    assert_eq!(map.line_position(input.end().offset(), PositionEncoding::Utf16), pos(2, 0));
+
+    for encoding in [PositionEncoding::Utf8, PositionEncoding::Utf16, PositionEncoding::Utf32] {
+        let position = map.line_position(offset, encoding);
+        assert_eq!(map.line_position_offset(index, position, encoding), Some(offset));
+    }
+    assert_eq!(
+        map.line_position_offset(index, pos(0, 10), PositionEncoding::Utf16),
+        Some(input.skip(2).offset()),
+    );
+    assert_eq!(
+        map.line_position_offset(index, pos(1, 2), PositionEncoding::Utf16),
+        Some(input.skip(6).offset()),
+    );
+    assert_eq!(map.line_position_offset(index, pos(3, 0), PositionEncoding::Utf16), None);
+
+    let span = input.skip(1).offset().span(offset);
+    let range = map.line_range(span, PositionEncoding::Utf16);
+    assert_eq!(range, LineRange { start: pos(0, 1), end: pos(1, 3) });
+    assert_eq!(map.line_range_span(index, range, PositionEncoding::Utf16), Some(span));
+}
+
+#[cfg(feature = "tree-sitter")]
+#[test]
+fn tree_sitter_conversions() {
+    use tree_sitter::{Point, Range};
+
+    let (map, index) = test_map("ab\ncäd");
+    let input = map.input(index);
+    let span = input.skip(1).offset().span(input.skip(6).offset());
+
+    let range = map.tree_sitter_range(span);
+    assert_eq!(range, Range {
+        start_byte: 1,
+        end_byte: 6,
+        start_point: Point { row: 0, column: 1 },
+        end_point: Point { row: 1, column: 3 },
+    });
+    assert_eq!(map.tree_sitter_range_span(index, range), Some(span));
+    assert_eq!(map.tree_sitter_point_offset(index, range.end_point), Some(span.end()));
+    assert_eq!(map.tree_sitter_point_offset(index, Point { row: 2, column: 0 }), None);
+    assert_eq!(map.tree_sitter_range_span(index, Range { end_byte: 5, ..range }), None);
+}
@@ -0,0 +1,22 @@
+#![cfg(feature = "embed")]
+
+use src_ctx::{SourceMap, embed_sources};
+
+
+#[test]
+fn embedded_sources() {
+    let load = embed_sources!("tests/fixtures/embed/**/*.txt");
+    let mut map = SourceMap::new();
+    let inserted = load(&mut map);
+    assert_eq!(inserted.len(), 2);
+
+    let index = map.file_index("tests/fixtures/embed/a.txt").unwrap();
+    assert_eq!(map.content(index), "first\n");
+    let index = map.file_index("tests/fixtures/embed/nested/b.txt").unwrap();
+    assert_eq!(map.content(index), "second\n");
+    assert!(! map.contains_file("tests/fixtures/embed/c.md"));
+
+    let load = embed_sources!("tests/fixtures/embed/*.txt");
+    let mut map = SourceMap::new();
+    assert_eq!(load(&mut map).len(), 1);
+}
@@ -0,0 +1,112 @@
+use std::path::Path;
+
+use src_ctx::{
+    ContextError, DisplayOptions, Origin, SourceMap, display_checkstyle, display_quickfix, display_short,
+    display_annotated_source, normalize, write_quickfix_file,
+};
+use test_util::Error;
+
+
+mod test_util;
+
+#[test]
+fn checkstyle() {
+    let mut map = SourceMap::new();
+    let a = map.insert(Origin::from_file("a.txt"), "abc\ndef".into()).try_into_inserted().unwrap();
+    let b = map.insert(Origin::from_file("b<1>.txt"), "ghi".into()).try_into_inserted().unwrap();
+
+    let errors = [
+        map.input(a).skip(5).error(Error("first"), "here").into_context_error(&map),
+        map.input(b).error(Error("\"quoted\" & <tagged>"), "here").into_context_error(&map),
+        map.input(a).skip(1).error(Error("second"), "here").into_context_error(&map),
+        ContextError::with_origins(Error("unknown"), []),
+    ];
+    let options = DisplayOptions::new();
+    assert_eq!(&format!("{}", display_checkstyle(&errors, &options)), &normalize(r#"
+        |<?xml version="1.0" encoding="UTF-8"?>
+        |<checkstyle version="4.3">
+        |  <file name="a.txt">
+        |    <error line="2" column="2" severity="error" message="first"/>
+        |    <error line="1" column="2" severity="error" message="second"/>
+        |  </file>
+        |  <file name="b&lt;1&gt;.txt">
+        |    <error line="1" column="1" severity="error" message="&quot;quoted&quot; &amp; &lt;tagged&gt;"/>
+        |  </file>
+        |  <file name="">
+        |    <error severity="error" message="unknown"/>
+        |  </file>
+        |</checkstyle>
+    "#));
+}
+
+#[test]
+fn short() {
+    let mut map = SourceMap::new();
+    let a = map.insert(Origin::from_file("a.txt"), "abc\ndef".into()).try_into_inserted().unwrap();
+    let b = map.insert(Origin::from_named("b"), "ghi".into()).try_into_inserted().unwrap();
+
+    let errors = [
+        map.input(a).skip(5).error(Error("first"), "here").into_context_error(&map),
+        map.input(b).skip(1).error(Error("second"), "here").into_context_error(&map),
+        ContextError::with_origins(Error("third"), []),
+    ];
+    let options = DisplayOptions::new();
+    assert_eq!(&format!("{}", display_short(&errors, &options)), &normalize("
+        |a.txt:2:2: error: first
+        |`b`, line 1, column 2: error: second
+        |error: third
+    "));
+    assert_eq!(&format!("{}", display_short(&errors[..0], &options)), "");
+}
+
+#[test]
+fn quickfix() {
+    let mut map = SourceMap::new();
+    let a = map.insert(Origin::from_file("a.txt"), "abc\ndef".into()).try_into_inserted().unwrap();
+    let b = map.insert(Origin::from_named("b"), "ghi".into()).try_into_inserted().unwrap();
+
+    let errors = [
+        map.input(a).skip(5).error(Error("first"), "here").into_context_error(&map),
+        map.input(b).skip(1).error(Error("second\nline"), "here").into_context_error(&map),
+        ContextError::with_origins(Error("third"), []),
+    ];
+    let options = DisplayOptions::new();
+    let expected = normalize("
+        |a.txt:2:2: error: first
+        |b:1:2: error: second line
+        |error: third
+    ");
+    assert_eq!(display_quickfix(&errors, &options).to_string(), expected);
+
+    let path = Path::new(env!("CARGO_TARGET_TMPDIR")).join("quickfix.txt");
+    write_quickfix_file(&path, &errors, &options).unwrap();
+    assert_eq!(std::fs::read_to_string(&path).unwrap(), expected);
+}
+
+#[test]
+fn annotated_source() {
+    let mut map = SourceMap::new();
+    let a = map.insert(Origin::from_file("a.txt"), "abc\ndef\nghi\n".into()).try_into_inserted().unwrap();
+    let b = map.insert(Origin::from_file("b.txt"), "xyz".into()).try_into_inserted().unwrap();
+
+    let errors = [
+        map.input(a).skip(6).error(Error("second"), "here").into_context_error(&map),
+        map.input(b).error(Error("elsewhere"), "here").into_context_error(&map),
+        map.input(a).skip(4).error_len(2, Error("first"), "these").into_context_error(&map),
+    ];
+    let options = DisplayOptions::new();
+    assert_eq!(display_annotated_source(&map, a, &errors, &options).to_string(), normalize("
+        |--> a.txt
+        | 1 | abc
+        | 2 | def
+        |   | ^^ these
+        |   = error: first
+        |   |   ^ here
+        |   = error: second
+        | 3 | ghi
+    "));
+    assert_eq!(display_annotated_source(&map, b, &errors[..0], &options).to_string(), normalize("
+        |--> b.txt
+        | 1 | xyz
+    "));
+}
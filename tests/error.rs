@@ -1,4 +1,7 @@
-use src_ctx::{ContextError, normalize};
+use src_ctx::{
+    CompactSpan, ContextError, ContextErrorOrigin, DisplayOptions, ExpectedTokens, Line, Offset,
+    Origin, SourceError, SourceMap, Verbosity, normalize,
+};
 use test_util::{Error, ErrorChain, test_map, test_map_file};
 
 
@@ -40,6 +43,28 @@ fn context_error_origins() {
     assert_eq!(error_a, error_b);
 }
 
+#[test]
+fn context_error_origin_lists() {
+    let mut map = SourceMap::new();
+    let a = map.insert(Origin::from_named("a"), "abc\ndef".into()).try_into_inserted().unwrap();
+    let b = map.insert(Origin::from_named("b"), "ghi".into()).try_into_inserted().unwrap();
+    let origin = |index, skip, note| {
+        map.context_error_origin(map.input(index).skip(skip).offset(), note, None)
+    };
+
+    let mut error = ContextError::with_origins(Error("test-error"), [origin(b, 0, "b0")]);
+    error.push_origin(origin(a, 5, "a5"));
+    error.extend_origins([origin(a, 1, "a1"), origin(b, 2, "b2")]);
+    let notes = |error: &ContextError<Error>| {
+        error.error_origins().iter().map(|origin| origin.note()).collect::<Vec<_>>()
+    };
+    assert_eq!(notes(&error), ["b0", "a5", "a1", "b2"]);
+
+    error.sort_origins();
+    assert_eq!(notes(&error), ["a1", "a5", "b0", "b2"]);
+    assert_eq!(error.error_origins()[0].origin(), &Origin::from_named("a"));
+}
+
 #[test]
 fn context_error_display_named() {
     let (map, index) = test_map("abc\ndef\nghi");
@@ -127,4 +152,408 @@ fn context_error_display_file() {
         | 3 | ghi
         |   |   ^ test-note
     "));
-}
\ No newline at end of file
+}
+#[test]
+fn context_error_annotations() {
+    let (map, index) = test_map("abc\ndef\nghi\njkl");
+    let input = map.input(index);
+
+    let error = ContextError::with_origins(Error("test-error"), [
+        map.context_error_origin(input.skip(9).offset(), "test-note", None)
+            .with_annotation(map.context_error_annotation(input.skip(1).offset(), "start")),
+    ]);
+    assert_eq!(&format!("{}", error.display_with_context()), &normalize("
+        |error: test-error
+        |--> `test`, line 3, column 2
+        | 1 | abc
+        |   |  - start
+        |   | ...
+        | 3 | ghi
+        |   |  ^ test-note
+    "));
+
+    let error = ContextError::with_origins(Error("test-error"), [
+        map.context_error_origin(input.skip(9).offset(), "test-note", Some(input.skip(4).offset()))
+            .with_annotation(map.context_error_annotation(input.skip(10).offset(), "same"))
+            .with_annotation(map.context_error_annotation(input.skip(13).offset(), "after")),
+    ]);
+    assert_eq!(error.error_origins()[0].annotations().len(), 2);
+    assert_eq!(&format!("{}", error.display_with_context()), &normalize("
+        |error: test-error
+        |--> `test`, line 3, column 2
+        | 2 | def
+        | 3 | ghi
+        |   |  ^ test-note
+        |   |   - same
+        | 4 | jkl
+        |   |  - after
+    "));
+}
+
+#[test]
+fn context_error_expected() {
+    let (map, index) = test_map("abc\ndef\nghi");
+    let input = map.input(index);
+
+    let origin = map.context_error_origin(input.skip(5).offset(), "test-note", Some(input.offset()))
+        .with_expected("dxf\nyyy");
+    assert_eq!(origin.expected(), Some("dxf\nyyy"));
+    let error = ContextError::with_origins(Error("test-error"), [origin]);
+    assert_eq!(&format!("{}", error.display_with_context()), &normalize("
+        |error: test-error
+        |--> `test`, line 2, column 2
+        | 1 | abc
+        | 2 - def
+        |   |  ^ test-note
+        |   + dxf
+        |   + yyy
+    "));
+}
+
+#[test]
+fn context_error_display_origins() {
+    let mut map = SourceMap::new();
+    let stdin = map.insert(Origin::Stdin, "abc\ndef".into()).try_into_inserted().unwrap();
+    let generated = map.insert(Origin::from_generated("x"), "abc\ndef".into())
+        .try_into_inserted().unwrap();
+
+    let error = map.input(stdin).skip(5).error(Error("test-error"), "test-note")
+        .into_context_error(&map);
+    assert_eq!(&format!("{error}"), "test-error at <stdin>:2:2");
+    assert_eq!(&format!("{}", error.display_with_context()), &normalize("
+        |error: test-error
+        |--> <stdin>:2:2
+        | 2 | def
+        |   |  ^ test-note
+    "));
+
+    let error = map.input(generated).skip(5).error(Error("test-error"), "test-note")
+        .into_context_error(&map);
+    assert_eq!(&format!("{error}"), "test-error in generated code from `x`, line 2, column 2");
+
+    let url = map.insert(Origin::from_url("https://example.com/x"), "abc\ndef".into())
+        .try_into_inserted().unwrap();
+    let error = map.input(url).skip(5).error(Error("test-error"), "test-note")
+        .into_context_error(&map);
+    assert_eq!(&format!("{error}"), "test-error at https://example.com/x:2:2");
+}
+
+#[test]
+fn context_error_highlight() {
+    let (map, index) = test_map("abc\nd\u{e9}fgh\nijk");
+    let input = map.input(index);
+
+    let origin = map.context_error_origin(input.skip(5).offset(), "test-note", None)
+        .with_highlight_len(3);
+    assert_eq!(origin.highlight_len(), 3);
+    let error = ContextError::with_origins(Error("test-error"), [origin]);
+    assert_eq!(&format!("{}", error.display_with_context()), &normalize("
+        |error: test-error
+        |--> `test`, line 2, column 2
+        | 2 | d\u{e9}fgh
+        |   |  ^^ test-note
+    "));
+
+    let error = ContextError::with_origins(Error("test-error"), [
+        map.context_error_origin(input.skip(7).offset(), "test-note", None)
+            .with_highlight_len(10),
+    ]);
+    assert_eq!(&format!("{}", error.display_with_context()), &normalize("
+        |error: test-error
+        |--> `test`, line 2, column 4
+        | 2 | d\u{e9}fgh
+        |   |   ^^^ test-note
+    "));
+
+    let error = ContextError::with_origins(Error("test-error"), [
+        map.context_error_origin(input.skip(7).offset(), "test-note", None)
+            .with_highlight_len(0),
+    ]);
+    assert_eq!(&format!("{}", error.display_with_context()), &normalize("
+        |error: test-error
+        |--> `test`, line 2, column 4
+        | 2 | d\u{e9}fgh
+        |   |   ^ test-note
+    "));
+}
+
+#[test]
+fn source_error_len() {
+    let (map, index) = test_map("let abc = 1");
+    let input = map.input(index);
+
+    let error = input.skip(4).error_len(3, Error("test-error"), "test-note");
+    assert_eq!(error.byte_len(), 3);
+    assert_eq!(input.error(Error("test-error"), "test-note").byte_len(), 1);
+    let span = input.skip(4).offset().span(input.skip(7).offset());
+    let span_error = input.error_span(span, Error("test-error"), "test-note");
+    assert_eq!(span_error.offset(), error.offset());
+    assert_eq!(span_error.byte_len(), 3);
+    let (_, other_index) = test_map("let abc = 1");
+    let other_span = CompactSpan::new(4, 3).to_span(other_index);
+    let result = std::panic::catch_unwind(|| {
+        input.error_span(other_span, Error("test-error"), "test-note")
+    });
+    assert!(result.is_err());
+
+    let error = error.into_context_error(&map);
+    assert_eq!(error.error_origins()[0].highlight_len(), 3);
+    assert_eq!(&format!("{}", error.display_with_context()), &normalize("
+        |error: test-error
+        |--> `test`, line 1, column 5
+        | 1 | let abc = 1
+        |   |     ^^^ test-note
+    "));
+}
+
+#[test]
+fn source_error_span() {
+    let (map, index) = test_map("let value = 1");
+    let input = map.input(index);
+    let span = input.skip(4).offset().span(input.skip(9).offset());
+
+    let error = SourceError::from_span(Error("test-error"), span, "test-note");
+    assert_eq!(error.span(), span);
+    assert_eq!(error.offset(), span.start());
+    assert_eq!(input.error_span(span, Error("test-error"), "test-note").span(), span);
+    assert_eq!(input.skip(4).error(Error("test-error"), "test-note").span().byte_len(), 1);
+
+    let error = error.into_context_error(&map);
+    let options = DisplayOptions::new().with_tilde_underlines(true);
+    assert!(options.tilde_underlines());
+    assert!(!DisplayOptions::new().tilde_underlines());
+    assert_eq!(error.display_with_options(&options).to_string(), normalize("
+        |error: test-error
+        |--> `test`, line 1, column 5
+        | 1 | let value = 1
+        |   |     ^~~~~ test-note
+    "));
+    assert_eq!(error.display_with_context().to_string(), normalize("
+        |error: test-error
+        |--> `test`, line 1, column 5
+        | 1 | let value = 1
+        |   |     ^^^^^ test-note
+    "));
+
+    let error = input.skip(4).error_len(0, Error("test-error"), "test-note")
+        .into_context_error(&map);
+    assert_eq!(error.display_with_options(&options).to_string(), normalize("
+        |error: test-error
+        |--> `test`, line 1, column 5
+        | 1 | let value = 1
+        |   |     ^ test-note
+    "));
+}
+
+#[test]
+fn context_error_opening() {
+    let mut map = SourceMap::new();
+    let a = map.insert(Origin::from_file("a"), "(abc\ndef".into()).try_into_inserted().unwrap();
+    let b = map.insert(Origin::from_file("b"), "ghi".into()).try_into_inserted().unwrap();
+    let input = map.input(a);
+
+    let error = input.end().error(Error("unclosed"), "expected `)`")
+        .with_opening(input.offset(), "opened here");
+    assert_eq!(error.opening(), Some((input.offset(), "opened here")));
+    let error = error.into_context_error(&map);
+    assert_eq!(&format!("{}", error.display_with_context()), &normalize("
+        |error: unclosed
+        |--> a:2:4
+        | 1 | (abc
+        |   | - opened here
+        | 2 | def
+        |   |    ^ expected `)`
+    "));
+
+    let error = map.input(b).end().error(Error("unclosed"), "expected `)`")
+        .with_opening(input.offset(), "opened here")
+        .into_context_error(&map);
+    assert_eq!(&format!("{error}"), "unclosed at b:1:4 and at a:1:1");
+    assert_eq!(&format!("{}", error.display_with_context()), &normalize("
+        |error: unclosed
+        |--> b:1:4
+        | 1 | ghi
+        |   |    ^ expected `)`
+        |--> a:1:1
+        | 1 | (abc
+        |   | ^ opened here
+    "));
+}
+
+#[test]
+fn context_error_trace() {
+    let mut map = SourceMap::new();
+    let a = map.insert(Origin::from_file("a.ext"), "x\n#include b.ext\n".into()).try_into_inserted().unwrap();
+    let b = map.insert(Origin::from_file("b.ext"), "#include c.ext\n".into()).try_into_inserted().unwrap();
+    let c = map.insert(Origin::from_file("c.ext"), "abc".into()).try_into_inserted().unwrap();
+
+    let origin = map.context_error_origin(map.input(c).skip(1).offset(), "here", None)
+        .with_trace_frame(map.context_error_origin(map.input(b).offset(), "", None))
+        .with_trace_frame(map.context_error_origin(map.input(a).skip(2).offset(), "", None));
+    assert_eq!(origin.trace_frames().len(), 2);
+    let error = ContextError::with_origins(Error("test-error"), [origin]);
+    assert_eq!(&format!("{error}"), "test-error at c.ext:1:2");
+    assert_eq!(&format!("{}", error.display_with_context()), &normalize("
+        |error: test-error
+        |--> c.ext:1:2
+        | 1 | abc
+        |   |  ^ here
+        | = included from b.ext:1:1
+        | = included from a.ext:2:1
+    "));
+}
+
+#[test]
+fn context_error_origin_with_stack() {
+    let mut map = SourceMap::new();
+    let a = map.insert(Origin::from_file("a.ext"), "#include b.ext\n#include b.ext\n".into());
+    let a = a.try_into_inserted().unwrap();
+    let b = map.insert(Origin::from_file("b.ext"), "abc".into()).try_into_inserted().unwrap();
+    let include = map.input(a).skip(15).offset();
+
+    let origin = map.context_error_origin_with_stack(map.input(b).skip(2).offset(), "here", &[include]);
+    assert_eq!(origin, map.context_error_origin(map.input(b).skip(2).offset(), "here", None)
+        .with_trace_frame(map.context_error_origin(include, "", None)));
+    assert_eq!(&format!("{origin}"), &normalize("
+        |--> b.ext:1:3
+        | 1 | abc
+        |   |   ^ here
+        | = included from a.ext:2:1
+    "));
+}
+
+#[test]
+fn context_error_render_string() {
+    let (map, index) = test_map_file("abc\ndef");
+    let input = map.input(index);
+    let options = DisplayOptions::new().with_verbosity(Verbosity::Medium);
+
+    let errors = [
+        input.skip(1).error(ErrorChain("first", Error("cause")), "a").into_context_error(&map),
+        input.skip(5).error(ErrorChain("second", Error("cause")), "b").into_context_error(&map),
+    ];
+    assert_eq!(errors[0].render_string(&options), format!("{}", errors[0].display_with_options(&options)));
+    assert_eq!(ContextError::render_batch_string(&errors, &options), normalize("
+        |error: first
+        |--> test:1:2
+        | 1 | abc
+        |   |  ^ a
+        |
+        |error: second
+        |--> test:2:2
+        | 2 | def
+        |   |  ^ b
+    "));
+    assert_eq!(ContextError::<Error>::render_batch_string(&[], &options), "");
+}
+
+#[test]
+fn context_error_without_position() {
+    let (map, index) = test_map_file("");
+    let error = ContextError::with_origins(Error("test-error"), [
+        map.context_error_source_origin(index, "file is empty"),
+    ]);
+    assert_eq!(error.to_string(), "test-error at test");
+    assert_eq!(error.display_with_context().to_string(), normalize("
+        |error: test-error
+        |--> test
+        | = file is empty
+    "));
+    assert_eq!(error.error_origins()[0].location(), None);
+
+    let options = DisplayOptions::new().with_verbosity(Verbosity::Short);
+    assert_eq!(error.display_with_options(&options).to_string(), "test: error: test-error\n");
+
+    let error = ContextError::with_origins(Error("test-error"), [
+        ContextErrorOrigin::without_position(Origin::from_named("missing"), ""),
+    ]);
+    assert_eq!(error.display_with_context().to_string(), normalize("
+        |error: test-error
+        |--> `missing`
+    "));
+}
+
+#[test]
+fn context_error_from_io_error() {
+    let io_error = std::io::Error::new(std::io::ErrorKind::PermissionDenied, "permission denied");
+    let origin = Origin::from_file("out/module.txt");
+    let error = ContextError::from_io_error(io_error, origin.clone(), "could not write output");
+    assert_eq!(error.error().kind(), std::io::ErrorKind::PermissionDenied);
+    assert_eq!(error.error_origins()[0].origin(), &origin);
+    assert_eq!(error.error_origins()[0].location(), None);
+    assert_eq!(error.display_with_context().to_string(), normalize("
+        |error: permission denied
+        |--> out/module.txt
+        | = could not write output
+    "));
+}
+
+#[test]
+fn context_error_at_end_of_input() {
+    for content in ["abc", "abc\n"] {
+        let (map, index) = test_map_file(content);
+        let error = Offset::end_of(&map, index).error(Error("unexpected end"), "here");
+        assert_eq!(error.into_context_error(&map).display_with_context().to_string(), normalize("
+            |error: unexpected end
+            |--> test:1:4
+            | 1 | abc
+            |   |    ^ here
+        "));
+    }
+
+    let (map, index) = test_map_file("");
+    let error = Offset::end_of(&map, index).error(Error("unexpected end"), "here");
+    assert_eq!(error.into_context_error(&map).display_with_context().to_string(), normalize("
+        |error: unexpected end
+        |--> test:1:1
+        | 1 | 
+        |   | ^ here
+    "));
+}
+
+#[test]
+fn context_error_line_origin() {
+    let (map, index) = test_map_file("abc\n\tdef  \r\nghi");
+    let line = map.line_offset(index, Line::from_zero_based(1)).unwrap();
+    let error = ContextError::with_origins(Error("trailing whitespace"), [
+        map.context_error_line_origin(line, "in this line"),
+    ]);
+    assert!(error.error_origins()[0].is_whole_line());
+    assert_eq!(error.to_string(), "trailing whitespace at test:2");
+    assert_eq!(
+        error.display_with_context().to_string(),
+        "error: trailing whitespace\n--> test:2\n 2 | \tdef  \r\n   | ^^^^^^ in this line\n",
+    );
+
+    let (map, index) = test_map("abc\ndef");
+    let origin = map.context_error_line_origin(map.input(index).skip(5).offset(), "here");
+    let error = ContextError::with_origins(Error("test-error"), [origin]);
+    assert_eq!(error.to_string(), "test-error in `test`, line 2");
+}
+
+#[test]
+fn expected_token_messages() {
+    let mut expected = ExpectedTokens::new();
+    assert!(expected.is_empty());
+    assert_eq!(expected.message(Some("x")), "unexpected `x`");
+    assert_eq!(expected.message(None), "unexpected end of input");
+
+    expected.insert_literal("a");
+    assert_eq!(expected.message(Some("x")), "expected `a`, found `x`");
+    expected.insert_literal("b");
+    assert_eq!(expected.message(Some("x")), "expected one of `a` or `b`, found `x`");
+    expected.insert("an identifier");
+    expected.insert_literal("a");
+    assert_eq!(expected.len(), 3);
+    assert_eq!(expected.iter().collect::<Vec<_>>(), ["`a`", "`b`", "an identifier"]);
+    assert_eq!(
+        expected.message(None),
+        "expected one of `a`, `b`, or an identifier, found end of input",
+    );
+
+    let expected = expected.with_max_listed(2);
+    assert_eq!(expected.max_listed(), 2);
+    assert_eq!(expected.message(Some("x")), "expected one of `a`, `b`, ..., found `x`");
+    let expected = expected.with_max_listed(0);
+    assert_eq!(expected.message(Some("x")), "expected one of 3 alternatives, found `x`");
+}
@@ -0,0 +1,30 @@
+#![cfg(feature = "test-util")]
+
+use src_ctx::{ContextError, Fixtures, SourceIndex, SourceMap};
+use test_util::Error;
+
+
+mod test_util;
+
+fn check_bangs(map: &SourceMap, index: SourceIndex) -> Vec<ContextError<Error>> {
+    let mut errors = Vec::new();
+    let mut input = map.input(index);
+    while let Some((c, rest)) = input.take_char() {
+        if c == '!' {
+            errors.push(input.error(Error("unexpected bang"), "here").into_context_error(map));
+        }
+        input = rest;
+    }
+    errors
+}
+
+#[test]
+fn fixtures() {
+    Fixtures::new("tests/fixtures/ui", "lang").run(check_bangs);
+}
+
+#[test]
+#[should_panic = "1 of the fixtures did not match"]
+fn missing_fixture_expectation() {
+    Fixtures::new("tests/fixtures/ui-missing", "lang").run(check_bangs);
+}
@@ -0,0 +1,39 @@
+#![cfg(all(feature = "serde_json", feature = "toml", feature = "serde_yaml"))]
+
+use src_ctx::normalize;
+use test_util::test_map_file;
+
+
+mod test_util;
+
+#[test]
+fn json_source_errors() {
+    let (map, index) = test_map_file("{\"a\": \"ä\",\n \"bä\": x}");
+    let error = serde_json::from_str::<serde_json::Value>(map.content(index)).unwrap_err();
+    let error = map.json_source_error(index, error);
+    assert_eq!(error.offset(), map.input(index).skip(20).offset());
+    assert_eq!(&format!("{}", error.into_context_error(&map).display_with_outer_context()), &normalize("
+        |error: expected value at line 2 column 9
+        |--> test:2:9
+        | 2 |  \"bä\": x}
+        |   |        ^ invalid JSON
+    "));
+}
+
+#[test]
+fn toml_source_errors() {
+    let (map, index) = test_map_file("a = \"ä\"\nb = x");
+    let error = toml::from_str::<toml::Value>(map.content(index)).unwrap_err();
+    let error = map.toml_source_error(index, error);
+    assert_eq!(error.offset(), map.input(index).skip(13).offset());
+    assert_eq!(error.note(), "invalid TOML");
+}
+
+#[test]
+fn yaml_source_errors() {
+    let (map, index) = test_map_file("a: \"ä\"\nb: [1, ä");
+    let error = serde_yaml::from_str::<serde_yaml::Value>(map.content(index)).unwrap_err();
+    let error = map.yaml_source_error(index, error);
+    assert_eq!(error.offset(), map.input(index).end().offset());
+    assert_eq!(error.note(), "invalid YAML");
+}
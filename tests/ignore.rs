@@ -0,0 +1,43 @@
+#![cfg(feature = "ignore")]
+
+use std::path::Path;
+
+use src_ctx::{LoadError, SourceMap};
+
+
+#[test]
+fn load_directory_respecting_ignore_files() {
+    let root = Path::new(env!("CARGO_TARGET_TMPDIR")).join("load_directory_respecting_ignore_files");
+    let _ = std::fs::remove_dir_all(&root);
+    for dir in ["src", "target", "vendor"] {
+        std::fs::create_dir_all(root.join(dir)).unwrap();
+    }
+    std::fs::write(root.join(".gitignore"), "/target\n").unwrap();
+    std::fs::write(root.join("vendor/.ignore"), "*.txt\n").unwrap();
+    for name in ["src/a.txt", "src/.hidden.txt", "target/b.txt", "vendor/c.txt"] {
+        std::fs::write(root.join(name), name).unwrap();
+    }
+
+    let mut map = SourceMap::new();
+    assert_eq!(map.load_directory(&root, ".txt").unwrap().len(), 4);
+
+    let mut map = SourceMap::new().with_respect_ignore_files(true);
+    let report = map.load_directory_report(&root, ".txt").unwrap();
+    let mut contents = report.inserted().iter().map(|&index| map.content(index)).collect::<Vec<_>>();
+    contents.sort();
+    assert_eq!(contents, ["src/.hidden.txt", "src/a.txt"]);
+    assert!(report.skipped().iter().all(|(path, _)| !path.starts_with(root.join("target"))));
+}
+
+#[test]
+fn load_directory_respecting_ignore_files_error() {
+    let root = Path::new(env!("CARGO_TARGET_TMPDIR")).join("load_directory_ignoring_missing");
+    let _ = std::fs::remove_dir_all(&root);
+
+    let mut map = SourceMap::new().with_respect_ignore_files(true);
+    let error = map.load_directory(&root, ".txt").unwrap_err();
+    assert!(matches!(error, LoadError::FindIgnoring { .. }), "{error:?}");
+    assert!(std::error::Error::source(&error).is_some());
+    let report = map.load_directory_lenient(&root, ".txt");
+    assert!(matches!(report.errors(), [LoadError::FindIgnoring { .. }]));
+}
@@ -1,3 +1,9 @@
+use std::ops::Range;
+
+use src_ctx::{
+    CompactSpan, SourceMap, Span, SpanInterner, SpanOffsetsError, SpanSet, SpanTree, StringLiteralError,
+    StringSyntax, is_ident_continue, is_ident_start, normalize,
+};
 use test_util::test_map;
 
 
@@ -52,6 +58,206 @@ fn inputs() {
     assert!(input.end().take_char().is_none());
 }
 
+#[test]
+fn keywords() {
+    let (map, index) = test_map("if iffy if_ if");
+    let input = map.input(index);
+    let is_ident_char = |c: char| c.is_alphanumeric() || c == '_';
+
+    assert_eq!(input.skip_str("if").unwrap().content(), " iffy if_ if");
+    assert!(input.skip_str("iff").is_none());
+
+    let rest = input.skip_keyword("if", is_ident_char).unwrap();
+    assert_eq!(rest.offset().byte(), 2);
+    assert!(rest.skip(1).skip_keyword("if", is_ident_char).is_none());
+    assert!(rest.skip(6).skip_keyword("if", is_ident_char).is_none());
+    assert!(rest.skip(10).skip_keyword("if", is_ident_char).unwrap().is_empty());
+    assert!(input.skip_keyword("else", is_ident_char).is_none());
+}
+
+#[test]
+fn string_literals() {
+    let (map, index) = test_map(r#""a\"b\n" rest"#);
+    let input = map.input(index);
+    let (value, rest) = input.take_string_literal(StringSyntax::default()).unwrap().unwrap();
+    assert_eq!(value, "a\"b\n");
+    assert_eq!(rest.content(), " rest");
+    assert!(rest.take_string_literal(StringSyntax::default()).is_none());
+
+    let syntax = StringSyntax::new('\'');
+    assert_eq!(syntax.escape(), None);
+    let (map, index) = test_map(r"'a\n'");
+    let (value, rest) = map.input(index).take_string_literal(syntax).unwrap().unwrap();
+    assert_eq!(value, "a\\n");
+    assert!(rest.is_empty());
+
+    let (map, index) = test_map("x = \"abc\\");
+    let input = map.input(index).skip(4);
+    let error = input.take_string_literal(StringSyntax::default()).unwrap().unwrap_err();
+    assert_eq!(error.error(), &StringLiteralError::Unterminated);
+    assert_eq!(error.offset(), input.end().offset());
+    assert_eq!(error.context_offset(), Some(input.offset()));
+    assert_eq!(&format!("{}", error.into_context_error(&map).display_with_context()), &normalize("
+        |error: Unterminated string literal
+        |--> `test`, line 1, column 10
+        | 1 | x = \"abc\\
+        |   |          ^ expected closing quote
+    "));
+
+    let (map, index) = test_map(r#""a\qb""#);
+    let error = map.input(index).take_string_literal(StringSyntax::default()).unwrap().unwrap_err();
+    assert_eq!(error.error(), &StringLiteralError::InvalidEscape('q'));
+    assert_eq!(error.offset().byte(), 2);
+}
+
+#[test]
+fn lines() {
+    let (map, index) = test_map("abc\ndef\n\nghi");
+    let input = map.input(index);
+
+    assert_eq!(input.rest_of_line().content(), "abc");
+    assert_eq!(input.skip_line().content(), "def\n\nghi");
+    assert_eq!(input.skip_line().offset().byte(), 4);
+
+    let mut lines = Vec::new();
+    let mut rest = input;
+    while let Some((line, next)) = rest.take_line() {
+        lines.push((line.content(), line.offset().byte()));
+        rest = next;
+    }
+    assert_eq!(lines, [("abc", 0), ("def", 4), ("", 8), ("ghi", 9)]);
+    assert!(rest.is_empty());
+    assert_eq!(rest.rest_of_line().content(), "");
+    assert_eq!(rest.skip_line().offset(), rest.offset());
+}
+
+#[test]
+fn indentation() {
+    let (map, index) = test_map("  \tabc\n    x {\n      y\n\n    }\n");
+    let input = map.input(index);
+
+    assert_eq!(input.indentation().content(), "  \t");
+    assert_eq!(input.indentation_width(4), 4);
+    assert_eq!(input.indentation_width(8), 8);
+    assert_eq!(input.skip(6).indentation().content(), "");
+
+    let block = input.skip(7);
+    let span = block.offset().span(input.end().offset());
+    assert_eq!(map.common_indentation(span), "    ");
+    assert_eq!(map.common_indentation(input.offset().span(input.end().offset())), "  ");
+
+    let dedented = map.dedent(span);
+    assert_eq!(dedented.content(), "x {\n  y\n\n}\n");
+    assert_eq!(dedented.offset(0).byte(), 11);
+    assert_eq!(dedented.offset(6).byte(), 21);
+    assert_eq!(dedented.offset(8).byte(), 23);
+    assert_eq!(dedented.offset(9).byte(), 28);
+    assert_eq!(dedented.offset(11).byte(), 30);
+}
+
+#[test]
+fn char_counts() {
+    let (map, index) = test_map("aäöc");
+    let input = map.input(index);
+
+    let (taken, rest) = input.take_chars(3).unwrap();
+    assert_eq!(taken.content(), "aäö");
+    assert_eq!(rest.content(), "c");
+    assert_eq!(rest.offset().byte(), 5);
+    assert_eq!(input.skip_chars(0).unwrap().offset(), input.offset());
+    assert_eq!(input.take_chars(0).map(|(taken, _)| taken.content()), Some(""));
+    assert_eq!(input.skip_chars(4).unwrap().offset(), input.end().offset());
+    assert!(input.skip_chars(5).is_none());
+    assert!(input.take_chars(5).is_none());
+}
+
+#[test]
+fn identifiers() {
+    let (map, index) = test_map("größe_2 + _x $y 2z");
+    let input = map.input(index);
+
+    let (ident, rest) = input.take_ident().unwrap();
+    assert_eq!(ident.content(), "größe_2");
+    assert_eq!(rest.offset().byte(), 9);
+    let rest = rest.skip_str(" + ").unwrap();
+    let (ident, rest) = rest.take_ident().unwrap();
+    assert_eq!(ident.content(), "_x");
+    let rest = rest.skip_char(' ').unwrap();
+    assert!(rest.take_ident().is_none());
+    let (ident, rest) = rest.take_ident_with(|c| c == '$', is_ident_continue).unwrap();
+    assert_eq!(ident.content(), "$y");
+    assert!(rest.skip_char(' ').unwrap().take_ident().is_none());
+    assert!(input.end().take_ident().is_none());
+
+    assert!(is_ident_start('ä') && is_ident_start('_') && !is_ident_start('1'));
+    assert!(is_ident_continue('1') && !is_ident_continue('-'));
+}
+
+#[test]
+fn backwards_scanning() {
+    let (map, index) = test_map("let ä = 2  \n;");
+    let input = map.input(index);
+    assert_eq!(input.prev_char(), None);
+    assert!(input.rfind(|_| true).is_none());
+
+    let semicolon = input.skip(13);
+    assert_eq!(semicolon.content(), ";");
+    assert_eq!(semicolon.prev_char(), Some('\n'));
+    let last = semicolon.rfind(|c| !c.is_whitespace()).unwrap();
+    assert_eq!(last.offset().byte(), 9);
+    assert_eq!(last.content(), "2  \n;");
+    assert_eq!(last.skip_chars(1).unwrap().offset().byte(), 10);
+
+    let truncated = input.skip(4).truncate(2);
+    assert_eq!(truncated.content(), "ä");
+    assert_eq!(truncated.prev_char(), Some(' '));
+    let back = truncated.skip_back(4);
+    assert_eq!(back.content(), "let ä");
+    assert_eq!(back.offset(), input.offset());
+    assert_eq!(truncated.skip(2).rfind(|c| c == 'ä').unwrap().content(), "ä");
+}
+
+#[test]
+fn end_trimming() {
+    let (map, index) = test_map("  let x = 2;\r\n \n");
+    let input = map.input(index);
+
+    let trimmed = input.trim();
+    assert_eq!(trimmed.content(), "let x = 2;");
+    assert_eq!(trimmed.offset().byte(), 2);
+    assert_eq!(input.trim_start().content(), "let x = 2;\r\n \n");
+    assert_eq!(input.trim_end().content(), "  let x = 2;");
+    assert_eq!(input.trim_end().offset(), input.offset());
+    assert!(trimmed.ends_with(";") && !trimmed.ends_with("2"));
+    assert_eq!(trimmed.truncate_end(1).content(), "let x = 2");
+    assert_eq!(trimmed.truncate_end(10).content(), "");
+    assert!(input.end().trim().is_empty());
+}
+
+#[test]
+fn span_ranges() {
+    let (map, index) = test_map("aäc");
+    let (other_map, other_index) = test_map("abc");
+    let input = map.input(index);
+    let (start, end) = (input.skip(1).offset(), input.end().offset());
+
+    let span = Span::try_from((start, end)).unwrap();
+    assert_eq!(&map[span], "äc");
+    assert_eq!(Range::from(span), start..end);
+    assert_eq!(Span::try_from(start..end), Ok(span));
+    assert_eq!(Span::try_from((end, start)), Err(SpanOffsetsError::Reversed));
+    assert_eq!(
+        Span::try_from((start, other_map.input(other_index).end().offset())),
+        Err(SpanOffsetsError::DifferentSources),
+    );
+
+    assert_eq!(map.span_from_range(index, 1..4), Some(span));
+    assert_eq!(map.span_from_range(index, 0..0).map(|span| &map[span]), Some(""));
+    assert_eq!(map.span_from_range(index, 2..4), None);
+    assert_eq!(map.span_from_range(index, 1..5), None);
+    assert_eq!(map.span_from_range(index, Range { start: 3, end: 1 }), None);
+}
+
 #[test]
 fn offsets() {
     let (map, index) = test_map("abcdef");
@@ -80,4 +286,120 @@ fn spans() {
     assert_eq!(span.byte_len(), 4);
     assert_eq!(span.byte_range(), 1..5);
     assert_eq!(map.span_str(span), "bcde");
-}
\ No newline at end of file
+}
+#[test]
+fn compact_spans() {
+    let (map, index) = test_map("abcdef");
+    let input = map.input(index);
+    let span = input.skip(2).offset().span(input.skip(5).offset());
+
+    let compact = span.to_compact().unwrap();
+    assert_eq!(compact, CompactSpan::new(2, 3));
+    assert_eq!(compact.start(), 2);
+    assert_eq!(compact.end(), 5);
+    assert_eq!(compact.byte_len(), 3);
+    assert_eq!(compact.byte_range(), 2..5);
+    assert_eq!(compact.to_span(index), span);
+    assert_eq!(map.span_str(compact.to_span(index)), "cde");
+    assert_eq!(std::mem::size_of::<CompactSpan>(), 8);
+}
+
+#[test]
+#[should_panic(expected = "compact span end must fit into u32")]
+fn compact_span_overflow() {
+    CompactSpan::new(u32::MAX, 1);
+}
+
+#[test]
+fn span_interner() {
+    let (map, index) = test_map("abcdef");
+    let input = map.input(index);
+    let a = input.offset().span(input.skip(2).offset());
+    let b = input.skip(2).offset().span(input.skip(4).offset());
+
+    let mut interner = SpanInterner::new(&map);
+    assert!(interner.is_empty());
+    let a_id = interner.intern(a);
+    let b_id = interner.intern(b);
+    assert_ne!(a_id, b_id);
+    assert_eq!(interner.intern(a), a_id);
+    assert_eq!(interner.len(), 2);
+    assert_eq!(interner.span(a_id), a);
+    assert_eq!(interner.span(b_id), b);
+
+    let mut other = SpanInterner::new(&SourceMap::new());
+    let result = std::panic::catch_unwind(move || other.intern(a));
+    assert!(result.is_err());
+}
+
+#[test]
+fn span_sets() {
+    let (map, index) = test_map("abcdefghijklmnop");
+    let span = |start: usize, end: usize| map.span_from_range(index, start..end).unwrap();
+    let ranges = |set: &SpanSet| set.iter()
+        .map(|span| (span.start().byte(), span.end().byte()))
+        .collect::<Vec<_>>();
+
+    let mut set = SpanSet::new(index);
+    assert!(set.is_empty());
+    set.insert(span(4, 6));
+    set.insert(span(0, 2));
+    set.insert(span(8, 10));
+    set.insert(span(3, 3));
+    assert_eq!(ranges(&set), [(0, 2), (4, 6), (8, 10)]);
+    set.insert(span(6, 8));
+    assert_eq!(ranges(&set), [(0, 2), (4, 10)]);
+    set.insert(span(1, 5));
+    assert_eq!(ranges(&set), [(0, 10)]);
+    assert_eq!((set.len(), set.byte_len()), (1, 10));
+
+    set.remove(span(2, 4));
+    set.remove(span(7, 12));
+    assert_eq!(ranges(&set), [(0, 2), (4, 7)]);
+    set.remove(span(0, 2));
+    assert_eq!(ranges(&set), [(4, 7)]);
+
+    assert!(set.contains(span(4, 4).start()));
+    assert!(!set.contains(span(7, 7).start()));
+    assert!(set.contains_span(span(5, 7)));
+    assert!(!set.contains_span(span(3, 5)));
+    assert!(set.overlaps(span(3, 5)));
+    assert!(!set.overlaps(span(7, 9)));
+
+    let mut other = SpanSet::new(index);
+    other.insert(span(6, 12));
+    set.union(&other);
+    assert_eq!(ranges(&set), [(4, 12)]);
+    other.remove(span(6, 8));
+    set.subtract(&other);
+    assert_eq!(ranges(&set), [(4, 8)]);
+
+    let (other_map, other_index) = test_map("abc");
+    let other_span = other_map.span_from_range(other_index, 0..2).unwrap();
+    assert!(!set.contains_span(other_span));
+    let result = std::panic::catch_unwind(move || set.insert(other_span));
+    assert!(result.is_err());
+}
+
+#[test]
+fn span_trees() {
+    let (map, index) = test_map("fn f(a, b) { a }");
+    let span = |start: usize, end: usize| map.span_from_range(index, start..end).unwrap();
+    let (other_map, other_index) = test_map("fn g() {}");
+    let other = other_map.span_from_range(other_index, 0..9).unwrap();
+    let spans = [span(0, 16), span(4, 10), span(5, 6), span(8, 9), span(11, 16), span(13, 14), other];
+    let tree = spans.iter().rev().copied().collect::<SpanTree>();
+    assert_eq!(tree.len(), 7);
+    let byte_ranges = |spans: Vec<Span>| spans.iter().map(|span| span.byte_range()).collect::<Vec<_>>();
+
+    let offset = span(13, 13).start();
+    assert_eq!(tree.innermost(offset), Some(span(13, 14)));
+    assert_eq!(byte_ranges(tree.containing(offset)), [0..16, 11..16, 13..14]);
+    assert_eq!(tree.innermost(span(16, 16).start()), None);
+    assert_eq!(tree.innermost(span(7, 7).start()), Some(span(4, 10)));
+    assert_eq!(tree.innermost(other.start()), Some(other));
+
+    assert_eq!(byte_ranges(tree.overlapping(span(6, 12))), [0..16, 4..10, 8..9, 11..16]);
+    assert_eq!(byte_ranges(tree.overlapping(span(9, 9))), [0..16, 4..10]);
+    assert!(SpanTree::new([]).innermost(offset).is_none());
+}
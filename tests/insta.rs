@@ -0,0 +1,21 @@
+#![cfg(feature = "insta")]
+
+use src_ctx::assert_context_snapshot;
+use test_util::{Error, ErrorChain, test_map_file};
+
+
+mod test_util;
+
+#[test]
+fn context_snapshot() {
+    let (map, index) = test_map_file("abc\ndef");
+    let error = map.input(index).skip(5).error(ErrorChain("test-error", Error("cause")), "test-note")
+        .into_context_error(&map);
+    assert_context_snapshot!(error, @r"
+    error: test-error
+    cause: cause
+    --> test:2:2
+        2 | def
+          |  ^ test-note
+    ");
+}
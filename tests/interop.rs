@@ -0,0 +1,39 @@
+#![cfg(any(feature = "codespan", feature = "codemap"))]
+
+use src_ctx::Span;
+use test_util::test_map;
+
+
+mod test_util;
+
+#[cfg(feature = "codespan")]
+#[test]
+fn codespan_conversions() {
+    use src_ctx::CompactSpan;
+
+    let (map, index) = test_map("abcdef");
+    let input = map.input(index);
+    let span = input.skip(2).offset().span(input.skip(5).offset());
+
+    let converted = codespan::Span::try_from(span).unwrap();
+    assert_eq!(converted, codespan::Span::new(2, 5));
+    assert_eq!(Span::from_codespan(index, converted), span);
+    assert_eq!(codespan::Span::from(CompactSpan::new(2, 3)), converted);
+    assert_eq!(CompactSpan::from(converted), CompactSpan::new(2, 3));
+}
+
+#[cfg(feature = "codemap")]
+#[test]
+fn codemap_conversions() {
+    let (map, index) = test_map("abcdef");
+    let input = map.input(index);
+    let span = input.skip(2).offset().span(input.skip(5).offset());
+
+    let mut codemap = codemap::CodeMap::new();
+    codemap.add_file("other".into(), "xyz".into());
+    let file = codemap.add_file("test".into(), map.content(index).into());
+
+    let converted = span.to_codemap(&file);
+    assert_eq!(file.source_slice(converted), "cde");
+    assert_eq!(Span::from_codemap(index, &file, converted), span);
+}
@@ -0,0 +1,40 @@
+use src_ctx::{Column, Line, Location};
+use test_util::{test_byte_map, test_map};
+
+
+mod test_util;
+
+#[test]
+fn lines_and_columns() {
+    assert_eq!(Line::from_one_based(0), None);
+    assert_eq!(Line::from_one_based(3).unwrap().to_zero_based(), 2);
+    assert_eq!(Line::from_zero_based(2).to_one_based(), 3);
+    assert_eq!(Line::FIRST.to_zero_based(), 0);
+    assert_eq!(Column::from_zero_based(0), Column::FIRST);
+    assert_eq!(Column::from_one_based(7).unwrap().to_string(), "7");
+}
+
+#[test]
+fn locate() {
+    let (map, index) = test_map("ab\ncd\n");
+    let input = map.input(index);
+
+    let location = map.locate(input.skip(4).offset());
+    assert_eq!(location, Location::new(Line::from_zero_based(1), Column::from_zero_based(1)));
+    assert_eq!(map.locate(input.offset()), Location::new(Line::FIRST, Column::FIRST));
+    assert_eq!(map.locate(input.skip(2).offset()).column().to_one_based(), 3);
+    assert_eq!(
+        map.locate(input.end().offset()),
+        Location::new(Line::from_zero_based(1), Column::from_zero_based(2)),
+    );
+
+    assert_eq!(map.line_offset(index, Line::from_zero_based(1)), Some(input.skip(3).offset()));
+    assert_eq!(map.line_offset(index, Line::from_zero_based(3)), None);
+
+    let origin = map.context_error_origin(input.skip(4).offset(), "here", None);
+    assert_eq!(origin.location(), Some(location));
+
+    let (map, index) = test_byte_map(b"abc");
+    let origin = map.context_error_origin(map.input(index).skip(1).offset(), "here", None);
+    assert_eq!(origin.location(), None);
+}
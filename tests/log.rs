@@ -0,0 +1,52 @@
+#![cfg(feature = "log")]
+
+use std::sync::Mutex;
+
+use log::{Level, LevelFilter, Log, Metadata, Record};
+use src_ctx::normalize;
+use test_util::{Error, test_map_file};
+
+
+mod test_util;
+
+static RECORDS: Mutex<Vec<(Level, String)>> = Mutex::new(Vec::new());
+
+struct TestLogger;
+
+impl Log for TestLogger {
+    fn enabled(&self, _: &Metadata<'_>) -> bool {
+        true
+    }
+
+    fn log(&self, record: &Record<'_>) {
+        RECORDS.lock().unwrap().push((record.level(), record.args().to_string()));
+    }
+
+    fn flush(&self) {}
+}
+
+#[test]
+fn log_levels() {
+    log::set_logger(&TestLogger).unwrap();
+    log::set_max_level(LevelFilter::Trace);
+
+    let (map, index) = test_map_file("abc");
+    let error = map.input(index).skip(1).error(Error("test-error"), "test-note")
+        .into_context_error(&map);
+    error.log(Level::Warn);
+    assert_eq!(RECORDS.lock().unwrap().as_slice(), &[
+        (Level::Warn, "test-error at test:1:2".into()),
+        (Level::Debug, normalize("
+            |error: test-error
+            |--> test:1:2
+            | 1 | abc
+            |   |  ^ test-note
+        ")),
+    ]);
+
+    for level in [Level::Debug, Level::Trace] {
+        RECORDS.lock().unwrap().clear();
+        error.log(level);
+        assert_eq!(RECORDS.lock().unwrap().as_slice(), &[(level, "test-error at test:1:2".into())]);
+    }
+}
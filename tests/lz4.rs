@@ -0,0 +1,40 @@
+#![cfg(feature = "lz4")]
+
+use src_ctx::{Origin, SourceMap};
+
+
+#[test]
+fn compressed_contents() {
+    let mut map = SourceMap::new()
+        .with_compressed_contents(true)
+        .with_content_cache_limit(Some(8));
+    let a = map.insert(Origin::from_named("a"), "aaaaaaaa".into()).try_into_inserted().unwrap();
+    assert_eq!(map.cached_content_bytes(), 8);
+    let b = map.insert(Origin::from_named("b"), "bbbbbbbb".into()).try_into_inserted().unwrap();
+    assert_eq!(map.cached_content_bytes(), 8);
+
+    assert_eq!(map.content(a), "aaaaaaaa");
+    assert_eq!(map.cached_content_bytes(), 16);
+    map.trim_content_cache();
+    assert_eq!(map.cached_content_bytes(), 8);
+    assert_eq!(map.input(b).skip(4).content(), "bbbb");
+    assert_eq!(map.content(a), "aaaaaaaa");
+}
+
+#[test]
+fn compressed_contents_many_entries() {
+    let mut map = SourceMap::new()
+        .with_compressed_contents(true)
+        .with_content_cache_limit(Some(40));
+    let indices = (0..1000).map(|n| {
+        map.insert(Origin::from_named(format!("{n}")), format!("{n:04}").into())
+            .try_into_inserted().unwrap()
+    }).collect::<Vec<_>>();
+    assert_eq!(map.cached_content_bytes(), 40);
+
+    for (n, &index) in indices.iter().enumerate().rev() {
+        assert_eq!(map.content(index), format!("{n:04}"));
+    }
+    map.trim_content_cache();
+    assert_eq!(map.cached_content_bytes(), 40);
+}
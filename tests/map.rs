@@ -1,5 +1,12 @@
-use src_ctx::{SourceMap, Origin, Insert};
-use test_util::test_map;
+use std::panic::AssertUnwindSafe;
+use std::path::Path;
+
+use src_ctx::{
+    SourceMap, Origin, Insert, LoadError, SkipReason, Fingerprint, GlobalOffset, GlobalSpan,
+    SpanResolveError, CompactSpan, SourceCategory, DisplayOptions, SearchOptions, DirectoryOrder,
+    normalize,
+};
+use test_util::{test_map, Error};
 
 
 mod test_util;
@@ -50,4 +57,656 @@ fn map_ids() {
 
     assert!(! map_a.contains(index_b));
     assert!(! map_b.contains(index_a));
-}
\ No newline at end of file
+}
+
+#[test]
+fn try_accessors() {
+    let (map_a, index_a) = test_map("abä");
+    let (map_b, index_b) = test_map("content b");
+
+    assert_eq!(map_a.try_origin(index_a), Some(&Origin::from_named("test")));
+    assert_eq!(map_a.try_content(index_a), Some("abä"));
+    assert_eq!(map_a.try_input(index_a).map(|input| input.len()), Some(4));
+    assert!(map_a.try_origin(index_b).is_none());
+    assert!(map_a.try_content(index_b).is_none());
+    assert!(map_a.try_input(index_b).is_none());
+
+    let input = map_a.input(index_a);
+    let span = input.offset().span(input.skip(2).offset());
+    assert_eq!(map_a.try_span_str(span), Some("ab"));
+    assert_eq!(map_b.try_span_str(span), None);
+    let span_b = map_b.input(index_b).offset().span(map_b.input(index_b).skip(3).offset());
+    assert_eq!(map_a.try_span_str(span_b), None);
+
+    let mut map = SourceMap::new();
+    let mut transaction = map.transaction();
+    let index = transaction.insert_anonymous("abc".into());
+    drop(transaction);
+    assert!(map.try_content(index).is_none());
+}
+
+#[test]
+fn checked_span_str() {
+    let (map, index) = test_map("abä");
+    let (other, other_index) = test_map("abc");
+
+    assert_eq!(map.get_span_str(CompactSpan::new(1, 3).to_span(index)), Ok("bä"));
+    assert_eq!(
+        map.get_span_str(CompactSpan::new(1, 1).to_span(other_index)),
+        Err(SpanResolveError::ForeignMap),
+    );
+    assert_eq!(other.get_span_str(CompactSpan::new(1, 1).to_span(other_index)), Ok("b"));
+    let error = map.get_span_str(CompactSpan::new(2, 3).to_span(index)).unwrap_err();
+    assert_eq!(error, SpanResolveError::OutOfBounds { end: 5, len: 4 });
+    assert_eq!(error.to_string(), "Span ends at byte 5, exceeding the content length of 4 bytes");
+    assert_eq!(
+        map.get_span_str(CompactSpan::new(0, 3).to_span(index)),
+        Err(SpanResolveError::NotCharBoundary { byte: 3 }),
+    );
+
+    let mut map = SourceMap::new();
+    let mut transaction = map.transaction();
+    let index = transaction.insert_anonymous("abc".into());
+    drop(transaction);
+    assert_eq!(
+        map.get_span_str(CompactSpan::new(0, 1).to_span(index)),
+        Err(SpanResolveError::MissingEntry),
+    );
+}
+#[test]
+fn anonymous_entries() {
+    let mut map = SourceMap::new();
+    map.insert(Origin::from_named("<anonymous-1>"), "taken".into());
+
+    let a = map.insert_anonymous("a".into());
+    let b = map.insert_anonymous("b".into());
+    assert_ne!(a, b);
+    assert_eq!(map.origin(a), &Origin::from_named("<anonymous-0>"));
+    assert_eq!(map.origin(b), &Origin::from_named("<anonymous-2>"));
+    assert_eq!(map.content(a), "a");
+    assert_eq!(map.content(b), "b");
+}
+
+#[test]
+fn canonical_paths() {
+    let mut map = SourceMap::new().with_canonical_paths(true);
+    let index = map.load_file("./src/lib.rs").unwrap().try_into_inserted().unwrap();
+    assert_eq!(map.origin(index), &Origin::from_file("./src/lib.rs"));
+    assert_eq!(map.load_file("src/../src/lib.rs").unwrap(), Insert::Previous(index));
+    assert_eq!(map.file_index("src/lib.rs"), Some(index));
+    assert!(map.contains_file("src/./lib.rs"));
+
+    let index = map.insert(Origin::from_file("missing/a.ext"), "".into())
+        .try_into_inserted().unwrap();
+    assert_eq!(map.file_index("./missing/b/../a.ext"), Some(index));
+
+    let mut map = SourceMap::new();
+    let index = map.load_file("./src/lib.rs").unwrap().try_into_inserted().unwrap();
+    assert!(map.load_file("src/lib.rs").unwrap().try_into_inserted().is_ok());
+    assert_ne!(map.file_index("src/lib.rs"), Some(index));
+}
+
+#[test]
+fn case_insensitive_paths() {
+    let mut map = SourceMap::new().with_case_insensitive_paths(true);
+    let index = map.insert(Origin::from_file("Dir/File.ext"), "content".into())
+        .try_into_inserted().unwrap();
+    assert_eq!(map.origin(index), &Origin::from_file("Dir/File.ext"));
+    assert_eq!(map.file_index("dir/file.EXT"), Some(index));
+    assert!(map.contains_file("DIR/FILE.EXT"));
+    assert_eq!(
+        map.insert(Origin::from_file("dir/file.ext"), "other".into()),
+        Insert::Previous(index),
+    );
+
+    let index = map.load_file("src/lib.rs").unwrap().try_into_inserted().unwrap();
+    assert_eq!(map.load_file("SRC/LIB.RS").unwrap(), Insert::Previous(index));
+}
+
+#[test]
+fn load_directory_report() {
+    let mut map = SourceMap::new();
+    let previous = map.load_file("tests/fixtures/load/a.txt").unwrap()
+        .try_into_inserted().unwrap();
+
+    let report = map.load_directory_report("tests/fixtures/load", ".txt").unwrap();
+    assert_eq!(report.previous(), &[previous]);
+    assert_eq!(report.inserted().len(), 1);
+    assert_eq!(map.origin(report.inserted()[0]), &Origin::from_file("tests/fixtures/load/nested/c.txt"));
+
+    let mut skipped = report.skipped().to_vec();
+    skipped.sort();
+    assert_eq!(skipped, [
+        (Path::new("tests/fixtures/load/b.md").into(), SkipReason::Extension),
+        (Path::new("tests/fixtures/load/nested/d.txt").into(), SkipReason::Binary),
+    ]);
+
+    assert!(matches!(
+        SourceMap::new().load_directory("tests/fixtures/load", ".txt"),
+        Err(LoadError::InvalidUtf8 { .. }),
+    ));
+}
+
+#[test]
+fn load_directory_with_progress() {
+    let mut map = SourceMap::new();
+    let mut paths = Vec::new();
+    let report = map.load_directory_with_progress("tests/fixtures/load", ".txt", |path| {
+        paths.push(path.to_path_buf());
+    }).unwrap();
+    assert_eq!(report.inserted().len(), 2);
+    assert_eq!(report.skipped().len(), 2);
+
+    paths.sort();
+    assert_eq!(paths, [
+        Path::new("tests/fixtures/load/a.txt"),
+        Path::new("tests/fixtures/load/b.md"),
+        Path::new("tests/fixtures/load/nested/c.txt"),
+        Path::new("tests/fixtures/load/nested/d.txt"),
+    ]);
+}
+
+#[test]
+fn load_directory_order() {
+    let root = Path::new(env!("CARGO_TARGET_TMPDIR")).join("load_directory_order");
+    let _ = std::fs::remove_dir_all(&root);
+    std::fs::create_dir_all(root.join("a/b")).unwrap();
+    for name in ["b.txt", "a.txt", "a/c.txt", "a/b/d.txt"] {
+        std::fs::write(root.join(name), name).unwrap();
+    }
+
+    let mut map = SourceMap::new();
+    let report = map.load_directory_report(&root, ".txt").unwrap();
+    let contents = report.inserted().iter().map(|&index| map.content(index)).collect::<Vec<_>>();
+    assert_eq!(contents, ["a/b/d.txt", "a/c.txt", "a.txt", "b.txt"]);
+
+    let mut map = SourceMap::new().with_directory_order(DirectoryOrder::FileSystem);
+    let report = map.load_directory_report(&root, ".txt").unwrap();
+    let mut contents = report.inserted().iter().map(|&index| map.content(index)).collect::<Vec<_>>();
+    contents.sort();
+    assert_eq!(contents, ["a.txt", "a/b/d.txt", "a/c.txt", "b.txt"]);
+}
+
+#[test]
+fn load_invalid_utf8() {
+    let error = SourceMap::new().load_file("tests/fixtures/invalid.txt").unwrap_err();
+    let LoadError::InvalidUtf8 { file, valid_up_to, line, column } = &error else {
+        panic!("unexpected error: {error:?}");
+    };
+    assert_eq!(file.as_ref(), Path::new("tests/fixtures/invalid.txt"));
+    assert_eq!(*valid_up_to, 5);
+    assert_eq!((*line, *column), (2, 3));
+    assert_eq!(
+        &error.to_string(),
+        "File `tests/fixtures/invalid.txt` contains invalid UTF-8 at line 2, column 3",
+    );
+}
+
+#[cfg(unix)]
+#[test]
+fn load_directory_lenient() {
+    let root = Path::new(env!("CARGO_TARGET_TMPDIR")).join("load_directory_lenient");
+    let _ = std::fs::remove_dir_all(&root);
+    std::fs::create_dir_all(&root).unwrap();
+    std::fs::write(root.join("a.txt"), "a").unwrap();
+    std::os::unix::fs::symlink(root.join("missing.txt"), root.join("broken.txt")).unwrap();
+
+    assert!(matches!(SourceMap::new().load_directory(&root, ".txt"), Err(LoadError::Find { .. })));
+
+    let mut map = SourceMap::new();
+    let report = map.load_directory_lenient(&root, ".txt");
+    assert_eq!(report.inserted().len(), 1);
+    assert_eq!(map.content(report.inserted()[0]), "a");
+    assert_eq!(report.errors().len(), 1);
+    assert!(matches!(report.errors()[0], LoadError::Find { .. }));
+
+    let report = SourceMap::new().load_directory_lenient(root.join("missing"), ".txt");
+    assert!(report.inserted().is_empty());
+    assert_eq!(report.errors().len(), 1);
+}
+
+#[cfg(unix)]
+#[test]
+fn load_directory_canonical_symlink() {
+    let root = Path::new(env!("CARGO_TARGET_TMPDIR")).join("load_directory_canonical_symlink");
+    let _ = std::fs::remove_dir_all(&root);
+    std::fs::create_dir_all(&root).unwrap();
+    std::fs::write(root.join("x.ext"), "x").unwrap();
+    std::os::unix::fs::symlink(root.join("x.ext"), root.join("y.ext")).unwrap();
+
+    let mut map = SourceMap::new().with_canonical_paths(true);
+    let inserts = map.load_directory(&root, ".ext").unwrap();
+    let index = inserts[0].try_into_inserted().unwrap();
+    assert_eq!(inserts, [Insert::Inserted(index), Insert::Previous(index)]);
+    assert_eq!(map.origin(index), &Origin::from_file(root.join("x.ext")));
+
+    let mut map = SourceMap::new().with_canonical_paths(true);
+    let report = map.load_directory_report(&root, ".ext").unwrap();
+    assert_eq!(report.inserted().len(), 1);
+    assert_eq!(report.previous(), report.inserted());
+}
+
+#[cfg(target_os = "linux")]
+#[test]
+fn load_directory_case_insensitive_duplicates() {
+    let root = Path::new(env!("CARGO_TARGET_TMPDIR"))
+        .join("load_directory_case_insensitive_duplicates");
+    let _ = std::fs::remove_dir_all(&root);
+    std::fs::create_dir_all(&root).unwrap();
+    std::fs::write(root.join("A.ext"), "upper").unwrap();
+    std::fs::write(root.join("a.ext"), "lower").unwrap();
+
+    let mut map = SourceMap::new().with_case_insensitive_paths(true);
+    let report = map.load_directory_lenient(&root, ".ext");
+    assert!(report.errors().is_empty());
+    assert_eq!(report.inserted().len(), 1);
+    assert_eq!(report.previous(), report.inserted());
+    assert_eq!(map.content(report.inserted()[0]), "upper");
+
+    let mut map = SourceMap::new().with_case_insensitive_paths(true);
+    let mut paths = Vec::new();
+    let report = map
+        .load_directory_with_progress(&root, ".ext", |path| paths.push(path.to_path_buf()))
+        .unwrap();
+    assert_eq!(paths.len(), 2);
+    assert_eq!(report.inserted().len(), 1);
+    assert_eq!(report.previous(), report.inserted());
+}
+
+#[test]
+fn max_file_size() {
+    let mut map = SourceMap::new().with_max_file_size(Some(1));
+    let error = map.load_file("tests/fixtures/load/a.txt").unwrap_err();
+    assert!(matches!(error, LoadError::TooLarge { size: 2, limit: 1, .. }));
+
+    let report = map.load_directory_report("tests/fixtures/load/nested", ".txt").unwrap();
+    assert!(report.inserted().is_empty());
+    assert!(report.skipped().iter().all(|(_, reason)| *reason == SkipReason::TooLarge));
+    assert_eq!(report.skipped().len(), 2);
+
+    let mut map = SourceMap::new().with_max_file_size(Some(2));
+    assert!(map.load_file("tests/fixtures/load/a.txt").is_ok());
+}
+
+#[test]
+fn transactions() {
+    let mut map = SourceMap::new();
+    let kept = map.insert(Origin::from_named("kept"), "kept".into()).try_into_inserted().unwrap();
+
+    let mut transaction = map.transaction();
+    transaction.insert(Origin::from_named("staged"), "staged".into());
+    transaction.insert_anonymous("anonymous".into());
+    assert!(transaction.origin_index(&Origin::from_named("staged")).is_some());
+    drop(transaction);
+    assert!(map.origin_index(&Origin::from_named("staged")).is_none());
+    assert_eq!(map.origins().count(), 1);
+    assert_eq!(map.content(kept), "kept");
+
+    let transaction = map.transaction();
+    transaction.rollback();
+    assert_eq!(map.origins().count(), 1);
+
+    let mut transaction = map.transaction();
+    let staged = transaction.insert(Origin::from_named("staged"), "staged".into())
+        .try_into_inserted().unwrap();
+    let anonymous = transaction.insert_anonymous("anonymous".into());
+    transaction.commit();
+    assert_eq!(map.origin_index(&Origin::from_named("staged")), Some(staged));
+    assert_eq!(map.origin(anonymous), &Origin::from_named("<anonymous-0>"));
+}
+
+#[test]
+fn transaction_rollback_indices() {
+    let mut map = SourceMap::new();
+    let mut transaction = map.transaction();
+    let removed = transaction.insert(Origin::from_named("removed"), "removed".into())
+        .try_into_inserted().unwrap();
+    assert!(transaction.contains(removed));
+    drop(transaction);
+    assert!(!map.contains(removed));
+
+    let inserted = map.insert(Origin::from_named("inserted"), "inserted".into())
+        .try_into_inserted().unwrap();
+    assert!(map.contains(inserted));
+    assert!(!map.contains(removed));
+    assert_ne!(inserted, removed);
+    assert_eq!(map.try_content(removed), None);
+    assert_eq!(map.fork().try_origin(removed), None);
+
+    let result = std::panic::catch_unwind(AssertUnwindSafe(|| map.content(removed).len()));
+    let message = result.unwrap_err();
+    assert_eq!(message.downcast_ref::<&str>(), Some(&"content index must belong to source map"));
+}
+
+#[test]
+fn entry_iteration() {
+    let mut map = SourceMap::new();
+    assert!(map.is_empty());
+    assert_eq!(map.len(), 0);
+
+    let a = map.insert(Origin::from_named("a"), "content a".into()).try_into_inserted().unwrap();
+    let b = map.insert(Origin::from_named("b"), "content b".into()).try_into_inserted().unwrap();
+    assert!(! map.is_empty());
+    assert_eq!(map.len(), 2);
+
+    let entries: Vec<_> = map.entries().collect();
+    assert_eq!(entries, [
+        (a, &Origin::from_named("a"), "content a"),
+        (b, &Origin::from_named("b"), "content b"),
+    ]);
+}
+
+#[test]
+fn fingerprints() {
+    let root = Path::new(env!("CARGO_TARGET_TMPDIR")).join("fingerprints");
+    let _ = std::fs::remove_dir_all(&root);
+    std::fs::create_dir_all(&root).unwrap();
+    let path = root.join("a.txt");
+    std::fs::write(&path, "abc").unwrap();
+
+    let map = SourceMap::new();
+    let fingerprint = map.fingerprint_file(&path).unwrap();
+    assert_eq!(fingerprint.len(), 3);
+    assert_eq!(fingerprint.content_hash(), Fingerprint::hash_content(b"abc"));
+    assert_eq!(Fingerprint::hash_content(b""), 0xcbf2_9ce4_8422_2325);
+    assert!(map.verify_fingerprint(&path, &fingerprint).unwrap());
+
+    let without_mtime = Fingerprint::new(fingerprint.content_hash(), fingerprint.len(), None);
+    assert!(map.verify_fingerprint(&path, &without_mtime).unwrap());
+
+    std::fs::write(&path, "abd").unwrap();
+    assert!(!map.verify_fingerprint(&path, &without_mtime).unwrap());
+    std::fs::write(&path, "abcd").unwrap();
+    assert!(!map.verify_fingerprint(&path, &fingerprint).unwrap());
+
+    assert!(matches!(
+        map.fingerprint_file(root.join("missing.txt")),
+        Err(LoadError::Read { .. }),
+    ));
+}
+
+#[test]
+fn global_offsets() {
+    let mut map = SourceMap::new();
+    let a = map.insert(Origin::from_named("a"), "abc".into()).try_into_inserted().unwrap();
+    let b = map.insert(Origin::from_named("b"), "".into()).try_into_inserted().unwrap();
+    let c = map.insert(Origin::from_named("c"), "def".into()).try_into_inserted().unwrap();
+
+    let positions = [
+        (map.input(a).offset(), 0),
+        (map.input(a).end().offset(), 3),
+        (map.input(b).offset(), 4),
+        (map.input(c).skip(1).offset(), 6),
+        (map.input(c).end().offset(), 8),
+    ];
+    for (offset, position) in positions {
+        let global = map.global_offset(offset).unwrap();
+        assert_eq!(global, GlobalOffset::from_u32(position));
+        assert_eq!(map.resolve_global_offset(global), Some(offset));
+    }
+    assert_eq!(map.resolve_global_offset(GlobalOffset::from_u32(9)), None);
+
+    let span = map.input(c).skip(1).offset().span(map.input(c).end().offset());
+    let global = map.global_span(span).unwrap();
+    assert_eq!(global, GlobalSpan::new(GlobalOffset::from_u32(6), 2));
+    assert_eq!(global.end(), GlobalOffset::from_u32(8));
+    assert_eq!(map.resolve_global_span(global), Some(span));
+    assert_eq!(map.resolve_global_span(GlobalSpan::new(GlobalOffset::from_u32(2), 3)), None);
+    assert_eq!(std::mem::size_of::<GlobalSpan>(), 8);
+}
+
+#[test]
+fn debug_and_dump() {
+    let (mut map, index) = test_map("abc");
+    map.insert(Origin::from_file("other"), "".into());
+    map.set_category(index, SourceCategory::Vendored);
+
+    let debug = format!("{map:?}");
+    assert!(debug.starts_with("SourceMap { id: "), "{debug}");
+    assert!(debug.contains(
+        r#"entries: [SourceData { origin: Named("test"), len: 3, category: Vendored }, "#,
+    ), "{debug}");
+
+    let dump = map.dump().to_string();
+    let (header, entries) = dump.split_once('\n').unwrap();
+    assert!(header.ends_with(" with 2 entries"), "{header}");
+    assert_eq!(entries, normalize("
+        |  0: Named(\"test\"), 3 bytes, hash e71fa2190541574b, Vendored
+        |  1: File(\"other\"), 0 bytes, hash cbf29ce484222325, User
+    "));
+}
+
+#[test]
+fn forks() {
+    let (mut map, index) = test_map("abc");
+    let mut fork = map.fork();
+    assert!(fork.contains(index));
+    assert_eq!(fork.content(index), "abc");
+    assert_eq!(fork.origin(index), &Origin::from_named("test"));
+    let input = map.input(index);
+    assert_eq!(fork.span_str(input.skip(1).offset().span(input.end().offset())), "bc");
+
+    let scratch = fork.insert_anonymous("scratch".into());
+    assert!(! map.contains(scratch));
+    assert_eq!(fork.content(scratch), "scratch");
+    assert!(matches!(fork.insert(Origin::from_named("test"), "".into()), Insert::Previous(_)));
+
+    let later = map.insert(Origin::from_named("later"), "later".into()).try_into_inserted().unwrap();
+    assert!(! fork.contains(later));
+    assert!(fork.try_content(later).is_none());
+    assert!(map.insert(Origin::from_named("<anonymous-0>"), "".into()).try_into_inserted().is_ok());
+
+    let nested = fork.fork();
+    assert_eq!(nested.content(index), "abc");
+    assert_eq!(nested.content(scratch), "scratch");
+}
+
+#[test]
+fn readers() {
+    fn assert_send_sync<T: Send + Sync>(_: &T) {}
+
+    let (mut map, index) = test_map("abc\ndef");
+    let reader = map.freeze();
+    assert_send_sync(&reader);
+    let later = map.insert_anonymous("later".into());
+    assert!(! reader.contains(later));
+
+    let handles = (0..2).map(|_| {
+        let reader = reader.clone();
+        std::thread::spawn(move || {
+            reader.input(index).skip(5).error(Error("thread"), "here")
+                .into_context_error(&reader)
+                .render_string(&DisplayOptions::stable())
+        })
+    }).collect::<Vec<_>>();
+    for handle in handles {
+        assert_eq!(handle.join().unwrap(), normalize("
+            |error: thread
+            |--> `test`, line 2, column 2
+            |    2 | def
+            |      |  ^ here
+        "));
+    }
+}
+
+#[test]
+fn content_cache() {
+    let root = Path::new(env!("CARGO_TARGET_TMPDIR")).join("content_cache");
+    let _ = std::fs::remove_dir_all(&root);
+    std::fs::create_dir_all(&root).unwrap();
+    for (name, content) in [("a.txt", "aaaa"), ("b.txt", "bbbb"), ("c.txt", "cccc")] {
+        std::fs::write(root.join(name), content).unwrap();
+    }
+
+    let mut map = SourceMap::new().with_content_cache_limit(Some(8));
+    let a = map.load_file(root.join("a.txt")).unwrap().try_into_inserted().unwrap();
+    let b = map.load_file(root.join("b.txt")).unwrap().try_into_inserted().unwrap();
+    assert_eq!(map.cached_content_bytes(), 8);
+    let named = map.insert_anonymous("named".into());
+
+    assert_eq!(map.content(a), "aaaa");
+    let c = map.load_file(root.join("c.txt")).unwrap().try_into_inserted().unwrap();
+    assert_eq!(map.cached_content_bytes(), 8);
+    assert_eq!(map.content(named), "named");
+
+    assert_eq!(map.input(b).skip(2).content(), "bb");
+    assert_eq!(map.cached_content_bytes(), 12);
+    map.trim_content_cache();
+    assert_eq!(map.cached_content_bytes(), 8);
+    assert_eq!(map.content(c), "cccc");
+    assert_eq!(map.content(b), "bbbb");
+
+    map.trim_content_cache();
+    std::fs::write(root.join("a.txt"), "changed").unwrap();
+    assert_eq!(map.try_content(a), Some("    "));
+    assert_eq!(map.input(a).end().offset().byte(), 4);
+    assert!(matches!(
+        map.get_span_str(map.input(b).offset().span(map.input(b).end().offset())),
+        Ok("bbbb"),
+    ));
+
+    std::fs::write(root.join("a.txt"), "aaaa").unwrap();
+    assert_eq!(map.content(c), "cccc");
+    map.trim_content_cache();
+    assert_eq!(map.cached_content_bytes(), 8);
+    let reader = map.freeze();
+    assert_eq!(reader.cached_content_bytes(), 12);
+    assert_eq!(map.cached_content_bytes(), 8);
+    assert_eq!(reader.content(a), "aaaa");
+}
+
+#[test]
+fn content_cache_changed_files() {
+    let root = Path::new(env!("CARGO_TARGET_TMPDIR")).join("content_cache_changed_files");
+    let _ = std::fs::remove_dir_all(&root);
+    std::fs::create_dir_all(&root).unwrap();
+    std::fs::write(root.join("a.txt"), "a\nb").unwrap();
+    std::fs::write(root.join("b.txt"), "cd").unwrap();
+
+    let mut map = SourceMap::new().with_content_cache_limit(Some(3));
+    let a = map.load_file(root.join("a.txt")).unwrap().try_into_inserted().unwrap();
+    let b = map.load_file(root.join("b.txt")).unwrap().try_into_inserted().unwrap();
+    assert_eq!(map.content(b), "cd");
+    std::fs::write(root.join("a.txt"), "changed").unwrap();
+
+    let input = map.input(a);
+    assert_eq!(input.content(), " \n ");
+    let rendered = input.skip(2).error(Error("changed"), "here")
+        .into_context_error(&map)
+        .render_string(&DisplayOptions::stable());
+    assert!(rendered.contains("content_cache_changed_files/a.txt:2:1\n"));
+}
+
+#[test]
+fn replace_and_versions() {
+    let mut map = SourceMap::new();
+    let a = map.insert(Origin::from_named("a"), "abc".into()).try_into_inserted().unwrap();
+    let b = map.insert(Origin::from_named("b"), "def".into()).try_into_inserted().unwrap();
+    map.set_category(a, SourceCategory::Vendored);
+    assert_eq!(map.version(a), 0);
+    assert_eq!(map.input(a).end().offset().byte(), 3);
+
+    map.replace(a, "abc\ndefgh".into());
+    assert_eq!(map.version(a), 1);
+    assert_eq!(map.version(b), 0);
+    assert_eq!(map.content(a), "abc\ndefgh");
+    assert_eq!(map.category(a), SourceCategory::Vendored);
+    assert_eq!(map.origin(a), &Origin::from_named("a"));
+    assert_eq!(map.locate(map.input(a).skip(6).offset()).line().to_one_based(), 2);
+
+    let offset = map.input(b).skip(1).offset();
+    let global = map.global_offset(offset).unwrap();
+    assert_eq!(global, GlobalOffset::from_u32(5));
+    assert_eq!(map.resolve_global_offset(global), Some(offset));
+    assert_eq!(map.global_offset(map.input(a).offset()), Some(GlobalOffset::from_u32(8)));
+    assert_eq!(map.resolve_global_offset(GlobalOffset::from_u32(1)), None);
+
+    let span = map.input(b).offset().span(map.input(b).end().offset());
+    let global_span = map.global_span(span).unwrap();
+    map.replace(a, "".into());
+    assert_eq!(map.version(a), 2);
+    assert_eq!(map.global_offset(offset), Some(global));
+    assert_eq!(map.resolve_global_span(global_span), Some(span));
+    assert_eq!(map.span_str(map.resolve_global_span(global_span).unwrap()), "def");
+    assert_eq!(map.global_offset(map.input(a).offset()), Some(GlobalOffset::from_u32(18)));
+    assert_eq!(map.resolve_global_offset(GlobalOffset::from_u32(8)), None);
+}
+
+#[test]
+fn anchors() {
+    let mut map = SourceMap::new();
+    let a = map.insert(Origin::from_named("a"), "head\nbody".into()).try_into_inserted().unwrap();
+    let b = map.insert(Origin::from_named("b"), "other".into()).try_into_inserted().unwrap();
+    let header_end = map.input(a).skip(4).offset();
+    map.set_anchor(a, "header_end", header_end);
+    map.set_anchor(a, "body", map.input(a).skip(2).offset());
+    map.set_anchor(a, "body", map.input(a).skip(5).offset());
+    assert_eq!(map.anchor(a, "header_end"), Some(header_end));
+    assert_eq!(map.anchor(a, "body").unwrap().byte(), 5);
+    assert_eq!(map.anchor(b, "header_end"), None);
+    let names = map.anchors(a).map(|(name, offset)| (name, offset.byte())).collect::<Vec<_>>();
+    assert_eq!(names, [("header_end", 4), ("body", 5)]);
+
+    let origin = map.context_error_origin(map.anchor(a, "body").unwrap(), "here", None);
+    assert_eq!(origin.to_string(), normalize("
+        |--> `a`, line 2, column 1
+        | 2 | body
+        |   | ^ here
+    "));
+
+    let fork = map.fork();
+    assert_eq!(fork.anchor(a, "header_end"), Some(header_end));
+    assert_eq!(map.remove_anchor(a, "body").unwrap().byte(), 5);
+    assert_eq!(map.remove_anchor(a, "body"), None);
+    assert_eq!(fork.anchor(a, "body").unwrap().byte(), 5);
+
+    map.replace(a, "new".into());
+    assert_eq!(map.anchors(a).count(), 0);
+}
+
+#[test]
+fn search() {
+    let mut map = SourceMap::new();
+    let a = map.insert(Origin::from_named("a"), "Foo foo food FÖÖ".into()).try_into_inserted().unwrap();
+    let b = map.insert(Origin::from_named("b"), "xfoo foo".into()).try_into_inserted().unwrap();
+    let ranges = |spans: Vec<src_ctx::Span>| {
+        spans.iter().map(|span| (span.source_index(), span.start().byte(), span.end().byte())).collect::<Vec<_>>()
+    };
+
+    let options = SearchOptions::new();
+    assert_eq!(ranges(map.search(a, "foo", options)), [(a, 4, 7), (a, 8, 11)]);
+    let options = options.with_whole_word(true);
+    assert!(options.whole_word());
+    assert_eq!(ranges(map.search(a, "foo", options)), [(a, 4, 7)]);
+    let options = options.with_case_insensitive(true);
+    assert!(options.case_insensitive());
+    assert_eq!(ranges(map.search(a, "foo", options)), [(a, 0, 3), (a, 4, 7)]);
+    assert_eq!(ranges(map.search(a, "föö", options)), [(a, 13, 18)]);
+    assert_eq!(ranges(map.search_all("foo", options)), [(a, 0, 3), (a, 4, 7), (b, 5, 8)]);
+    assert!(map.search(a, "", options).is_empty());
+}
+
+#[test]
+fn extract() {
+    let mut map = SourceMap::new();
+    let a = map.insert(Origin::from_named("a"), "abc".into()).try_into_inserted().unwrap();
+    let b = map.insert(Origin::from_named("b"), "def".into()).try_into_inserted().unwrap();
+    let c = map.insert(Origin::from_named("c"), "ghi".into()).try_into_inserted().unwrap();
+    map.set_category(c, SourceCategory::Vendored);
+
+    let (extracted, translation) = map.extract([c, a, c]);
+    assert_eq!(extracted.len(), 2);
+    assert_eq!(translation.len(), 2);
+    assert!(!extracted.contains(a));
+    assert_eq!(translation.index(b), None);
+    let new_c = translation.index(c).unwrap();
+    assert_eq!(extracted.content(new_c), "ghi");
+    assert_eq!(extracted.category(new_c), SourceCategory::Vendored);
+    assert_eq!(extracted.origin_index(&Origin::from_named("a")), translation.index(a));
+
+    let offset = translation.offset(map.input(a).skip(1).offset()).unwrap();
+    assert_eq!(extracted.global_offset(offset), Some(GlobalOffset::from_u32(5)));
+    let span = map.input(c).offset().span(map.input(c).skip(2).offset());
+    assert_eq!(extracted.span_str(translation.span(span).unwrap()), "gh");
+    assert_eq!(translation.offset(map.input(b).offset()), None);
+}
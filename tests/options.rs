@@ -0,0 +1,455 @@
+use src_ctx::{
+    CategoryDisplay, ColorChoice, ContextError, DiagnosticTag, DisplayOptions, DisplayStrings, Origin,
+    SourceCategory, SourceMap, Verbosity, display_checkstyle, display_short, normalize,
+};
+use test_util::{Error, ErrorChain, test_map, test_map_file};
+
+
+mod test_util;
+
+const GERMAN: DisplayStrings = DisplayStrings::ENGLISH
+    .with_error("Fehler")
+    .with_cause("Ursache")
+    .with_at("bei")
+    .with_in_named("in")
+    .with_generated("generierter Code von")
+    .with_and("und")
+    .with_offset("Position")
+    .with_line("Zeile")
+    .with_column("Spalte")
+    .with_to("bis")
+    .with_included_from("eingebunden von")
+    .with_hint("Hinweis")
+    .with_bytes("Bytes")
+    .with_chars("Zeichen");
+
+#[test]
+fn localized_strings() {
+    let options = DisplayOptions::new().with_strings(GERMAN);
+    assert_eq!(options.strings(), &GERMAN);
+    assert_eq!(DisplayOptions::new().strings(), &DisplayStrings::ENGLISH);
+
+    let (map, index) = test_map("abc\ndef\nghi");
+    let input = map.input(index);
+
+    let error = input.skip(6).error(ErrorChain("test-chain", Error("test-error")), "test-note")
+        .into_context_error(&map);
+    assert_eq!(
+        &format!("{}", error.display_inline_with_options(&options)),
+        "test-chain in `test`, Zeile 2, Spalte 3",
+    );
+    assert_eq!(&format!("{}", error.display_with_options(&options)), &normalize("
+        |Fehler: test-chain
+        |Ursache: test-error
+        |--> `test`, Zeile 2, Spalte 3
+        | 2 | def
+        |   |   ^ test-note
+    "));
+
+    let (map, index) = test_map_file("abc\ndef\nghi");
+    let input = map.input(index);
+    let error = ContextError::with_origins(Error("test-error"), [
+        map.context_error_origin(input.offset(), "a", None),
+        map.context_error_origin(input.skip(4).offset(), "b", None),
+    ]);
+    assert_eq!(
+        &format!("{}", error.display_inline_with_options(&options)),
+        "test-error bei test:1:1 und bei test:2:1",
+    );
+}
+
+#[test]
+fn verbosity() {
+    let (map, index) = test_map_file("abc\ndef\nghi");
+    let input = map.input(index);
+    let error = input.skip(6).error(ErrorChain("test-chain", Error("test-error")), "test-note")
+        .into_context_error(&map);
+
+    let options = DisplayOptions::new().with_verbosity(Verbosity::Short);
+    assert_eq!(options.verbosity(), Verbosity::Short);
+    assert_eq!(
+        &format!("{}", error.display_with_options(&options)),
+        "test:2:3: error: test-chain\n",
+    );
+
+    let options = DisplayOptions::new().with_verbosity(Verbosity::Medium);
+    assert_eq!(&format!("{}", error.display_with_options(&options)), &normalize("
+        |error: test-chain
+        |--> test:2:3
+        | 2 | def
+        |   |   ^ test-note
+    "));
+
+    let options = DisplayOptions::new().with_verbosity(Verbosity::Full);
+    assert_eq!(
+        format!("{}", error.display_with_options(&options)),
+        format!("{}", error.display_with_context()),
+    );
+
+    let error = ContextError::with_origins(Error("test-error"), []);
+    let options = DisplayOptions::new().with_verbosity(Verbosity::Short);
+    assert_eq!(&format!("{}", error.display_with_options(&options)), "error: test-error\n");
+}
+
+#[test]
+fn category_display() {
+    let mut map = SourceMap::new();
+    let user = map.insert(Origin::from_file("user"), "abc".into()).try_into_inserted().unwrap();
+    let vendored = map.insert(Origin::from_file("vendored"), "def".into())
+        .try_into_inserted().unwrap();
+    assert_eq!(map.category(vendored), SourceCategory::User);
+    map.set_category(vendored, SourceCategory::Vendored);
+    assert_eq!(map.category(vendored), SourceCategory::Vendored);
+
+    let error = ContextError::with_origins(Error("test-error"), [
+        map.context_error_origin(map.input(user).skip(1).offset(), "a", None),
+        map.context_error_origin(map.input(vendored).skip(1).offset(), "b", None),
+    ]);
+    assert_eq!(error.error_origins()[1].category(), SourceCategory::Vendored);
+
+    let options = DisplayOptions::new()
+        .with_category_display(SourceCategory::Vendored, CategoryDisplay::Location);
+    assert_eq!(options.category_display(SourceCategory::User), CategoryDisplay::Full);
+    assert_eq!(&format!("{}", error.display_with_options(&options)), &normalize("
+        |error: test-error
+        |--> user:1:2
+        | 1 | abc
+        |   |  ^ a
+        |--> vendored:1:2
+    "));
+
+    let options = DisplayOptions::new()
+        .with_category_display(SourceCategory::Vendored, CategoryDisplay::Hidden);
+    assert_eq!(&format!("{}", error.display_with_options(&options)), &normalize("
+        |error: test-error
+        |--> user:1:2
+        | 1 | abc
+        |   |  ^ a
+    "));
+    assert_eq!(
+        &format!("{}", error.display_inline_with_options(&options)),
+        "test-error at user:1:2",
+    );
+}
+
+#[test]
+fn path_display() {
+    let mut map = SourceMap::new();
+    let index = map.insert(Origin::from_file("C:\\dir\\file.ext"), "abc".into())
+        .try_into_inserted().unwrap();
+    let error = map.input(index).skip(1).error(Error("test-error"), "test-note")
+        .into_context_error(&map);
+
+    let options = DisplayOptions::new().with_forward_slashes(true);
+    assert!(options.forward_slashes());
+    assert_eq!(
+        &format!("{}", error.display_inline_with_options(&options)),
+        "test-error at C:/dir/file.ext:1:2",
+    );
+
+    let options = options.with_lowercase_drive_letters(true);
+    assert!(options.lowercase_drive_letters());
+    assert_eq!(&format!("{}", error.display_with_options(&options)), &normalize("
+        |error: test-error
+        |--> c:/dir/file.ext:1:2
+        | 1 | abc
+        |   |  ^ test-note
+    "));
+}
+
+#[test]
+fn colors() {
+    let (map, index) = test_map_file("abc\ndef");
+    let input = map.input(index);
+    let error = input.skip(5).error(Error("test-error"), "test-note")
+        .with_context(input.offset())
+        .into_context_error(&map);
+
+    let options = DisplayOptions::new().with_colors(true);
+    assert!(options.colors());
+    assert!(!DisplayOptions::new().colors());
+    assert_eq!(
+        format!("{}", error.display_with_options(&options)),
+        [
+            "\x1b[1;31merror\x1b[0m: test-error\n",
+            "\x1b[1;34m-->\x1b[0m test:2:2\n",
+            "\x1b[1;34m 1 |\x1b[0m abc\n",
+            "\x1b[1;34m 2 |\x1b[0m def\n",
+            "\x1b[1;34m   |\x1b[0m  \x1b[1;31m^ test-note\x1b[0m\n",
+        ].concat(),
+    );
+}
+
+#[test]
+fn diagnostic_tags() {
+    let (map, index) = test_map_file("let abc = 23;");
+    let origin = map.context_error_origin(map.input(index).skip(4).offset(), "unused", None)
+        .with_highlight_len(3)
+        .with_tag(DiagnosticTag::Unnecessary)
+        .with_tag(DiagnosticTag::Unnecessary);
+    assert_eq!(origin.tags(), [DiagnosticTag::Unnecessary]);
+    let error = ContextError::with_origins(Error("test-error"), [origin.clone()]);
+    assert_eq!(format!("{}", error.display_with_context()), normalize("
+        |error: test-error
+        |--> test:1:5
+        | 1 | let abc = 23;
+        |   |     ^^^ unused
+    "));
+
+    let options = DisplayOptions::new().with_colors(true);
+    assert_eq!(
+        format!("{}", error.display_with_options(&options)),
+        [
+            "\x1b[1;31merror\x1b[0m: test-error\n",
+            "\x1b[1;34m-->\x1b[0m test:1:5\n",
+            "\x1b[1;34m 1 |\x1b[0m let \x1b[2mabc\x1b[0m = 23;\n",
+            "\x1b[1;34m   |\x1b[0m     \x1b[1;31m^^^ unused\x1b[0m\n",
+        ].concat(),
+    );
+
+    let error = ContextError::with_origins(Error("test-error"), [
+        origin.with_tag(DiagnosticTag::Deprecated),
+    ]);
+    assert!(format!("{}", error.display_with_options(&options))
+        .contains(" let \x1b[9m\x1b[2mabc\x1b[0m\x1b[0m = 23;\n"));
+}
+
+#[test]
+fn color_choice() {
+    let file = std::fs::File::open("Cargo.toml").unwrap();
+    assert!(ColorChoice::Always.enabled_for(&file));
+    assert!(!ColorChoice::Never.enabled_for(&file));
+    assert_eq!(ColorChoice::default(), ColorChoice::Never);
+
+    std::env::remove_var("NO_COLOR");
+    std::env::remove_var("CLICOLOR_FORCE");
+    assert!(!ColorChoice::Auto.enabled_for(&file));
+    std::env::set_var("CLICOLOR_FORCE", "1");
+    assert!(ColorChoice::Auto.enabled_for(&file));
+    assert!(DisplayOptions::new().with_color_choice(ColorChoice::Auto, &file).colors());
+    std::env::set_var("NO_COLOR", "1");
+    assert!(!ColorChoice::Auto.enabled_for(&file));
+    std::env::set_var("NO_COLOR", "");
+    assert!(ColorChoice::Auto.enabled_for(&file));
+    std::env::set_var("CLICOLOR_FORCE", "0");
+    assert!(!ColorChoice::Auto.enabled_for(&file));
+    std::env::remove_var("NO_COLOR");
+    std::env::remove_var("CLICOLOR_FORCE");
+}
+
+#[test]
+fn stable_options() {
+    let options = DisplayOptions::stable();
+    assert!(options.forward_slashes());
+    assert!(options.lowercase_drive_letters());
+    assert!(!options.colors());
+    assert_eq!(options.gutter_width(), Some(4));
+    assert_eq!(options.path_base(), std::env::current_dir().ok().as_deref());
+
+    let path = std::env::current_dir().unwrap().join("src").join("test.ext");
+    let mut map = SourceMap::new();
+    let index = map.insert(Origin::from_file(path), "a\n".repeat(12).into())
+        .try_into_inserted().unwrap();
+    for (skip, expected) in [(16, normalize("
+        |error: test-error
+        |--> src/test.ext:9:1
+        |    9 | a
+        |      | ^ test-note
+    ")), (18, normalize("
+        |error: test-error
+        |--> src/test.ext:10:1
+        |   10 | a
+        |      | ^ test-note
+    "))] {
+        let error = map.input(index).skip(skip).error(Error("test-error"), "test-note")
+            .into_context_error(&map);
+        assert_eq!(error.render_string(&options), expected);
+    }
+}
+
+#[test]
+fn gutter_width() {
+    let (map, index) = test_map_file("abc\ndef");
+    let error = map.input(index).skip(6).error(Error("test-error"), "test-note")
+        .into_context_error(&map);
+    let options = DisplayOptions::new().with_gutter_width(Some(3));
+    assert_eq!(options.gutter_width(), Some(3));
+
+    assert_eq!(error.display_with_options(&options).to_string(), normalize("
+        |error: test-error
+        |--> test:2:3
+        |   2 | def
+        |     |   ^ test-note
+    "));
+}
+
+#[test]
+fn zero_based_columns() {
+    let (map, index) = test_map_file("abc\ndef");
+    let error = map.input(index).skip(6).error(Error("test-error"), "test-note")
+        .into_context_error(&map);
+    let options = DisplayOptions::new().with_zero_based_columns(true);
+    assert!(options.zero_based_columns());
+
+    assert_eq!(error.display_with_options(&options).to_string(), normalize("
+        |error: test-error
+        |--> test:2:2
+        | 2 | def
+        |   |   ^ test-note
+    "));
+    assert_eq!(
+        display_short(std::slice::from_ref(&error), &options).to_string(),
+        "test:2:2: error: test-error\n",
+    );
+    assert!(display_checkstyle(&[error], &options).to_string()
+        .contains(r#"line="2" column="2""#));
+
+    let (map, index) = test_map("abc");
+    let error = map.input(index).skip(1).error(Error("test-error"), "test-note")
+        .into_context_error(&map);
+    assert!(error.display_with_options(&options).to_string().contains("line 1, column 1\n"));
+}
+
+#[test]
+fn location_ranges() {
+    let (map, index) = test_map_file("abc\nlet value = 23;");
+    let error = map.input(index).skip(8).error(Error("test-error"), "test-note")
+        .with_byte_len(5)
+        .into_context_error(&map);
+    let options = DisplayOptions::new().with_location_ranges(true);
+    assert!(options.location_ranges());
+    assert!(!DisplayOptions::new().location_ranges());
+
+    assert_eq!(
+        error.display_inline_with_options(&options).to_string(),
+        "test-error at test:2:5-2:10",
+    );
+    assert_eq!(error.display_with_options(&options).to_string(), normalize("
+        |error: test-error
+        |--> test:2:5-2:10
+        | 2 | let value = 23;
+        |   |     ^^^^^ test-note
+    "));
+    let zero_based = options.clone().with_zero_based_columns(true);
+    assert_eq!(
+        error.display_inline_with_options(&zero_based).to_string(),
+        "test-error at test:2:4-2:9",
+    );
+    assert_eq!(error.to_string(), "test-error at test:2:5");
+
+    let (map, index) = test_map("let value = 23;");
+    let error = map.input(index).skip(4).error(Error("test-error"), "test-note")
+        .with_byte_len(5)
+        .into_context_error(&map);
+    assert_eq!(
+        error.display_inline_with_options(&options).to_string(),
+        "test-error in `test`, line 1, column 5 to line 1, column 10",
+    );
+
+    let error = map.input(index).skip(4).error(Error("test-error"), "test-note")
+        .into_context_error(&map);
+    assert_eq!(
+        error.display_inline_with_options(&options).to_string(),
+        "test-error in `test`, line 1, column 5",
+    );
+}
+
+#[test]
+fn cause_chains() {
+    #[derive(Debug)]
+    struct Chain(&'static str, Option<Box<Chain>>);
+
+    impl std::error::Error for Chain {
+        fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+            self.1.as_deref().map(|source| source as _)
+        }
+    }
+
+    impl std::fmt::Display for Chain {
+        fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+            self.0.fmt(f)
+        }
+    }
+
+    let chain = ["outer", "outer", "middle", "middle", "inner"].into_iter().rev()
+        .fold(None, |source, message| Some(Box::new(Chain(message, source))))
+        .unwrap();
+    let error = ContextError::with_origins(*chain, []);
+
+    let options = DisplayOptions::new().with_cause_deduplication(true);
+    assert!(options.cause_deduplication());
+    assert_eq!(error.display_with_options(&options).to_string(), normalize("
+        |error: outer
+        |cause: middle
+        |cause: inner
+    "));
+
+    let options = options.with_max_cause_depth(Some(1));
+    assert_eq!(options.max_cause_depth(), Some(1));
+    assert_eq!(error.display_with_options(&options).to_string(), normalize("
+        |error: outer
+        |cause: middle
+    "));
+
+    let options = DisplayOptions::new().with_max_cause_depth(Some(2));
+    assert_eq!(error.display_with_options(&options).to_string(), normalize("
+        |error: outer
+        |cause: outer
+        |cause: middle
+    "));
+
+    let options = DisplayOptions::new().with_max_cause_depth(Some(0));
+    assert_eq!(error.display_with_options(&options).to_string(), "error: outer\n");
+}
+
+#[test]
+fn wrap_width() {
+    let (map, index) = test_map_file("abc\ndef");
+    let error = map.input(index).skip(5).error(
+        ErrorChain("the outer error message is long", Error("short")),
+        "the note is also rather long",
+    ).into_context_error(&map);
+    let options = DisplayOptions::new().with_wrap_width(Some(20));
+    assert_eq!(options.wrap_width(), Some(20));
+
+    assert_eq!(error.display_with_options(&options).to_string(), normalize("
+        |error: the outer
+        |       error message
+        |       is long
+        |cause: short
+        |--> test:2:2
+        | 2 | def
+        |   |  ^ the note is
+        |   |    also rather
+        |   |    long
+    "));
+}
+
+#[test]
+fn column_ruler_and_highlight_widths() {
+    let (map, index) = test_map_file("abc\nrecord  ÄÖÜ 0012");
+    let error = map.input(index).skip(12).error(Error("test-error"), "test-note")
+        .with_byte_len(6)
+        .into_context_error(&map);
+    let options = DisplayOptions::new().with_column_ruler(true).with_highlight_widths(true);
+    assert!(options.column_ruler());
+    assert!(options.highlight_widths());
+    assert!(!DisplayOptions::new().column_ruler());
+    assert!(!DisplayOptions::new().highlight_widths());
+
+    assert_eq!(error.display_with_options(&options).to_string(), normalize("
+        |error: test-error
+        |--> test:2:9
+        |   | ....+....1....+.
+        | 2 | record  ÄÖÜ 0012
+        |   |         ^^^ test-note (6 bytes, 3 chars)
+    "));
+    let options = options.with_strings(GERMAN).with_zero_based_columns(true);
+    assert_eq!(error.display_with_options(&options).to_string(), normalize("
+        |Fehler: test-error
+        |--> test:2:8
+        |   | .....+....1....+
+        | 2 | record  ÄÖÜ 0012
+        |   |         ^^^ test-note (6 Bytes, 3 Zeichen)
+    "));
+}
@@ -0,0 +1,35 @@
+#![cfg(feature = "pest")]
+
+use pest::error::{Error as PestError, ErrorVariant};
+use pest::Position;
+use src_ctx::{Offset, Span, normalize};
+use test_util::test_map_file;
+
+
+mod test_util;
+
+#[test]
+fn pest_conversions() {
+    let (map, index) = test_map_file("abc def");
+    let content = map.content(index);
+    let input = map.input(index);
+
+    let start = Position::new(content, 4).unwrap();
+    assert_eq!(Offset::from_pest(index, start), input.skip(4).offset());
+    let span = start.span(&Position::new(content, 7).unwrap());
+    assert_eq!(map.span_str(Span::from_pest(index, span)), "def");
+
+    let error = PestError::<()>::new_from_span(
+        ErrorVariant::CustomError { message: "unexpected word".into() },
+        span,
+    );
+    let error = map.pest_source_error(index, error);
+    assert_eq!(error.offset(), input.skip(4).offset());
+    let error = error.map(|error| error.variant.message().into_owned());
+    assert_eq!(&format!("{}", error.into_context_error(&map).display_with_outer_context()), &normalize("
+        |error: unexpected word
+        |--> test:1:5
+        | 1 | abc def
+        |   |     ^ invalid syntax
+    "));
+}
@@ -0,0 +1,143 @@
+use src_ctx::{
+    ContextError, DisplayOptions, Line, LinePosition, Origin, PositionEncoding, Preprocessed,
+    SourceMap, display_annotated_source, normalize,
+};
+use test_util::Error;
+
+
+mod test_util;
+
+#[test]
+fn preprocessed_positions() {
+    let mut preprocessed = Preprocessed::new();
+    preprocessed.keep(0, "ab");
+    preprocessed.insert(2, "INC");
+    preprocessed.keep(10, "cd");
+    assert_eq!(preprocessed.content(), "abINCcd");
+    let original = (0..=7).map(|byte| preprocessed.original_byte(byte)).collect::<Vec<_>>();
+    assert_eq!(original, [0, 1, 2, 2, 2, 10, 11, 12]);
+
+    assert_eq!(Preprocessed::strip_shebang("abc"), None);
+    assert_eq!(Preprocessed::strip_shebang("#!/bin/sh\nabc").unwrap().content(), "\nabc");
+    assert_eq!(Preprocessed::strip_carriage_returns("abc"), None);
+    let stripped = Preprocessed::strip_carriage_returns("ab\r\ncd\r\n").unwrap();
+    assert_eq!(stripped.content(), "ab\ncd\n");
+    assert_eq!(stripped.original_byte(4), 5);
+
+    let original = (0..=8).map(|byte| stripped.preprocessed_byte(byte)).collect::<Vec<_>>();
+    assert_eq!(original, [0, 1, 2, 2, 3, 4, 5, 5, 6]);
+    let shebang = Preprocessed::strip_shebang("#!/bin/sh\nabc").unwrap();
+    assert_eq!(shebang.preprocessed_byte(3), 0);
+    assert_eq!(shebang.preprocessed_byte(10), 1);
+}
+
+#[test]
+fn preprocessor() {
+    let mut map = SourceMap::new().with_preprocessor(|origin, content| match origin {
+        Origin::File(_) => Preprocessed::strip_shebang(content),
+        _ => None,
+    });
+    let index = map.insert(Origin::from_file("test"), "#!/bin/x\nab\ncd".into()).try_into_inserted().unwrap();
+    let other = map.insert(Origin::from_named("other"), "#!/bin/x".into()).try_into_inserted().unwrap();
+    assert_eq!(map.content(index), "\nab\ncd");
+    assert_eq!(map.original_content(index), "#!/bin/x\nab\ncd");
+    assert_eq!(map.content(other), "#!/bin/x");
+    assert_eq!(map.original_content(other), "#!/bin/x");
+
+    let input = map.input(index).skip(5);
+    assert_eq!(map.original_byte(input.offset()), 13);
+    assert_eq!(map.locate(input.offset()).line().to_one_based(), 3);
+    let error = input.error(Error("test-error"), "here").into_context_error(&map);
+    assert_eq!(error.to_string(), "test-error at test:3:2");
+    assert_eq!(error.display_with_context().to_string(), normalize("
+        |error: test-error
+        |--> test:3:2
+        | 3 | cd
+        |   |  ^ here
+    "));
+}
+
+#[test]
+fn preprocessed_locations() {
+    let mut map = SourceMap::new().with_preprocessor(|_, content| {
+        Preprocessed::strip_carriage_returns(content)
+    });
+    let content = (1..=50).map(|line| format!("line{line:02}\r\n")).collect::<String>();
+    let index = map.insert(Origin::from_named("test"), content.into()).try_into_inserted().unwrap();
+    for line in 1..=50 {
+        let input = map.input(index).skip((line - 1) * 7 + 1);
+        let location = map.locate(input.offset());
+        assert_eq!(location.line().to_one_based(), line);
+        assert_eq!(location.column().to_one_based(), 2);
+    }
+    let input = map.input(index).skip(343);
+    let error = input.error(Error("test-error"), "here").into_context_error(&map);
+    assert_eq!(error.to_string(), "test-error in `test`, line 50, column 1");
+}
+
+#[test]
+fn preprocessed_line_origins() {
+    let mut map = SourceMap::new().with_preprocessor(|_, content| {
+        let (line, rest) = content.split_once("@inc")?;
+        let mut preprocessed = Preprocessed::new();
+        preprocessed.keep(0, line);
+        preprocessed.insert(line.len(), "included content");
+        preprocessed.keep(line.len() + 4, rest);
+        Some(preprocessed)
+    });
+    let index = map.insert(Origin::from_file("test"), "ab\n@inc\ncd".into())
+        .try_into_inserted().unwrap();
+    assert_eq!(map.content(index), "ab\nincluded content\ncd");
+
+    let line = map.input(index).skip(8).offset();
+    let error = ContextError::with_origins(Error("test-error"), [
+        map.context_error_line_origin(line, "in this line"),
+    ]);
+    assert_eq!(error.error_origins()[0].highlight_len(), 4);
+    assert_eq!(error.display_with_context().to_string(), normalize("
+        |error: test-error
+        |--> test:2
+        | 2 | @inc
+        |   | ^^^^ in this line
+    "));
+}
+
+#[test]
+fn preprocessed_line_positions() {
+    let mut map = SourceMap::new().with_preprocessor(|_, content| {
+        let rest = content.strip_prefix("inc\n")?;
+        let mut preprocessed = Preprocessed::new();
+        preprocessed.insert(0, "one\ntwo\n");
+        preprocessed.keep(4, rest);
+        Some(preprocessed)
+    });
+    let index = map.insert(Origin::from_named("test"), "inc\nab\ncd".into()).try_into_inserted().unwrap();
+    assert_eq!(map.content(index), "one\ntwo\nab\ncd");
+
+    let line = |number| map.line_offset(index, Line::from_one_based(number).unwrap());
+    assert_eq!(line(1).map(|offset| offset.byte()), Some(8));
+    assert_eq!(line(2).map(|offset| offset.byte()), Some(8));
+    assert_eq!(line(3).map(|offset| offset.byte()), Some(11));
+    assert_eq!(line(4), None);
+    for number in 2..=3 {
+        assert_eq!(map.locate(line(number).unwrap()).line().to_one_based(), number);
+    }
+
+    let offset = map.input(index).skip(12).offset();
+    let position = map.line_position(offset, PositionEncoding::Utf8);
+    assert_eq!(position, LinePosition { line: 2, character: 1 });
+    assert_eq!(map.line_position_offset(index, position, PositionEncoding::Utf8), Some(offset));
+
+    let error = map.input(index).skip(11).error(Error("test-error"), "here")
+        .into_context_error(&map);
+    let errors = [error];
+    let options = DisplayOptions::new();
+    assert_eq!(display_annotated_source(&map, index, &errors, &options).to_string(), normalize("
+        |--> test
+        | 1 | inc
+        | 2 | ab
+        | 3 | cd
+        |   | ^ here
+        |   = error: test-error
+    "));
+}
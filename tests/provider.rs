@@ -0,0 +1,62 @@
+use src_ctx::{
+    DisplayOptions, Origin, ProviderId, SourceIndex, SourceProvider, display_annotated_source,
+    normalize,
+};
+use test_util::Error;
+
+
+mod test_util;
+
+struct Files {
+    id: ProviderId,
+    files: Vec<(Origin, String)>,
+}
+
+impl SourceProvider for Files {
+    fn origin(&self, idx: SourceIndex) -> &Origin {
+        &self.files[self.id.index_of(idx).unwrap() as usize].0
+    }
+
+    fn content(&self, idx: SourceIndex) -> &str {
+        &self.files[self.id.index_of(idx).unwrap() as usize].1
+    }
+}
+
+#[test]
+fn custom_providers() {
+    let files = Files {
+        id: ProviderId::new(),
+        files: vec![
+            (Origin::from_file("a.txt"), "abc\ndef\n".into()),
+            (Origin::from_file("b.txt"), "ghi".into()),
+        ],
+    };
+    let a = files.id.source_index(0);
+    assert_eq!(files.id.index_of(a), Some(0));
+    assert_eq!(ProviderId::new().index_of(a), None);
+    assert_eq!(&*files.line_starts(a), &[0, 4, 8]);
+
+    let input = files.input(a).skip(5);
+    assert_eq!(input.content(), "ef\n");
+    let error = input.error(Error("test-error"), "here").into_context_error(&files);
+    assert_eq!(error.display_with_context().to_string(), normalize("
+        |error: test-error
+        |--> a.txt:2:2
+        | 2 | def
+        |   |  ^ here
+    "));
+
+    let options = DisplayOptions::new();
+    assert_eq!(display_annotated_source(&files, a, &[error], &options).to_string(), normalize("
+        |--> a.txt
+        | 1 | abc
+        | 2 | def
+        |   |  ^ here
+        |   = error: test-error
+    "));
+
+    let b = files.id.source_index(1);
+    let origin = files.context_error_source_origin(b, "file is empty");
+    assert_eq!(origin.origin(), &Origin::from_file("b.txt"));
+    assert_eq!(origin.location(), None);
+}
@@ -0,0 +1,27 @@
+#![cfg(feature = "rayon")]
+
+use src_ctx::{ContextError, DisplayOptions};
+use test_util::{Error, test_map};
+
+
+mod test_util;
+
+#[test]
+fn parallel_rendering() {
+    let (map, index) = test_map("abc\ndef\nghi");
+    let errors = (0..100)
+        .map(|byte| map.input(index).skip(byte % 11).error(Error("test-error"), "here"))
+        .map(|error| error.into_context_error(&map))
+        .collect::<Vec<_>>();
+    let options = DisplayOptions::new();
+
+    let rendered = ContextError::render_each_parallel(&errors, &options);
+    assert_eq!(rendered.len(), errors.len());
+    for (error, rendered) in errors.iter().zip(&rendered) {
+        assert_eq!(&error.render_string(&options), rendered);
+    }
+    assert_eq!(
+        ContextError::render_batch_string_parallel(&errors, &options),
+        ContextError::render_batch_string(&errors, &options),
+    );
+}
@@ -0,0 +1,58 @@
+use src_ctx::{ContextError, DisplayOptions, FriendlyReport, normalize};
+use test_util::{Error, test_map_file};
+
+
+mod test_util;
+
+#[test]
+fn friendly_reports() {
+    let (map, index) = test_map_file("let x = 2;\nlet y = cont;");
+    let error = map.input(index).skip(19)
+        .error(Error("I cannot find a `cont` variable."), "used here")
+        .with_byte_len(4)
+        .into_context_error(&map);
+    let report = FriendlyReport::new(&error, "unknown name")
+        .with_paragraph("These names seem close though:")
+        .with_hint("Read the documentation to learn how to declare variables.");
+    assert_eq!(report.title(), "unknown name");
+    assert_eq!(report.paragraphs(), ["These names seem close though:"]);
+    assert_eq!(report.hint(), Some("Read the documentation to learn how to declare variables."));
+
+    assert_eq!(report.to_string(), normalize("
+        |-- UNKNOWN NAME ----------------------------------------------------------- test
+        |
+        |I cannot find a `cont` variable.
+        |
+        |These names seem close though:
+        |
+        |--> test:2:9
+        | 2 | let y = cont;
+        |   |         ^^^^ used here
+        |
+        |hint: Read the documentation to learn how to declare variables.
+    "));
+
+    let options = DisplayOptions::new().with_wrap_width(Some(40));
+    assert_eq!(report.display_with_options(&options).to_string(), normalize("
+        |-- UNKNOWN NAME ------------------- test
+        |
+        |I cannot find a `cont` variable.
+        |
+        |These names seem close though:
+        |
+        |--> test:2:9
+        | 2 | let y = cont;
+        |   |         ^^^^ used here
+        |
+        |hint: Read the documentation to learn
+        |      how to declare variables.
+    "));
+
+    let error = ContextError::with_origins(Error("Something went wrong."), []);
+    let report = FriendlyReport::new(&error, "problem");
+    assert_eq!(report.display_with_options(&options).to_string(), normalize("
+        |-- PROBLEM -----------------------------
+        |
+        |Something went wrong.
+    "));
+}
@@ -0,0 +1,83 @@
+use src_ctx::{Spanned, edit_distance, suggest_similar};
+use test_util::test_map;
+
+
+mod test_util;
+
+#[test]
+fn spanned_values() {
+    let (map, index) = test_map("abc 123");
+    let input = map.input(index);
+    let span = input.skip(4).offset().span(input.end().offset());
+
+    let mut spanned = Spanned::new(span, "123");
+    assert_eq!(spanned.span(), span);
+    assert_eq!(spanned.value(), &"123");
+    *spanned.value_mut() = "0123";
+    let spanned = spanned.map(|value| value.parse::<u32>().unwrap());
+    assert_eq!(spanned.span(), span);
+    assert_eq!(spanned.into_value(), 123);
+}
+
+#[cfg(all(feature = "serde_spanned", feature = "toml"))]
+#[test]
+fn serde_spanned_values() {
+    let (map, index) = test_map("a = 1\nb = \"value\"\n");
+    #[derive(serde::Deserialize)]
+    struct Config {
+        b: serde_spanned::Spanned<String>,
+    }
+
+    let config: Config = toml::from_str(map.content(index)).unwrap();
+    let value = Spanned::from_serde_spanned(index, config.b);
+    assert_eq!(value.value(), "value");
+    assert_eq!(map.span_str(value.span()), "\"value\"");
+}
+
+#[cfg(feature = "logos")]
+#[test]
+fn logos_tokens() {
+    use logos::Logos;
+    use src_ctx::Span;
+
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, Logos)]
+    #[logos(skip r" +")]
+    enum Token {
+        #[regex("[a-z]+")]
+        Word,
+        #[regex("[0-9]+")]
+        Number,
+    }
+
+    let (map, index) = test_map("abc 12 !");
+    let tokens: Vec<_> = map.lex_spanned::<Token>(index)
+        .map(|token| (map.span_str(token.span()), token.into_value()))
+        .collect();
+    assert_eq!(tokens, [("abc", Ok(Token::Word)), ("12", Ok(Token::Number)), ("!", Err(()))]);
+
+    let span = Span::from_logos(index, 4..6);
+    assert_eq!(map.span_str(span), "12");
+}
+
+#[test]
+fn similar_suggestions() {
+    assert_eq!(edit_distance("kitten", "sitting"), 3);
+    assert_eq!(edit_distance("", "abc"), 3);
+    assert_eq!(edit_distance("äb", "ab"), 1);
+
+    let (map, index) = test_map("value values valve vault x");
+    let mut candidates = Vec::new();
+    let mut input = map.input(index);
+    while let Some((ident, rest)) = input.take_ident() {
+        candidates.push(Spanned::new(ident.offset().span(ident.end().offset()), ident.content()));
+        input = rest.skip_char(' ').unwrap_or(rest);
+    }
+    candidates.push(candidates[2]);
+
+    let suggestions = suggest_similar("vale", candidates.iter().copied());
+    let values = suggestions.iter().map(|suggestion| *suggestion.value()).collect::<Vec<_>>();
+    assert_eq!(values, ["value", "valve"]);
+    assert_eq!(suggestions[1].span(), candidates[2].span());
+    assert!(suggest_similar("value", [candidates[0]]).is_empty());
+    assert!(suggest_similar("y", candidates.iter().copied()).iter().any(|s| *s.value() == "x"));
+}
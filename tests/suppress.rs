@@ -0,0 +1,40 @@
+use src_ctx::{Line, Suppressions};
+use test_util::{Error, test_map, test_map_file};
+
+
+mod test_util;
+
+const SOURCE: &str = "\
+# src-ctx: allow-file(deprecated)
+x = 1 # src-ctx: allow(unused, shadowed)
+let y = 23;
+let z = 42;
+# src-ctx: deny(unused)
+";
+
+#[test]
+fn suppressions() {
+    let (map, index) = test_map(SOURCE);
+    let suppressions = Suppressions::scan(&map, "# src-ctx:");
+    assert_eq!(suppressions.prefix(), "# src-ctx:");
+    let entries = suppressions.entries().iter()
+        .map(|entry| (entry.line().map(Line::to_one_based), entry.code(), entry.offset().byte()))
+        .collect::<Vec<_>>();
+    assert_eq!(entries, [(None, "deprecated", 0), (Some(3), "unused", 40), (Some(3), "shadowed", 40)]);
+
+    let input = map.input(index);
+    let error_at = |byte, code| input.skip(byte).error(Error(code), "here").into_context_error(&map);
+    let mut errors = vec![
+        error_at(76, "unused"),
+        error_at(76, "other"),
+        error_at(88, "unused"),
+        error_at(88, "deprecated"),
+    ];
+    suppressions.retain_unsuppressed(&mut errors, |error| Some(error.0));
+    let remaining = errors.iter().map(|error| error.error().0).collect::<Vec<_>>();
+    assert_eq!(remaining, ["other", "unused"]);
+
+    let (other, other_index) = test_map_file("value");
+    let error = other.input(other_index).error(Error("deprecated"), "here").into_context_error(&other);
+    assert!(!suppressions.is_suppressed(&error.error_origins()[0], "deprecated"));
+}
@@ -1,6 +1,6 @@
 #![allow(unused)]
 
-use src_ctx::{SourceMap, Origin, SourceIndex};
+use src_ctx::{SourceMap, ByteSourceMap, Origin, SourceIndex};
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
 pub struct Error(pub &'static str);
@@ -41,3 +41,10 @@ pub fn test_map_file(content: &str) -> (SourceMap, SourceIndex) {
         .try_into_inserted().unwrap();
     (map, index)
 }
+
+pub fn test_byte_map(content: &[u8]) -> (ByteSourceMap, SourceIndex) {
+    let mut map = ByteSourceMap::new();
+    let index = map.insert(Origin::from_file("test"), content.into())
+        .try_into_inserted().unwrap();
+    (map, index)
+}
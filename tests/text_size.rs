@@ -0,0 +1,27 @@
+#![cfg(feature = "text-size")]
+
+use src_ctx::{CompactSpan, Offset, Span};
+use test_util::test_map;
+use text_size::{TextRange, TextSize};
+
+
+mod test_util;
+
+#[test]
+fn text_size_conversions() {
+    let (map, index) = test_map("abcdef");
+    let input = map.input(index);
+    let offset = input.skip(2).offset();
+    let span = offset.span(input.skip(5).offset());
+
+    assert_eq!(offset.to_text_size(), Some(TextSize::from(2)));
+    assert_eq!(Offset::from_text_size(index, TextSize::from(2)), offset);
+
+    let range = span.to_text_range().unwrap();
+    assert_eq!(range, TextRange::new(2.into(), 5.into()));
+    assert_eq!(Span::from_text_range(index, range), span);
+    assert_eq!(map.span_str(Span::from_text_range(index, range)), "cde");
+
+    assert_eq!(TextRange::from(CompactSpan::new(2, 3)), range);
+    assert_eq!(CompactSpan::from(range), CompactSpan::new(2, 3));
+}